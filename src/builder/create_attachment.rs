@@ -71,16 +71,19 @@ impl CreateAttachment {
         Ok(CreateAttachment::bytes(data, filename))
     }
 
-    /// Builds an [`CreateAttachment`] by downloading attachment data from a URL.
+    /// Builds an [`CreateAttachment`] by downloading attachment data from a URL. URLs of Discord's
+    /// CDN are downloaded from the CDN of the [`Http::api_base`].
     ///
     /// # Errors
     ///
     /// [`Error::Url`] if the URL is invalid, [`Error::Http`] if downloading the data fails.
     #[cfg(feature = "http")]
     pub async fn url(http: impl AsRef<Http>, url: &str) -> Result<CreateAttachment> {
-        let url = Url::parse(url).map_err(|_| Error::Url(url.to_string()))?;
+        let http = http.as_ref();
+        let url = http.api_base().cdn_url(url);
+        let url = Url::parse(&url).map_err(|_| Error::Url(url.clone()))?;
 
-        let response = http.as_ref().client.get(url.clone()).send().await?;
+        let response = http.client.get(url.clone()).send().await?;
         let data = response.bytes().await?.to_vec();
 
         let filename = url
@@ -1,16 +1,19 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use super::{Cache, CacheUpdate};
-use crate::model::channel::{GuildChannel, Message};
+use crate::model::channel::{Channel, GuildChannel, Message, PrivateChannel};
 use crate::model::event::{
     ChannelCreateEvent,
     ChannelDeleteEvent,
     ChannelPinsUpdateEvent,
+    ChannelRecipientAddEvent,
+    ChannelRecipientRemoveEvent,
     ChannelUpdateEvent,
     GuildCreateEvent,
     GuildDeleteEvent,
     GuildEmojisUpdateEvent,
     GuildMemberAddEvent,
+    GuildMemberListUpdateEvent,
     GuildMemberRemoveEvent,
     GuildMemberUpdateEvent,
     GuildMembersChunkEvent,
@@ -23,6 +26,10 @@ use crate::model::event::{
     MessageUpdateEvent,
     PresenceUpdateEvent,
     ReadyEvent,
+    ReadySupplementalEvent,
+    RelationshipAddEvent,
+    RelationshipRemoveEvent,
+    RelationshipUpdateEvent,
     ThreadCreateEvent,
     ThreadDeleteEvent,
     ThreadUpdateEvent,
@@ -30,34 +37,67 @@ use crate::model::event::{
     VoiceChannelStatusUpdateEvent,
     VoiceStateUpdateEvent,
 };
-use crate::model::gateway::ShardInfo;
-use crate::model::guild::{Guild, GuildMemberFlags, Member, Role};
-use crate::model::id::ShardId;
-use crate::model::user::{CurrentUser, OnlineStatus};
+use crate::model::gateway::{Presence, ShardInfo};
+use crate::model::guild::{Guild, GuildMemberFlags, Member, MemberList, MemberListItem, Role};
+use crate::model::id::{MessageId, ShardId};
+use crate::model::relationship::Relationship;
+use crate::model::user::{CurrentUser, OnlineStatus, User};
 use crate::model::voice::VoiceState;
 
 impl CacheUpdate for ChannelCreateEvent {
-    type Output = GuildChannel;
+    type Output = Channel;
 
     fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
-        let old_channel = cache
-            .guilds
-            .get_mut(&self.channel.guild_id)
-            .and_then(|mut g| g.channels.insert(self.channel.id, self.channel.clone()));
+        match &self.channel {
+            Channel::Guild(channel) => {
+                let old_channel = cache
+                    .guilds
+                    .get_mut(&channel.guild_id)
+                    .and_then(|mut g| g.channels.insert(channel.id, channel.clone()));
+
+                cache.channels.insert(channel.id, channel.guild_id);
+                old_channel.map(Channel::Guild)
+            },
+            Channel::Private(channel) => {
+                insert_private_channel(cache, channel.clone()).map(Channel::Private)
+            },
+        }
+    }
+}
 
-        cache.channels.insert(self.channel.id, self.channel.guild_id);
-        old_channel
+/// Stores a presence that isn't tied to a guild, removing it if the user went offline.
+fn insert_presence(cache: &Cache, presence: Presence) {
+    if presence.status == OnlineStatus::Offline {
+        cache.presences.remove(&presence.user.id);
+    } else {
+        cache.presences.insert(presence.user.id, presence);
     }
 }
 
+/// Stores a private channel, replacing any previous state.
+fn insert_private_channel(cache: &Cache, channel: PrivateChannel) -> Option<PrivateChannel> {
+    for recipient in &channel.recipients {
+        cache.update_user_entry(recipient);
+    }
+
+    cache.private_channels.insert(channel.id, channel)
+}
+
 impl CacheUpdate for ChannelDeleteEvent {
     type Output = Vec<Message>;
 
     fn update(&mut self, cache: &Cache) -> Option<Vec<Message>> {
-        let (channel_id, guild_id) = (self.channel.id, self.channel.guild_id);
-
-        cache.channels.remove(&channel_id);
-        cache.guilds.get_mut(&guild_id).map(|mut g| g.channels.remove(&channel_id));
+        let channel_id = match &self.channel {
+            Channel::Guild(channel) => {
+                cache.channels.remove(&channel.id);
+                cache.guilds.get_mut(&channel.guild_id).map(|mut g| g.channels.remove(&channel.id));
+                channel.id
+            },
+            Channel::Private(channel) => {
+                cache.private_channels.remove(&channel.id);
+                channel.id
+            },
+        };
 
         // Remove the cached messages for the channel.
         cache.messages.remove(&channel_id).map(|(_, messages)| messages.into_values().collect())
@@ -65,15 +105,53 @@ impl CacheUpdate for ChannelDeleteEvent {
 }
 
 impl CacheUpdate for ChannelUpdateEvent {
-    type Output = GuildChannel;
+    type Output = Channel;
+
+    fn update(&mut self, cache: &Cache) -> Option<Channel> {
+        match &self.channel {
+            Channel::Guild(channel) => {
+                cache.channels.insert(channel.id, channel.guild_id);
+
+                cache
+                    .guilds
+                    .get_mut(&channel.guild_id)
+                    .and_then(|mut g| g.channels.insert(channel.id, channel.clone()))
+                    .map(Channel::Guild)
+            },
+            Channel::Private(channel) => {
+                insert_private_channel(cache, channel.clone()).map(Channel::Private)
+            },
+        }
+    }
+}
 
-    fn update(&mut self, cache: &Cache) -> Option<GuildChannel> {
-        cache.channels.insert(self.channel.id, self.channel.guild_id);
+impl CacheUpdate for ChannelRecipientAddEvent {
+    type Output = ();
 
-        cache
-            .guilds
-            .get_mut(&self.channel.guild_id)
-            .and_then(|mut g| g.channels.insert(self.channel.id, self.channel.clone()))
+    fn update(&mut self, cache: &Cache) -> Option<()> {
+        cache.update_user_entry(&self.user);
+
+        if let Some(mut channel) = cache.private_channels.get_mut(&self.channel_id) {
+            if let Some(recipient) = channel.recipients.iter_mut().find(|u| u.id == self.user.id) {
+                recipient.clone_from(&self.user);
+            } else {
+                channel.recipients.push(self.user.clone());
+            }
+        }
+
+        None
+    }
+}
+
+impl CacheUpdate for ChannelRecipientRemoveEvent {
+    type Output = ();
+
+    fn update(&mut self, cache: &Cache) -> Option<()> {
+        if let Some(mut channel) = cache.private_channels.get_mut(&self.channel_id) {
+            channel.recipients.retain(|u| u.id != self.user.id);
+        }
+
+        None
     }
 }
 
@@ -97,29 +175,35 @@ impl CacheUpdate for GuildCreateEvent {
     type Output = ();
 
     fn update(&mut self, cache: &Cache) -> Option<()> {
-        cache.unavailable_guilds.remove(&self.guild.id);
-        let mut guild = self.guild.clone();
+        insert_guild(cache, self.guild.clone());
 
-        for (user_id, member) in &mut guild.members {
-            cache.update_user_entry(&member.user);
-            if let Some(u) = cache.user(user_id) {
-                member.user = u.clone();
-            }
-        }
+        None
+    }
+}
 
-        cache.guilds.insert(self.guild.id, guild);
-        for channel_id in self.guild.channels.keys() {
-            cache.channels.insert(*channel_id, self.guild.id);
+/// Stores a full guild, along with its channels and members, replacing any previous state.
+fn insert_guild(cache: &Cache, mut guild: Guild) {
+    cache.unavailable_guilds.remove(&guild.id);
+
+    for (user_id, member) in &mut guild.members {
+        cache.update_user_entry(&member.user);
+        if let Some(u) = cache.user(user_id) {
+            member.user = u.clone();
         }
+    }
 
-        None
+    for channel_id in guild.channels.keys() {
+        cache.channels.insert(*channel_id, guild.id);
     }
+    cache.guilds.insert(guild.id, guild);
 }
 
 impl CacheUpdate for GuildDeleteEvent {
     type Output = Guild;
 
     fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
+        cache.member_lists.remove(&self.guild.id);
+
         if self.guild.unavailable {
             cache.unavailable_guilds.insert(self.guild.id, ());
             cache.guilds.remove(&self.guild.id);
@@ -241,6 +325,32 @@ impl CacheUpdate for GuildMemberUpdateEvent {
     }
 }
 
+impl CacheUpdate for GuildMemberListUpdateEvent {
+    type Output = ();
+
+    fn update(&mut self, cache: &Cache) -> Option<()> {
+        let members = self.ops.iter().flat_map(|op| op.items()).filter_map(MemberListItem::member);
+        for item in members {
+            cache.update_user_entry(&item.member.user);
+            if let Some(mut g) = cache.guilds.get_mut(&self.guild_id) {
+                g.members.insert(item.member.user.id, item.member.clone());
+            }
+        }
+
+        if !cache.member_lists.contains(&self.guild_id) {
+            cache.member_lists.insert(self.guild_id, HashMap::new());
+        }
+
+        let mut lists = cache.member_lists.get_mut(&self.guild_id)?;
+        lists
+            .entry(self.id.clone())
+            .or_insert_with(|| MemberList::new(self.guild_id, self.id.clone()))
+            .apply(self);
+
+        None
+    }
+}
+
 impl CacheUpdate for GuildMembersChunkEvent {
     type Output = ();
 
@@ -351,15 +461,19 @@ impl CacheUpdate for MessageCreateEvent {
 
         if let Some(mut guild) = guild {
             if let Some(channel) = guild.channels.get_mut(&self.message.channel_id) {
-                update_channel_last_message_id(&self.message, channel, cache);
+                update_last_message_id(&self.message, &mut channel.last_message_id, cache);
             } else {
                 // This may be a thread.
                 let thread =
                     guild.threads.iter_mut().find(|thread| thread.id == self.message.channel_id);
                 if let Some(thread) = thread {
-                    update_channel_last_message_id(&self.message, thread, cache);
+                    update_last_message_id(&self.message, &mut thread.last_message_id, cache);
                 }
             }
+        } else if self.message.guild_id.is_none() {
+            if let Some(mut channel) = cache.private_channels.get_mut(&self.message.channel_id) {
+                update_last_message_id(&self.message, &mut channel.last_message_id, cache);
+            }
         }
 
         // Add the new message to the cache and remove the oldest cached message.
@@ -387,18 +501,22 @@ impl CacheUpdate for MessageCreateEvent {
     }
 }
 
-fn update_channel_last_message_id(message: &Message, channel: &mut GuildChannel, cache: &Cache) {
-    if let Some(last_message_id) = channel.last_message_id {
-        let most_recent_timestamp = cache.message(channel.id, last_message_id).map(|m| m.timestamp);
+fn update_last_message_id(
+    message: &Message,
+    last_message_id: &mut Option<MessageId>,
+    cache: &Cache,
+) {
+    if let Some(current) = *last_message_id {
+        let most_recent_timestamp = cache.message(message.channel_id, current).map(|m| m.timestamp);
         if let Some(most_recent_timestamp) = most_recent_timestamp {
             if message.timestamp > most_recent_timestamp {
-                channel.last_message_id = Some(message.id);
+                *last_message_id = Some(message.id);
             }
         } else {
-            channel.last_message_id = Some(message.id);
+            *last_message_id = Some(message.id);
         }
     } else {
-        channel.last_message_id = Some(message.id);
+        *last_message_id = Some(message.id);
     }
 }
 
@@ -428,34 +546,37 @@ impl CacheUpdate for PresenceUpdateEvent {
             self.presence.user.update_with_user(&user);
         }
 
-        if let Some(guild_id) = self.presence.guild_id {
-            if let Some(mut guild) = cache.guilds.get_mut(&guild_id) {
-                // If the member went offline, remove them from the presence list.
-                if self.presence.status == OnlineStatus::Offline {
-                    guild.presences.remove(&self.presence.user.id);
-                } else {
-                    guild.presences.insert(self.presence.user.id, self.presence.clone());
-                }
+        let Some(guild_id) = self.presence.guild_id else {
+            insert_presence(cache, self.presence.clone());
+            return None;
+        };
 
-                // Create a partial member instance out of the presence update data.
-                if let Some(user) = self.presence.user.to_user() {
-                    guild.members.entry(self.presence.user.id).or_insert_with(|| Member {
-                        deaf: false,
-                        guild_id,
-                        joined_at: None,
-                        mute: false,
-                        nick: None,
-                        user,
-                        roles: vec![],
-                        pending: false,
-                        premium_since: None,
-                        permissions: None,
-                        avatar: None,
-                        communication_disabled_until: None,
-                        flags: GuildMemberFlags::default(),
-                        unusual_dm_activity_until: None,
-                    });
-                }
+        if let Some(mut guild) = cache.guilds.get_mut(&guild_id) {
+            // If the member went offline, remove them from the presence list.
+            if self.presence.status == OnlineStatus::Offline {
+                guild.presences.remove(&self.presence.user.id);
+            } else {
+                guild.presences.insert(self.presence.user.id, self.presence.clone());
+            }
+
+            // Create a partial member instance out of the presence update data.
+            if let Some(user) = self.presence.user.to_user() {
+                guild.members.entry(self.presence.user.id).or_insert_with(|| Member {
+                    deaf: false,
+                    guild_id,
+                    joined_at: None,
+                    mute: false,
+                    nick: None,
+                    user,
+                    roles: vec![],
+                    pending: false,
+                    premium_since: None,
+                    permissions: None,
+                    avatar: None,
+                    communication_disabled_until: None,
+                    flags: GuildMemberFlags::default(),
+                    unusual_dm_activity_until: None,
+                });
             }
         }
 
@@ -469,9 +590,15 @@ impl CacheUpdate for ReadyEvent {
     fn update(&mut self, cache: &Cache) -> Option<()> {
         let ready = self.ready.clone();
 
-        for unavailable in ready.guilds {
-            cache.guilds.remove(&unavailable.id);
-            cache.unavailable_guilds.insert(unavailable.id, ());
+        // User sessions receive full guilds in Ready rather than a burst of GuildCreates, so
+        // only guilds affected by an outage are left for a later GuildCreate to fill in.
+        for guild in ready.guilds {
+            if guild.unavailable {
+                cache.guilds.remove(&guild.id);
+                cache.unavailable_guilds.insert(guild.id, ());
+            } else {
+                insert_guild(cache, guild);
+            }
         }
 
         // We may be removed from some guilds between disconnect and ready, so handle that.
@@ -500,12 +627,121 @@ impl CacheUpdate for ReadyEvent {
             cached_shard_data.total = shard_data.total;
             cached_shard_data.connected.insert(shard_data.id);
         }
+        // Subscriptions don't carry over to a new session, so any lists would go stale.
+        cache.member_lists.clear();
+
+        cache.private_channels.clear();
+        for channel in ready.private_channels {
+            if let Channel::Private(channel) = channel {
+                insert_private_channel(cache, channel);
+            }
+        }
+
+        cache.relationships.clear();
+        for relationship in ready.relationships {
+            if let Some(user) = &relationship.user {
+                cache.update_user_entry(user);
+            }
+            cache.relationships.insert(relationship.id, relationship);
+        }
+
+        cache.presences.clear();
+        let merged_presences = ready.merged_presences.map(|p| p.friends).unwrap_or_default();
+        for presence in ready.presences.into_iter().chain(merged_presences) {
+            insert_presence(cache, presence);
+        }
+
         *cache.user.write() = ready.user;
 
         None
     }
 }
 
+impl CacheUpdate for ReadySupplementalEvent {
+    type Output = ();
+
+    fn update(&mut self, cache: &Cache) -> Option<()> {
+        for presence in &self.merged_presences.friends {
+            insert_presence(cache, presence.clone());
+        }
+
+        for channel in &self.lazy_private_channels {
+            if let Channel::Private(channel) = channel {
+                insert_private_channel(cache, channel.clone());
+            }
+        }
+
+        let current_user = User::from(cache.user.read().clone());
+        for (i, supplemental) in self.guilds.iter().enumerate() {
+            let Some(mut guild) = cache.guilds.get_mut(&supplemental.id) else {
+                continue;
+            };
+
+            for voice_state in &supplemental.voice_states {
+                guild.voice_states.insert(voice_state.user_id, voice_state.clone());
+            }
+
+            for presence in self.merged_presences.guilds.get(i).into_iter().flatten() {
+                if presence.status != OnlineStatus::Offline {
+                    guild.presences.insert(presence.user.id, presence.clone());
+                }
+            }
+
+            // Members only carry their user's Id, so they can only be cached once the user is.
+            for member in self.merged_members.get(i).into_iter().flatten() {
+                let user = if member.user_id == current_user.id {
+                    Some(current_user.clone())
+                } else {
+                    cache.users.get(&member.user_id).map(|u| u.clone())
+                };
+
+                if let Some(user) = user {
+                    guild.members.insert(member.user_id, member.clone().into_member(user));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl CacheUpdate for RelationshipAddEvent {
+    type Output = Relationship;
+
+    fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
+        if let Some(user) = &self.relationship.user {
+            cache.update_user_entry(user);
+        }
+
+        cache.relationships.insert(self.relationship.id, self.relationship.clone())
+    }
+}
+
+impl CacheUpdate for RelationshipRemoveEvent {
+    type Output = Relationship;
+
+    fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
+        cache.relationships.remove(&self.id).map(|(_, relationship)| relationship)
+    }
+}
+
+impl CacheUpdate for RelationshipUpdateEvent {
+    type Output = Relationship;
+
+    fn update(&mut self, cache: &Cache) -> Option<Self::Output> {
+        let mut relationship = cache.relationships.get_mut(&self.id)?;
+        let old = relationship.clone();
+
+        relationship.kind = self.kind;
+        relationship.nickname.clone_from(&self.nickname);
+        if self.since.is_some() {
+            relationship.since = self.since;
+        }
+
+        Some(old)
+    }
+}
+
 impl CacheUpdate for ThreadCreateEvent {
     type Output = GuildChannel;
 
@@ -115,12 +115,16 @@ type MappedGuildRef<'a, T> = CacheRef<'a, GuildId, T, Guild>;
 pub type MemberRef<'a> = MappedGuildRef<'a, Member>;
 pub type GuildRoleRef<'a> = MappedGuildRef<'a, Role>;
 pub type UserRef<'a> = CacheRef<'a, UserId, User, Never>;
+pub type RelationshipRef<'a> = CacheRef<'a, UserId, Relationship, Never>;
+pub type PresenceRef<'a> = CacheRef<'a, UserId, Presence, Never>;
 pub type GuildRef<'a> = CacheRef<'a, GuildId, Guild, Never>;
 pub type SettingsRef<'a> = CacheRef<'a, Never, Settings, Never>;
 pub type GuildChannelRef<'a> = MappedGuildRef<'a, GuildChannel>;
+pub type PrivateChannelRef<'a> = CacheRef<'a, ChannelId, PrivateChannel, Never>;
 pub type CurrentUserRef<'a> = CacheRef<'a, Never, CurrentUser, Never>;
 pub type GuildRolesRef<'a> = MappedGuildRef<'a, HashMap<RoleId, Role>>;
 pub type GuildChannelsRef<'a> = MappedGuildRef<'a, HashMap<ChannelId, GuildChannel>>;
+pub type MemberListRef<'a> = CacheRef<'a, GuildId, MemberList, HashMap<String, MemberList>>;
 pub type MessageRef<'a> = CacheRef<'a, ChannelId, Message, HashMap<MessageId, Message>>;
 pub type ChannelMessagesRef<'a> = CacheRef<'a, ChannelId, HashMap<MessageId, Message>, Never>;
 
@@ -130,6 +134,7 @@ pub(crate) struct CachedShardData {
     pub total: u32,
     pub connected: HashSet<ShardId>,
     pub has_sent_shards_ready: bool,
+    pub has_sent_cache_ready: bool,
 }
 
 /// A cache containing data received from [`Shard`]s.
@@ -138,12 +143,17 @@ pub(crate) struct CachedShardData {
 /// Issuing too many requests will lead to ratelimits.
 ///
 /// This is the list of cached resources and the events that populate them:
-/// - channels: [`ChannelCreateEvent`], [`ChannelUpdateEvent`], [`GuildCreateEvent`]
-/// - guilds: [`GuildCreateEvent`]
+/// - channels: [`ChannelCreateEvent`], [`ChannelUpdateEvent`], [`GuildCreateEvent`], [`ReadyEvent`]
+/// - private_channels: [`ChannelCreateEvent`], [`ChannelUpdateEvent`], [`ChannelDeleteEvent`],
+///   [`ChannelRecipientAddEvent`], [`ChannelRecipientRemoveEvent`], [`MessageCreateEvent`],
+///   [`ReadyEvent`]
+/// - guilds: [`GuildCreateEvent`], [`ReadyEvent`]
 /// - unavailable_guilds: [`ReadyEvent`], [`GuildDeleteEvent`]
 /// - users: [`GuildMemberAddEvent`], [`GuildMemberRemoveEvent`], [`GuildMembersChunkEvent`],
 ///   [`PresenceUpdateEvent`], [`ReadyEvent`]
 /// - presences: [`PresenceUpdateEvent`], [`ReadyEvent`]
+/// - relationships: [`ReadyEvent`], [`RelationshipAddEvent`], [`RelationshipRemoveEvent`],
+///   [`RelationshipUpdateEvent`]
 /// - messages: [`MessageCreateEvent`]
 ///
 /// The documentation of each event contains the required gateway intents.
@@ -180,6 +190,11 @@ pub struct Cache {
     // Channels cache:
     /// A map of channel ids to the guilds in which the channel data is stored.
     pub(crate) channels: MaybeMap<ChannelId, GuildId>,
+    /// A map of the current user's private channels and group DMs.
+    ///
+    /// Seeded from [`Ready::private_channels`] and kept up to date via channel and recipient
+    /// events.
+    pub(crate) private_channels: MaybeMap<ChannelId, PrivateChannel>,
 
     // Guilds cache:
     // ---
@@ -188,9 +203,18 @@ pub struct Cache {
     pub(crate) guilds: MaybeMap<GuildId, Guild>,
     /// A list of guilds which are "unavailable".
     ///
-    /// Additionally, guilds are always unavailable for bot users when a Ready is received. Guilds
-    /// are "sent in" over time through the receiving of [`Event::GuildCreate`]s.
+    /// User sessions receive full guilds in Ready, so only guilds affected by an outage end up
+    /// here. They are "sent in" over time through the receiving of [`Event::GuildCreate`]s.
     pub(crate) unavailable_guilds: MaybeMap<GuildId, ()>,
+    /// A map of guilds to the member lists received for them, keyed by list Id.
+    ///
+    /// Lists are only sent for ranges subscribed to with
+    /// [`ShardMessenger::update_guild_subscriptions`], and are built up from
+    /// [`GuildMemberListUpdate`][`GuildMemberListUpdateEvent`] events.
+    ///
+    /// [`ShardMessenger::update_guild_subscriptions`]:
+    /// crate::gateway::ShardMessenger::update_guild_subscriptions
+    pub(crate) member_lists: MaybeMap<GuildId, HashMap<String, MemberList>>,
 
     // Users cache:
     // ---
@@ -208,6 +232,20 @@ pub struct Cache {
     /// [`GuildMemberRemove`][`GuildMemberRemoveEvent`], as other structs such as members or
     /// recipients may still exist.
     pub(crate) users: MaybeMap<UserId, User>,
+    /// A map of the current user's relationships, keyed by the Id of the target user.
+    ///
+    /// Seeded from [`Ready::relationships`] and kept up to date via the
+    /// [`RelationshipAdd`][`RelationshipAddEvent`],
+    /// [`RelationshipRemove`][`RelationshipRemoveEvent`] and
+    /// [`RelationshipUpdate`][`RelationshipUpdateEvent`] events.
+    pub(crate) relationships: MaybeMap<UserId, Relationship>,
+    /// A map of presences that aren't tied to a guild, i.e. those of the current user's friends
+    /// and implicit relationships.
+    ///
+    /// Seeded from [`Ready::presences`] and
+    /// [`ReadySupplemental`][`ReadySupplementalEvent`], and kept up to date via
+    /// [`PresenceUpdate`][`PresenceUpdateEvent`] events without a guild.
+    pub(crate) presences: MaybeMap<UserId, Presence>,
 
     // Messages cache:
     // ---
@@ -276,11 +314,15 @@ impl Cache {
             temp_users: temp_cache(settings.time_to_live),
 
             channels: MaybeMap(settings.cache_channels.then(DashMap::default)),
+            private_channels: MaybeMap(settings.cache_channels.then(DashMap::default)),
 
             guilds: MaybeMap(settings.cache_guilds.then(DashMap::default)),
             unavailable_guilds: MaybeMap(settings.cache_guilds.then(DashMap::default)),
+            member_lists: MaybeMap(settings.cache_guilds.then(DashMap::default)),
 
             users: MaybeMap(settings.cache_users.then(DashMap::default)),
+            relationships: MaybeMap(settings.cache_users.then(DashMap::default)),
+            presences: MaybeMap(settings.cache_users.then(DashMap::default)),
 
             messages: DashMap::default(),
             message_queue: DashMap::default(),
@@ -289,6 +331,7 @@ impl Cache {
                 total: 1,
                 connected: HashSet::new(),
                 has_sent_shards_ready: false,
+                has_sent_cache_ready: false,
             }),
             user: RwLock::new(CurrentUser::default()),
             settings: RwLock::new(settings),
@@ -396,6 +439,43 @@ impl Cache {
         None
     }
 
+    /// Retrieves a guild member list by its Id.
+    ///
+    /// Use [`Self::channel_member_list`] to look up the list shown in a channel's sidebar.
+    pub fn member_list(
+        &self,
+        guild_id: impl Into<GuildId>,
+        list_id: &str,
+    ) -> Option<MemberListRef<'_>> {
+        let lists = self.member_lists.get(&guild_id.into())?;
+        let list = lists.try_map(|lists| lists.get(list_id)).ok()?;
+        Some(CacheRef::from_mapped_ref(list))
+    }
+
+    /// Retrieves the member list shown in a guild channel's sidebar, if it has been subscribed to.
+    ///
+    /// The channel must be cached, as the list is identified by its permission overwrites. See
+    /// [`GuildChannel::member_list_id`].
+    pub fn channel_member_list<C: Into<ChannelId>>(&self, id: C) -> Option<MemberListRef<'_>> {
+        let id = id.into();
+        let guild_id = *self.channels.get(&id)?;
+        let list_id = self.guilds.get(&guild_id)?.channels.get(&id)?.member_list_id();
+        self.member_list(guild_id, &list_id)
+    }
+
+    /// Retrieves a [`PrivateChannel`], i.e. a DM or group DM, from the cache based on the given
+    /// Id.
+    #[inline]
+    pub fn private_channel<C: Into<ChannelId>>(&self, id: C) -> Option<PrivateChannelRef<'_>> {
+        self.private_channels.get(&id.into()).map(CacheRef::from_ref)
+    }
+
+    /// Returns all of the current user's cached private channels and group DMs.
+    #[inline]
+    pub fn private_channels(&self) -> ReadOnlyMapRef<'_, ChannelId, PrivateChannel> {
+        self.private_channels.as_read_only()
+    }
+
     /// Get a reference to the cached messages for a channel based on the given `Id`.
     ///
     /// # Examples
@@ -697,6 +777,32 @@ impl Cache {
         self.users.len()
     }
 
+    /// Retrieves the current user's [`Relationship`] with the given user, if one is cached.
+    #[inline]
+    pub fn relationship<U: Into<UserId>>(&self, user_id: U) -> Option<RelationshipRef<'_>> {
+        self.relationships.get(&user_id.into()).map(CacheRef::from_ref)
+    }
+
+    /// Returns all of the current user's cached relationships, keyed by the target user's Id.
+    #[inline]
+    pub fn relationships(&self) -> ReadOnlyMapRef<'_, UserId, Relationship> {
+        self.relationships.as_read_only()
+    }
+
+    /// Retrieves the presence of one of the current user's friends or implicit relationships.
+    ///
+    /// Presences of other users are only available per guild, through [`Guild::presences`].
+    #[inline]
+    pub fn presence<U: Into<UserId>>(&self, user_id: U) -> Option<PresenceRef<'_>> {
+        self.presences.get(&user_id.into()).map(CacheRef::from_ref)
+    }
+
+    /// Returns the presences of the current user's friends and implicit relationships.
+    #[inline]
+    pub fn presences(&self) -> ReadOnlyMapRef<'_, UserId, Presence> {
+        self.presences.as_read_only()
+    }
+
     /// This method provides a reference to the user used by the bot.
     #[inline]
     pub fn current_user(&self) -> CurrentUserRef<'_> {
@@ -828,10 +934,10 @@ mod test {
         // Add a channel delete event to the cache, the cached messages for that channel should now
         // be gone.
         let mut delete = ChannelDeleteEvent {
-            channel: channel.clone(),
+            channel: Channel::Guild(channel.clone()),
         };
         assert!(cache.update(&mut delete).is_some());
-        assert!(!cache.messages.contains_key(&delete.channel.id));
+        assert!(!cache.messages.contains_key(&channel.id));
 
         // Test deletion of a guild channel's message cache when a GuildDeleteEvent is received.
         let mut guild_create = GuildCreateEvent {
@@ -857,4 +963,152 @@ mod test {
         // Assert that the channel's message cache no longer exists.
         assert!(!cache.messages.contains_key(&ChannelId::new(2)));
     }
+
+    #[test]
+    fn test_cache_ready_supplemental() {
+        let cache = Cache::default();
+        cache.user.write().id = UserId::new(10);
+
+        let mut guild_create = GuildCreateEvent {
+            guild: Guild {
+                id: GuildId::new(1),
+                ..Default::default()
+            },
+        };
+        cache.update(&mut guild_create);
+
+        let mut event: ReadySupplementalEvent = crate::json::from_value(crate::json::json!({
+            "merged_presences": {
+                "friends": [{"user_id": "20", "status": "idle", "activities": []}],
+                "guilds": [[{"user_id": "30", "status": "online"}]],
+            },
+            "merged_members": [[{
+                "user_id": "10",
+                "roles": [],
+                "joined_at": "2020-01-01T00:00:00.000000+00:00",
+                "nick": "me",
+            }]],
+            "lazy_private_channels": [],
+            "guilds": [{"id": "1", "voice_states": [{
+                "user_id": "30",
+                "channel_id": "2",
+                "session_id": "abc",
+                "deaf": false,
+                "mute": false,
+                "self_deaf": false,
+                "self_mute": false,
+                "self_video": false,
+                "suppress": false,
+                "request_to_speak_timestamp": null,
+            }]}],
+        }))
+        .unwrap();
+        cache.update(&mut event);
+
+        assert_eq!(cache.presence(UserId::new(20)).unwrap().status, OnlineStatus::Idle);
+
+        let guild = cache.guild(GuildId::new(1)).unwrap();
+        let member = &guild.members[&UserId::new(10)];
+        assert_eq!(member.nick.as_deref(), Some("me"));
+        assert_eq!(member.guild_id, GuildId::new(1));
+        assert_eq!(guild.presences[&UserId::new(30)].guild_id, Some(GuildId::new(1)));
+        assert_eq!(guild.voice_states[&UserId::new(30)].guild_id, Some(GuildId::new(1)));
+    }
+
+    #[test]
+    fn test_cache_private_channels() {
+        use crate::json::{from_value, json};
+        use crate::model::gateway::user_json;
+
+        let settings = Settings {
+            max_messages: 10,
+            ..Default::default()
+        };
+        let cache = Cache::new_with_settings(settings);
+        let channel_id = ChannelId::new(3);
+        let recipients = |cache: &Cache| -> Vec<UserId> {
+            let channel = cache.private_channel(channel_id).unwrap();
+            channel.recipients.iter().map(|user| user.id).collect()
+        };
+
+        let mut create: ChannelCreateEvent =
+            from_value(json!({"id": "3", "type": 3, "recipients": [user_json("2")]})).unwrap();
+        assert!(cache.update(&mut create).is_none());
+        assert_eq!(recipients(&cache), [UserId::new(2)]);
+        assert!(cache.user(UserId::new(2)).is_some());
+
+        // Recipients joining and leaving the group DM are tracked.
+        let mut add: ChannelRecipientAddEvent =
+            from_value(json!({"channel_id": "3", "user": user_json("4")})).unwrap();
+        cache.update(&mut add);
+        assert_eq!(recipients(&cache), [UserId::new(2), UserId::new(4)]);
+        assert!(cache.user(UserId::new(4)).is_some());
+
+        let mut remove: ChannelRecipientRemoveEvent =
+            from_value(json!({"channel_id": "3", "user": user_json("2")})).unwrap();
+        cache.update(&mut remove);
+        assert_eq!(recipients(&cache), [UserId::new(4)]);
+
+        // Messages sent in the channel become its last message.
+        let mut message = MessageCreateEvent {
+            message: Message {
+                id: MessageId::new(5),
+                channel_id,
+                ..Default::default()
+            },
+        };
+        cache.update(&mut message);
+        let channel = PrivateChannel::clone(&cache.private_channel(channel_id).unwrap());
+        assert_eq!(channel.last_message_id, Some(MessageId::new(5)));
+
+        // Deleting the channel removes it along with its messages.
+        let mut delete = ChannelDeleteEvent {
+            channel: Channel::Private(channel),
+        };
+        assert_eq!(cache.update(&mut delete).unwrap().len(), 1);
+        assert!(cache.private_channel(channel_id).is_none());
+        assert!(!cache.messages.contains_key(&channel_id));
+    }
+
+    #[test]
+    fn test_cache_relationships() {
+        use crate::json::{from_value, json};
+
+        let cache = Cache::default();
+        let user_id = UserId::new(2);
+
+        let mut add: RelationshipAddEvent = from_value(json!({
+            "id": "2",
+            "type": 3,
+            "nickname": null,
+            "user": {"id": "2", "username": "friend", "discriminator": "0", "avatar": null},
+            "since": "2024-01-01T00:00:00+00:00",
+        }))
+        .unwrap();
+        assert!(cache.update(&mut add).is_none());
+        assert_eq!(cache.relationship(user_id).unwrap().kind, RelationshipType::IncomingRequest);
+        assert_eq!(cache.user(user_id).unwrap().name, "friend");
+
+        // Accepting the request updates the cached relationship, keeping its user.
+        let mut update: RelationshipUpdateEvent =
+            from_value(json!({"id": "2", "type": 1, "nickname": "pal", "since": null})).unwrap();
+        let old = cache.update(&mut update).unwrap();
+        assert_eq!(old.kind, RelationshipType::IncomingRequest);
+        {
+            let relationship = cache.relationship(user_id).unwrap();
+            assert_eq!(relationship.kind, RelationshipType::Friend);
+            assert_eq!(relationship.nickname.as_deref(), Some("pal"));
+            assert!(relationship.since.is_some());
+            assert!(relationship.user.is_some());
+        }
+
+        let mut remove: RelationshipRemoveEvent =
+            from_value(json!({"id": "2", "type": 1, "nickname": "pal"})).unwrap();
+        assert_eq!(cache.update(&mut remove).unwrap().kind, RelationshipType::Friend);
+        assert!(cache.relationship(user_id).is_none());
+
+        // Updates of relationships that aren't cached are ignored.
+        assert!(cache.update(&mut update).is_none());
+        assert!(cache.relationship(user_id).is_none());
+    }
 }
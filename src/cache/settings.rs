@@ -28,11 +28,12 @@ pub struct Settings {
     ///
     /// Defaults to true.
     pub cache_guilds: bool,
-    /// Whether to cache channel data received from gateway.
+    /// Whether to cache channel data received from gateway, including private channels.
     ///
     /// Defaults to true.
     pub cache_channels: bool,
-    /// Whether to cache user data received from gateway.
+    /// Whether to cache user data received from gateway, including the current user's
+    /// relationships.
     ///
     /// Defaults to true.
     pub cache_users: bool,
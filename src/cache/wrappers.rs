@@ -42,6 +42,12 @@ impl<K: Eq + Hash, V> MaybeMap<K, V> {
         self.0.as_ref().map_or(0, |map| map.len())
     }
 
+    pub fn clear(&self) {
+        if let Some(map) = self.0.as_ref() {
+            map.clear();
+        }
+    }
+
     pub fn shrink_to_fit(&self) {
         if let Some(map) = self.0.as_ref() {
             map.shrink_to_fit();
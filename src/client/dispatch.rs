@@ -1,20 +1,27 @@
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use tracing::debug;
+use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
+use futures::future::{join_all, BoxFuture};
+use futures::StreamExt;
+use tokio::sync::{Notify, Semaphore};
+use tokio::time::{timeout, Duration};
+use tracing::{debug, warn};
 
 #[cfg(feature = "gateway")]
 use super::event_handler::{EventHandler, RawEventHandler};
+use super::subscription::EventSender;
 use super::{Context, FullEvent};
 #[cfg(feature = "cache")]
 use crate::cache::{Cache, CacheUpdate};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
 use crate::internal::tokio::spawn_named;
-use crate::model::channel::ChannelType;
+use crate::model::channel::{Channel, ChannelType};
 use crate::model::event::Event;
 use crate::model::guild::Member;
-#[cfg(feature = "cache")]
-use crate::model::id::GuildId;
+use crate::model::id::{ChannelId, GuildId};
 
 #[cfg(feature = "cache")]
 macro_rules! if_cache {
@@ -42,16 +49,522 @@ macro_rules! update_cache {
     ($cache:ident, $event:ident) => {};
 }
 
+/// How long a key of [`DispatchStrategy::Keyed`] is kept around without receiving events.
+const LANE_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Decides in which order the event handlers are called for the events received by a shard.
+///
+/// The cache is always updated in the order events are received, before their handlers are
+/// called.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DispatchStrategy {
+    /// Every handler call is spawned as soon as its event is received, so calls for later events
+    /// may run before, or at the same time as, calls for earlier ones.
+    #[default]
+    Concurrent,
+    /// The handlers for an event are only called once those for the previous event of the shard
+    /// have returned.
+    Sequential,
+    /// Like [`Self::Sequential`], but only for events sharing a key. Events of up to
+    /// `max_parallel` different keys are handled at the same time.
+    ///
+    /// Events without the kind of ID used as the key fall back to the other kind, and events
+    /// with neither, such as [`FullEvent::Ready`], share a key of their own.
+    Keyed { key: DispatchKey, max_parallel: usize },
+}
+
+/// What events are grouped by for [`DispatchStrategy::Keyed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DispatchKey {
+    /// Events are grouped by channel, so that, for example, the events of a message are handled
+    /// in order.
+    Channel,
+    /// Events are grouped by guild, with direct messages grouped by channel.
+    Guild,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum LaneKey {
+    Channel(ChannelId),
+    Guild(GuildId),
+}
+
+/// Limits the number of handler calls of a client running at once.
+///
+/// Events whose handlers can't be called yet wait in a backlog of each shard, while the shard
+/// keeps receiving events and updating the cache. What happens once a backlog is full depends on
+/// the [`BacklogPolicy`], which by default makes the shard wait for room.
+///
+/// The limit is shared by its clones, which can be used to look at its metrics.
+#[derive(Clone, Debug)]
+pub struct HandlerLimit {
+    inner: Arc<HandlerLimitInner>,
+    policy: BacklogPolicy,
+}
+
+/// What happens once the backlog of a shard under a [`HandlerLimit`] is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BacklogPolicy {
+    /// The shard stops receiving events until the backlog has room again, leaving them to wait
+    /// on the connection.
+    ///
+    /// The shard keeps heartbeating in the meantime, and keeps receiving events while one of its
+    /// heartbeats hasn't been acknowledged, so that it isn't disconnected as unresponsive. The
+    /// backlog may briefly hold more events than the limit because of this.
+    #[default]
+    Wait,
+    /// The shard keeps receiving events, and the oldest waiting event is dropped without calling
+    /// its handlers when another one arrives.
+    ///
+    /// Events about the lifecycle of the client, namely [`FullEvent::Ready`],
+    /// [`FullEvent::Resume`], [`FullEvent::CacheReady`] and [`FullEvent::ShardsReady`], are never
+    /// dropped, and are kept even beyond the limit. Any other event may be dropped, even if a
+    /// later event about the same channel or guild, such as an update of a dropped message, is
+    /// kept.
+    DropOldest,
+}
+
+#[derive(Debug)]
+struct HandlerLimitInner {
+    permits: Arc<Semaphore>,
+    max_in_flight: usize,
+    max_queued: usize,
+    queued: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+impl HandlerLimit {
+    /// Creates a limit of `max_in_flight` handler calls running at once, with up to `max_queued`
+    /// events waiting per shard. Both are at least 1.
+    ///
+    /// Shards wait for room once their backlog is full, see [`BacklogPolicy::Wait`].
+    #[must_use]
+    pub fn new(max_in_flight: usize, max_queued: usize) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+
+        Self {
+            inner: Arc::new(HandlerLimitInner {
+                permits: Arc::new(Semaphore::new(max_in_flight)),
+                max_in_flight,
+                max_queued: max_queued.max(1),
+                queued: AtomicUsize::new(0),
+                dropped: AtomicU64::new(0),
+            }),
+            policy: BacklogPolicy::default(),
+        }
+    }
+
+    /// Sets what happens once the backlog of a shard is full. Defaults to
+    /// [`BacklogPolicy::Wait`].
+    #[must_use]
+    pub fn policy(mut self, policy: BacklogPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the number of handler calls currently running.
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.inner.max_in_flight - self.inner.permits.available_permits()
+    }
+
+    /// Returns the number of events currently waiting for their handlers to be called, across
+    /// all shards.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.inner.queued.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of events dropped so far because a backlog was full, which only happens
+    /// with [`BacklogPolicy::DropOldest`].
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The handler calls for a single event.
+type Job = Vec<(&'static str, BoxFuture<'static, ()>)>;
+type Lanes = Mutex<HashMap<Option<LaneKey>, Sender<Job>>>;
+
+/// Runs the handler calls of a shard according to its [`DispatchStrategy`] and [`HandlerLimit`],
+/// and sends its events to the subscriptions of the client.
+pub(crate) struct Dispatcher {
+    router: Arc<Router>,
+    events: Option<EventSender>,
+    backlog: Option<Arc<Backlog>>,
+}
+
+impl Dispatcher {
+    pub fn new(
+        strategy: DispatchStrategy,
+        events: Option<EventSender>,
+        limit: Option<HandlerLimit>,
+    ) -> Self {
+        let router = Arc::new(Router::new(strategy));
+        let backlog = limit.map(|limit| {
+            let backlog = Arc::new(Backlog {
+                limit,
+                queue: Mutex::new(BacklogQueue::default()),
+                notify: Notify::new(),
+                room: Notify::new(),
+            });
+            spawn_named(
+                "dispatch::backlog",
+                run_backlog(Arc::clone(&backlog), Arc::clone(&router)),
+            );
+            backlog
+        });
+
+        Self {
+            router,
+            events,
+            backlog,
+        }
+    }
+
+    fn lane_key(&self, event: &Event) -> Option<LaneKey> {
+        let DispatchStrategy::Keyed {
+            key, ..
+        } = self.router.strategy
+        else {
+            return None;
+        };
+
+        let (guild_id, channel_id) = event_ids(event);
+        let (guild_key, channel_key) =
+            (guild_id.map(LaneKey::Guild), channel_id.map(LaneKey::Channel));
+        match key {
+            DispatchKey::Channel => channel_key.or(guild_key),
+            DispatchKey::Guild => guild_key.or(channel_key),
+        }
+    }
+
+    fn dispatch(&self, key: Option<LaneKey>, job: Job, lifecycle: bool) {
+        match &self.backlog {
+            Some(backlog) => backlog.push(key, job, lifecycle),
+            None => self.router.route(key, job),
+        }
+    }
+
+    /// Waits until the backlog has room for another event, if it makes the shard wait once full.
+    pub async fn wait_for_room(&self) {
+        if let Some(backlog) = &self.backlog {
+            if backlog.limit.policy == BacklogPolicy::Wait {
+                backlog.wait_for_room().await;
+            }
+        }
+    }
+}
+
+impl Drop for Dispatcher {
+    fn drop(&mut self) {
+        // Lets the backlog finish the events it holds, then stop.
+        if let Some(backlog) = &self.backlog {
+            backlog.queue.lock().expect("poison").closed = true;
+            backlog.notify.notify_one();
+        }
+    }
+}
+
+/// Hands handler calls to the tasks running them according to a [`DispatchStrategy`].
+struct Router {
+    strategy: DispatchStrategy,
+    lanes: Lanes,
+    lane_permits: Option<Arc<Semaphore>>,
+}
+
+impl Router {
+    fn new(strategy: DispatchStrategy) -> Self {
+        let lane_permits = match strategy {
+            DispatchStrategy::Keyed {
+                max_parallel, ..
+            } => Some(Arc::new(Semaphore::new(max_parallel.max(1)))),
+            _ => None,
+        };
+
+        Self {
+            strategy,
+            lanes: Mutex::new(HashMap::new()),
+            lane_permits,
+        }
+    }
+
+    fn route(self: &Arc<Self>, key: Option<LaneKey>, job: Job) {
+        if self.strategy == DispatchStrategy::Concurrent {
+            for (name, future) in job {
+                spawn_named(name, future);
+            }
+            return;
+        }
+
+        let mut lanes = self.lanes.lock().expect("poison");
+        let job = match lanes.get(&key) {
+            Some(tx) => match tx.unbounded_send(job) {
+                Ok(()) => return,
+                // The task of the lane was stopped, such as by the runtime shutting down.
+                Err(why) => why.into_inner(),
+            },
+            None => job,
+        };
+
+        let (tx, rx) = mpsc::unbounded();
+        tx.unbounded_send(job).expect("receiver is alive");
+        lanes.insert(key, tx);
+
+        spawn_named("dispatch::lane", run_lane(key, rx, Arc::clone(self)));
+    }
+}
+
+/// Calls the handlers for the events of a key one after another, stopping once none have been
+/// received for a while.
+async fn run_lane(key: Option<LaneKey>, mut rx: Receiver<Job>, router: Arc<Router>) {
+    loop {
+        let job = match timeout(LANE_IDLE_TIMEOUT, rx.next()).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(_) => {
+                // Events are only sent to the lane while the lock is held, so none can be missed
+                // in between checking and removing it.
+                let mut lanes = router.lanes.lock().expect("poison");
+                if let Ok(job) = rx.try_recv() {
+                    job
+                } else {
+                    lanes.remove(&key);
+                    return;
+                }
+            },
+        };
+
+        let _permit = match &router.lane_permits {
+            Some(permits) => permits.acquire().await.ok(),
+            None => None,
+        };
+
+        let handles = job.into_iter().map(|(name, future)| spawn_named(name, future));
+        for result in join_all(handles).await {
+            if let Err(why) = result {
+                debug!("Event handler failed: {:?}", why);
+            }
+        }
+    }
+}
+
+/// The events of a shard waiting for room under its [`HandlerLimit`].
+struct Backlog {
+    limit: HandlerLimit,
+    queue: Mutex<BacklogQueue>,
+    // Notified when a job is pushed.
+    notify: Notify,
+    // Notified when a job is popped.
+    room: Notify,
+}
+
+#[derive(Default)]
+struct BacklogQueue {
+    jobs: VecDeque<QueuedJob>,
+    closed: bool,
+    // Whether events were dropped since the backlog was last empty, to only warn once per burst.
+    overflowed: bool,
+}
+
+struct QueuedJob {
+    key: Option<LaneKey>,
+    job: Job,
+    // Whether the job is for an event about the lifecycle of the client, which is never dropped
+    // by BacklogPolicy::DropOldest.
+    lifecycle: bool,
+}
+
+impl Backlog {
+    fn push(&self, key: Option<LaneKey>, job: Job, lifecycle: bool) {
+        let limit = &self.limit.inner;
+        let mut queue = self.queue.lock().expect("poison");
+
+        queue.jobs.push_back(QueuedJob {
+            key,
+            job,
+            lifecycle,
+        });
+        // A backlog of nothing but lifecycle events grows past the limit instead.
+        let full = queue.jobs.len() > limit.max_queued;
+        let droppable = if full && self.limit.policy == BacklogPolicy::DropOldest {
+            queue.jobs.iter().position(|queued| !queued.lifecycle)
+        } else {
+            None
+        };
+        if let Some(index) = droppable {
+            queue.jobs.remove(index);
+            limit.dropped.fetch_add(1, Ordering::Relaxed);
+
+            if !queue.overflowed {
+                queue.overflowed = true;
+                warn!("Event handlers can't keep up, dropping the oldest events");
+            }
+        } else {
+            limit.queued.fetch_add(1, Ordering::Relaxed);
+        }
+        drop(queue);
+
+        self.notify.notify_one();
+    }
+
+    fn pop(&self) -> Popped {
+        let mut queue = self.queue.lock().expect("poison");
+
+        if let Some(queued) = queue.jobs.pop_front() {
+            self.limit.inner.queued.fetch_sub(1, Ordering::Relaxed);
+            self.room.notify_one();
+            return Popped::Job(queued.key, queued.job);
+        }
+
+        queue.overflowed = false;
+        if queue.closed {
+            Popped::Closed
+        } else {
+            Popped::Empty
+        }
+    }
+
+    async fn wait_for_room(&self) {
+        // Only the shard waits, so a notification for a pop in between checking and waiting is
+        // kept for it.
+        while self.queue.lock().expect("poison").jobs.len() >= self.limit.inner.max_queued {
+            self.room.notified().await;
+        }
+    }
+}
+
+enum Popped {
+    Job(Option<LaneKey>, Job),
+    Empty,
+    Closed,
+}
+
+/// Hands the events of a backlog to the router as handler calls finish. The calls for an event
+/// hold a permit of the limit each, returned once all of them have finished.
+async fn run_backlog(backlog: Arc<Backlog>, router: Arc<Router>) {
+    let limit = &backlog.limit.inner;
+
+    loop {
+        let (key, job) = match backlog.pop() {
+            Popped::Job(key, job) => (key, job),
+            Popped::Empty => {
+                backlog.notify.notified().await;
+                continue;
+            },
+            Popped::Closed => return,
+        };
+
+        // Events with more handlers than the limit would never get enough permits otherwise.
+        let count = job.len().min(limit.max_in_flight);
+        let count = u32::try_from(count).unwrap_or(u32::MAX);
+        let Ok(permit) = Arc::clone(&limit.permits).acquire_many_owned(count).await else {
+            return;
+        };
+
+        let permit = Arc::new(permit);
+        let job = job
+            .into_iter()
+            .map(|(name, future)| {
+                let permit = Arc::clone(&permit);
+                let future: BoxFuture<'static, ()> = Box::pin(async move {
+                    future.await;
+                    drop(permit);
+                });
+                (name, future)
+            })
+            .collect();
+
+        router.route(key, job);
+    }
+}
+
+/// Returns the guild and channel an event belongs to, if any.
+#[allow(deprecated)]
+fn event_ids(event: &Event) -> (Option<GuildId>, Option<ChannelId>) {
+    match event {
+        Event::ChannelCreate(event) => channel_ids(&event.channel),
+        Event::ChannelDelete(event) => channel_ids(&event.channel),
+        Event::ChannelUpdate(event) => channel_ids(&event.channel),
+        Event::ChannelPinsUpdate(event) => (event.guild_id, Some(event.channel_id)),
+        Event::ChannelRecipientAdd(event) => (None, Some(event.channel_id)),
+        Event::ChannelRecipientRemove(event) => (None, Some(event.channel_id)),
+        Event::InviteCreate(event) => (event.guild_id, Some(event.channel_id)),
+        Event::InviteDelete(event) => (event.guild_id, Some(event.channel_id)),
+        Event::MessageCreate(event) => (event.message.guild_id, Some(event.message.channel_id)),
+        Event::MessageDelete(event) => (event.guild_id, Some(event.channel_id)),
+        Event::MessageDeleteBulk(event) => (event.guild_id, Some(event.channel_id)),
+        Event::MessageUpdate(event) => (event.guild_id, Some(event.channel_id)),
+        Event::MessagePollVoteAdd(event) => (event.guild_id, Some(event.channel_id)),
+        Event::MessagePollVoteRemove(event) => (event.guild_id, Some(event.channel_id)),
+        Event::ReactionAdd(event) => (event.reaction.guild_id, Some(event.reaction.channel_id)),
+        Event::ReactionRemove(event) => (event.reaction.guild_id, Some(event.reaction.channel_id)),
+        Event::ReactionRemoveAll(event) => (event.guild_id, Some(event.channel_id)),
+        Event::ReactionRemoveEmoji(event) => {
+            (event.reaction.guild_id, Some(event.reaction.channel_id))
+        },
+        Event::ThreadCreate(event) => (Some(event.thread.guild_id), Some(event.thread.id)),
+        Event::ThreadUpdate(event) => (Some(event.thread.guild_id), Some(event.thread.id)),
+        Event::ThreadDelete(event) => (Some(event.thread.guild_id), Some(event.thread.id)),
+        Event::TypingStart(event) => (event.guild_id, Some(event.channel_id)),
+        Event::WebhookUpdate(event) => (Some(event.guild_id), Some(event.channel_id)),
+        Event::GuildAuditLogEntryCreate(event) => (Some(event.guild_id), None),
+        Event::GuildBanAdd(event) => (Some(event.guild_id), None),
+        Event::GuildBanRemove(event) => (Some(event.guild_id), None),
+        Event::GuildCreate(event) => (Some(event.guild.id), None),
+        Event::GuildDelete(event) => (Some(event.guild.id), None),
+        Event::GuildEmojisUpdate(event) => (Some(event.guild_id), None),
+        Event::GuildIntegrationsUpdate(event) => (Some(event.guild_id), None),
+        Event::GuildMemberAdd(event) => (Some(event.member.guild_id), None),
+        Event::GuildMemberRemove(event) => (Some(event.guild_id), None),
+        Event::GuildMemberUpdate(event) => (Some(event.guild_id), None),
+        Event::GuildMemberListUpdate(event) => (Some(event.guild_id), None),
+        Event::GuildMembersChunk(event) => (Some(event.guild_id), None),
+        Event::GuildRoleCreate(event) => (Some(event.role.guild_id), None),
+        Event::GuildRoleDelete(event) => (Some(event.guild_id), None),
+        Event::GuildRoleUpdate(event) => (Some(event.role.guild_id), None),
+        Event::GuildStickersUpdate(event) => (Some(event.guild_id), None),
+        Event::GuildUpdate(event) => (Some(event.guild.id), None),
+        Event::ThreadListSync(event) => (Some(event.guild_id), None),
+        Event::ThreadMembersUpdate(event) => (Some(event.guild_id), None),
+        Event::VoiceChannelStatusUpdate(event) => (Some(event.guild_id), None),
+        Event::PresenceUpdate(event) => (event.presence.guild_id, None),
+        Event::VoiceStateUpdate(event) => {
+            (event.voice_state.guild_id, event.voice_state.channel_id)
+        },
+        _ => (None, None),
+    }
+}
+
+fn channel_ids(channel: &Channel) -> (Option<GuildId>, Option<ChannelId>) {
+    match channel {
+        Channel::Guild(channel) => (Some(channel.guild_id), Some(channel.id)),
+        Channel::Private(channel) => (None, Some(channel.id)),
+    }
+}
+
 pub(crate) fn dispatch_model(
     event: Event,
     context: &Context,
     #[cfg(feature = "framework")] framework: Option<Arc<dyn Framework>>,
     event_handlers: Vec<Arc<dyn EventHandler>>,
     raw_event_handlers: Vec<Arc<dyn RawEventHandler>>,
+    dispatcher: &Dispatcher,
 ) {
+    let key = dispatcher.lane_key(&event);
+    let mut job: Job = Vec::new();
+    let mut lifecycle = false;
+
     for raw_handler in raw_event_handlers {
         let (context, event) = (context.clone(), event.clone());
-        tokio::spawn(async move { raw_handler.raw_event(context, event).await });
+        job.push((
+            "dispatch::raw_event",
+            Box::pin(async move { raw_handler.raw_event(context, event).await }),
+        ));
     }
 
     let full_events = update_cache_with_event(
@@ -62,13 +575,24 @@ pub(crate) fn dispatch_model(
 
     if let Some(events) = full_events {
         let iter = std::iter::once(events.0).chain(events.1);
+        lifecycle = iter.clone().any(|event| is_lifecycle(&event));
+        if let Some(tx) = dispatcher.events.as_ref().filter(|tx| tx.receiver_count() > 0) {
+            for event in iter.clone() {
+                // Only fails if every subscription was dropped in the meantime.
+                drop(tx.send((context.clone(), event)));
+            }
+        }
+
         for handler in event_handlers {
             for event in iter.clone() {
                 let context = context.clone();
                 let handler = Arc::clone(&handler);
-                spawn_named(event.snake_case_name(), async move {
-                    event.dispatch(context, &*handler).await;
-                });
+                job.push((
+                    event.snake_case_name(),
+                    Box::pin(async move {
+                        event.dispatch(context, &*handler).await;
+                    }),
+                ));
             }
         }
 
@@ -77,17 +601,34 @@ pub(crate) fn dispatch_model(
             for event in iter {
                 let context = context.clone();
                 let framework = Arc::clone(&framework);
-                spawn_named("dispatch::framework::dispatch", async move {
-                    framework.dispatch(context, event).await;
-                });
+                job.push((
+                    "dispatch::framework::dispatch",
+                    Box::pin(async move {
+                        framework.dispatch(context, event).await;
+                    }),
+                ));
             }
         }
     }
+
+    if !job.is_empty() {
+        dispatcher.dispatch(key, job, lifecycle);
+    }
+}
+
+/// Whether an event is about the lifecycle of the client, which a [`HandlerLimit`] never drops.
+fn is_lifecycle(event: &FullEvent) -> bool {
+    #[cfg(feature = "cache")]
+    if matches!(event, FullEvent::CacheReady { .. } | FullEvent::ShardsReady { .. }) {
+        return true;
+    }
+
+    matches!(event, FullEvent::Ready { .. } | FullEvent::Resume { .. })
 }
 
 /// Updates the cache with the incoming event data and builds the full event data out of it.
 ///
-/// Can return a secondary [`FullEvent`] for "virtual" events like [`FullEvent::CacheReady`] or
+/// Can return secondary [`FullEvent`]s for "virtual" events like [`FullEvent::CacheReady`] or
 /// [`FullEvent::ShardsReady`]. Secondary events are traditionally dispatched first.
 ///
 /// Can return `None` if an event is unknown.
@@ -95,8 +636,8 @@ pub(crate) fn dispatch_model(
 fn update_cache_with_event(
     #[cfg(feature = "cache")] cache: &Cache,
     event: Event,
-) -> Option<(FullEvent, Option<FullEvent>)> {
-    let mut extra_event = None;
+) -> Option<(FullEvent, Vec<FullEvent>)> {
+    let mut extra_events = Vec::new();
     let event = match event {
         Event::CommandPermissionsUpdate(event) => FullEvent::CommandPermissionsUpdate {
             permission: event.permission,
@@ -116,41 +657,74 @@ fn update_cache_with_event(
         Event::ChannelCreate(mut event) => {
             update_cache!(cache, event);
 
-            let channel = event.channel;
-            if channel.kind == ChannelType::Category {
-                FullEvent::CategoryCreate {
-                    category: channel,
-                }
-            } else {
-                FullEvent::ChannelCreate {
+            match event.channel {
+                Channel::Guild(channel) if channel.kind == ChannelType::Category => {
+                    FullEvent::CategoryCreate {
+                        category: channel,
+                    }
+                },
+                Channel::Guild(channel) => FullEvent::ChannelCreate {
                     channel,
-                }
+                },
+                Channel::Private(channel) => FullEvent::PrivateChannelCreate {
+                    channel,
+                },
             }
         },
         Event::ChannelDelete(mut event) => {
             let cached_messages = if_cache!(event.update(cache));
 
-            let channel = event.channel;
-            if channel.kind == ChannelType::Category {
-                FullEvent::CategoryDelete {
-                    category: channel,
-                }
-            } else {
-                FullEvent::ChannelDelete {
+            match event.channel {
+                Channel::Guild(channel) if channel.kind == ChannelType::Category => {
+                    FullEvent::CategoryDelete {
+                        category: channel,
+                    }
+                },
+                Channel::Guild(channel) => FullEvent::ChannelDelete {
                     channel,
                     messages: cached_messages,
-                }
+                },
+                Channel::Private(channel) => FullEvent::PrivateChannelDelete {
+                    channel,
+                    messages: cached_messages,
+                },
             }
         },
         Event::ChannelPinsUpdate(event) => FullEvent::ChannelPinsUpdate {
             pin: event,
         },
+        Event::ChannelRecipientAdd(mut event) => {
+            update_cache!(cache, event);
+
+            FullEvent::ChannelRecipientAdd {
+                event,
+            }
+        },
+        Event::ChannelRecipientRemove(mut event) => {
+            update_cache!(cache, event);
+
+            FullEvent::ChannelRecipientRemove {
+                event,
+            }
+        },
         Event::ChannelUpdate(mut event) => {
-            let old_channel = if_cache!(event.update(cache));
+            let old_channel: Option<Channel> = if_cache!(event.update(cache));
 
-            FullEvent::ChannelUpdate {
-                old: old_channel,
-                new: event.channel,
+            match event.channel {
+                Channel::Guild(new) => FullEvent::ChannelUpdate {
+                    old: old_channel.and_then(|old| match old {
+                        Channel::Guild(old) => Some(old),
+                        Channel::Private(_) => None,
+                    }),
+                    new,
+                },
+                Channel::Private(new) => FullEvent::PrivateChannelUpdate {
+                    old: old_channel.and_then(|old| match old {
+                        Channel::Private(old) => Some(old),
+                        Channel::Guild(_) => None,
+                    }),
+                    new,
+                },
             }
         },
         Event::GuildAuditLogEntryCreate(event) => FullEvent::GuildAuditLogEntryCreate {
@@ -177,8 +751,9 @@ fn update_cache_with_event(
 
                     let guild_amount =
                         cache.guilds.iter().map(|i| *i.key()).collect::<Vec<GuildId>>();
+                    cache.shard_data.write().has_sent_cache_ready = true;
 
-                    extra_event = Some(FullEvent::CacheReady {
+                    extra_events.push(FullEvent::CacheReady {
                         guilds: guild_amount,
                     });
                 }
@@ -237,6 +812,13 @@ fn update_cache_with_event(
                 event,
             }
         },
+        Event::GuildMemberListUpdate(mut event) => {
+            update_cache!(cache, event);
+
+            FullEvent::GuildMemberListUpdate {
+                event,
+            }
+        },
         Event::GuildMembersChunk(mut event) => {
             update_cache!(cache, event);
 
@@ -347,12 +929,28 @@ fn update_cache_with_event(
             #[cfg(feature = "cache")]
             {
                 let mut shards = cache.shard_data.write();
-                if shards.connected.len() as u32 == shards.total && !shards.has_sent_shards_ready {
+                let all_connected = shards.connected.len() as u32 == shards.total;
+
+                // Full guilds were delivered with Ready, so no GuildCreate may follow to complete
+                // the cache once every shard is ready.
+                if all_connected
+                    && !shards.has_sent_cache_ready
+                    && cache.unavailable_guilds.len() == 0
+                {
+                    shards.has_sent_cache_ready = true;
+                    let guilds = cache.guilds.iter().map(|i| *i.key()).collect();
+
+                    extra_events.push(FullEvent::CacheReady {
+                        guilds,
+                    });
+                }
+
+                if all_connected && !shards.has_sent_shards_ready {
                     shards.has_sent_shards_ready = true;
                     let total = shards.total;
                     drop(shards);
 
-                    extra_event = Some(FullEvent::ShardsReady {
+                    extra_events.push(FullEvent::ShardsReady {
                         total_shards: total,
                     });
                 }
@@ -362,6 +960,39 @@ fn update_cache_with_event(
                 data_about_bot: event.ready,
             }
         },
+        Event::ReadySupplemental(mut event) => {
+            update_cache!(cache, event);
+
+            FullEvent::ReadySupplemental {
+                event,
+            }
+        },
+        Event::RelationshipAdd(mut event) => {
+            update_cache!(cache, event);
+
+            FullEvent::RelationshipAdd {
+                relationship: event.relationship,
+                should_notify: event.should_notify,
+            }
+        },
+        Event::RelationshipRemove(mut event) => {
+            let old_if_available = if_cache!(event.update(cache));
+
+            FullEvent::RelationshipRemove {
+                old_if_available,
+                event,
+            }
+        },
+        Event::RelationshipUpdate(mut event) => {
+            let before = if_cache!(event.update(cache));
+            let after = if_cache!(cache.relationship(event.id).map(|r| r.clone()));
+
+            FullEvent::RelationshipUpdate {
+                old_if_available: before,
+                new: after,
+                event,
+            }
+        },
         Event::Resumed(event) => FullEvent::Resume {
             event,
         },
@@ -380,6 +1011,10 @@ fn update_cache_with_event(
                 new: event.current_user,
             }
         },
+        Event::UserSettingsProtoUpdate(event) => FullEvent::UserSettingsProtoUpdate {
+            settings: event.settings,
+            partial: event.partial,
+        },
         Event::VoiceServerUpdate(event) => FullEvent::VoiceServerUpdate {
             event,
         },
@@ -493,5 +1128,148 @@ fn update_cache_with_event(
         },
     };
 
-    Some((event, extra_event))
+    Some((event, extra_events))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+    use tokio::sync::mpsc as tokio_mpsc;
+
+    use super::*;
+
+    /// A job sending the value once a permit of the gate, if any, is available.
+    fn job(
+        tx: &tokio_mpsc::UnboundedSender<u64>,
+        gate: Option<&Arc<Semaphore>>,
+        value: u64,
+    ) -> Job {
+        let (tx, gate) = (tx.clone(), gate.cloned());
+        vec![(
+            "test",
+            Box::pin(async move {
+                if let Some(gate) = gate {
+                    gate.acquire().await.unwrap().forget();
+                }
+                tx.send(value).unwrap();
+            }),
+        )]
+    }
+
+    #[tokio::test]
+    async fn keyed_order() {
+        let strategy = DispatchStrategy::Keyed {
+            key: DispatchKey::Channel,
+            max_parallel: 2,
+        };
+        let dispatcher = Dispatcher::new(strategy, None, None);
+        let gate = Arc::new(Semaphore::new(0));
+        let (tx, mut rx) = tokio_mpsc::unbounded_channel();
+
+        // Events of the same key are handled in order, while those of other keys don't wait.
+        let first = Some(LaneKey::Channel(ChannelId::new(1)));
+        let second = Some(LaneKey::Channel(ChannelId::new(2)));
+        dispatcher.dispatch(first, job(&tx, Some(&gate), 1), false);
+        dispatcher.dispatch(first, job(&tx, None, 2), false);
+        dispatcher.dispatch(second, job(&tx, None, 3), false);
+
+        assert_eq!(rx.recv().await, Some(3));
+        assert!(rx.try_recv().is_err());
+
+        gate.add_permits(1);
+        assert_eq!(rx.recv().await, Some(1));
+        assert_eq!(rx.recv().await, Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "cache")]
+    fn cache_ready_once() {
+        use crate::json::from_value;
+        use crate::model::event::ReadyEvent;
+        use crate::model::gateway::ready_json;
+
+        let cache = Cache::new();
+        let ready: ReadyEvent = from_value(ready_json([])).unwrap();
+
+        // Re-identifying doesn't announce the cache again.
+        for expected in [1, 0] {
+            let (_, extra_events) =
+                update_cache_with_event(&cache, Event::Ready(ready.clone())).unwrap();
+            let cache_ready =
+                extra_events.iter().filter(|e| matches!(e, FullEvent::CacheReady { .. })).count();
+            assert_eq!(cache_ready, expected);
+        }
+    }
+
+    /// Yields to the backlog task until the backlog holds the given number of events.
+    async fn wait_for_queued(limit: &HandlerLimit, queued: usize) {
+        while limit.queued() != queued {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_limit() {
+        let limit = HandlerLimit::new(1, 2);
+        let dispatcher = Dispatcher::new(DispatchStrategy::Concurrent, None, Some(limit.clone()));
+        let gate = Arc::new(Semaphore::new(0));
+        let (tx, mut rx) = tokio_mpsc::unbounded_channel();
+
+        // The first event runs, the second waits for it in the backlog task, and the backlog
+        // holds two more, after which the shard has to wait.
+        dispatcher.dispatch(None, job(&tx, Some(&gate), 1), false);
+        wait_for_queued(&limit, 0).await;
+        for value in 2..=4 {
+            dispatcher.dispatch(None, job(&tx, Some(&gate), value), false);
+        }
+        wait_for_queued(&limit, 2).await;
+        assert_eq!(limit.in_flight(), 1);
+        assert!(dispatcher.wait_for_room().now_or_never().is_none());
+
+        gate.add_permits(4);
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            received.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(received, [1, 2, 3, 4]);
+        assert_eq!(limit.queued(), 0);
+        assert_eq!(limit.dropped(), 0);
+        dispatcher.wait_for_room().await;
+    }
+
+    #[tokio::test]
+    async fn handler_limit_lifecycle() {
+        let limit = HandlerLimit::new(1, 2).policy(BacklogPolicy::DropOldest);
+        let dispatcher = Dispatcher::new(DispatchStrategy::Concurrent, None, Some(limit.clone()));
+        let gate = Arc::new(Semaphore::new(0));
+        let (tx, mut rx) = tokio_mpsc::unbounded_channel();
+
+        // The first event runs and the second waits for it in the backlog task. The shard never
+        // waits, with the oldest events other than lifecycle ones dropped instead.
+        for value in 1..=2 {
+            dispatcher.dispatch(None, job(&tx, Some(&gate), value), false);
+            wait_for_queued(&limit, 0).await;
+        }
+        for (value, lifecycle) in [(3, true), (4, false), (5, false)] {
+            dispatcher.dispatch(None, job(&tx, Some(&gate), value), lifecycle);
+        }
+        assert_eq!(limit.queued(), 2);
+        assert_eq!(limit.dropped(), 1);
+        dispatcher.wait_for_room().await;
+
+        // Once the backlog holds nothing else, it grows past the limit.
+        for value in 6..=7 {
+            dispatcher.dispatch(None, job(&tx, Some(&gate), value), true);
+        }
+        assert_eq!(limit.queued(), 3);
+        assert_eq!(limit.dropped(), 2);
+
+        gate.add_permits(5);
+        let mut received = Vec::new();
+        for _ in 0..5 {
+            received.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(received, [1, 2, 3, 6, 7]);
+        assert_eq!(limit.queued(), 0);
+    }
 }
@@ -149,6 +149,31 @@ event_handler! {
     /// The old channel data is only provided when the cache feature is enabled.
     ChannelUpdate { old: Option<GuildChannel>, new: GuildChannel } => async fn channel_update(&self, ctx: Context);
 
+    /// Dispatched when a user is added to a group DM.
+    ///
+    /// Provides the group DM's id and the user's data.
+    ChannelRecipientAdd { event: ChannelRecipientAddEvent } => async fn channel_recipient_add(&self, ctx: Context);
+
+    /// Dispatched when a user is removed from a group DM.
+    ///
+    /// Provides the group DM's id and the user's data.
+    ChannelRecipientRemove { event: ChannelRecipientRemoveEvent } => async fn channel_recipient_remove(&self, ctx: Context);
+
+    /// Dispatched when a private channel or group DM is created.
+    ///
+    /// Provides said channel's data.
+    PrivateChannelCreate { channel: PrivateChannel } => async fn private_channel_create(&self, ctx: Context);
+
+    /// Dispatched when a private channel or group DM is closed or deleted.
+    ///
+    /// Provides said channel's data and its cached messages, if the cache feature is enabled.
+    PrivateChannelDelete { channel: PrivateChannel, messages: Option<Vec<Message>> } => async fn private_channel_delete(&self, ctx: Context);
+
+    /// Dispatched when a private channel or group DM is updated.
+    ///
+    /// The old channel data is only provided when the cache feature is enabled.
+    PrivateChannelUpdate { old: Option<PrivateChannel>, new: PrivateChannel } => async fn private_channel_update(&self, ctx: Context);
+
     /// Dispatched when a new audit log entry is created.
     ///
     /// Provides said entry's data and the id of the guild where it was created.
@@ -219,6 +244,14 @@ event_handler! {
     /// on the bot application page.
     GuildMemberUpdate { old_if_available: Option<Member>, new: Option<Member>, event: GuildMemberUpdateEvent } => async fn guild_member_update(&self, ctx: Context);
 
+    /// Dispatched when a subscribed range of a guild member list changes.
+    ///
+    /// Provides the list operations. With the `cache` feature, the resulting list state is
+    /// available through [`Cache::member_list`].
+    ///
+    /// [`Cache::member_list`]: crate::cache::Cache::member_list
+    GuildMemberListUpdate { event: GuildMemberListUpdateEvent } => async fn guild_member_list_update(&self, ctx: Context);
+
     /// Dispatched when the data for offline members was requested.
     ///
     /// Provides the guild's id and the data.
@@ -319,6 +352,31 @@ event_handler! {
     /// Provides data about the bot and the guilds it's in.
     Ready { data_about_bot: Ready } => async fn ready(&self, ctx: Context);
 
+    /// Dispatched right after [`Self::ready`] for user sessions.
+    ///
+    /// Provides the presences, members, voice states and private channels that were left out of
+    /// Ready. With the `cache` feature, these are available in the cache by the time this fires.
+    ReadySupplemental { event: ReadySupplementalEvent } => async fn ready_supplemental(&self, ctx: Context);
+
+    /// Dispatched when a relationship with another user is created, e.g. a friend request is
+    /// received or sent, or a user is blocked.
+    ///
+    /// Provides the new relationship and whether the client should notify the user about it.
+    RelationshipAdd { relationship: Relationship, should_notify: bool } => async fn relationship_add(&self, ctx: Context);
+
+    /// Dispatched when a relationship with another user is removed.
+    ///
+    /// Provides the cached relationship (if cache feature is enabled and the data is available)
+    /// and the event information.
+    RelationshipRemove { old_if_available: Option<Relationship>, event: RelationshipRemoveEvent } => async fn relationship_remove(&self, ctx: Context);
+
+    /// Dispatched when a relationship with another user is modified, e.g. a friend request is
+    /// accepted.
+    ///
+    /// Provides the old (if cache feature is enabled and the data is available) and new
+    /// relationship, as well as the event information.
+    RelationshipUpdate { old_if_available: Option<Relationship>, new: Option<Relationship>, event: RelationshipUpdateEvent } => async fn relationship_update(&self, ctx: Context);
+
     /// Dispatched upon reconnection.
     Resume { event: ResumedEvent } => async fn resume(&self, ctx: Context);
 
@@ -335,6 +393,11 @@ event_handler! {
     /// Provides the old (if cache feature is enabled and the data is available) and new data.
     UserUpdate { old_data: Option<CurrentUser>, new: CurrentUser } => async fn user_update(&self, ctx: Context);
 
+    /// Dispatched when the current user's protobuf-encoded settings are updated.
+    ///
+    /// Provides the new settings and whether they only contain the changed fields.
+    UserSettingsProtoUpdate { settings: UserSettingsProto, partial: bool } => async fn user_settings_proto_update(&self, ctx: Context);
+
     /// Dispatched when a guild's voice server was updated (or changed to another one).
     ///
     /// Provides the voice server's data.
@@ -22,9 +22,13 @@ pub(crate) mod dispatch;
 mod error;
 #[cfg(feature = "gateway")]
 mod event_handler;
+#[cfg(feature = "gateway")]
+mod subscription;
 
 use std::future::IntoFuture;
 use std::ops::Range;
+#[cfg(feature = "gateway")]
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 #[cfg(feature = "framework")]
 use std::sync::OnceLock;
@@ -32,15 +36,19 @@ use std::sync::OnceLock;
 use futures::channel::mpsc::UnboundedReceiver as Receiver;
 use futures::future::BoxFuture;
 use futures::StreamExt as _;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{broadcast, Mutex, RwLock};
 use tracing::{debug, error, info, instrument};
 use typemap_rev::{TypeMap, TypeMapKey};
 
 pub use self::context::Context;
+#[cfg(feature = "gateway")]
+pub use self::dispatch::{BacklogPolicy, DispatchKey, DispatchStrategy, HandlerLimit};
 pub use self::error::Error as ClientError;
 #[cfg(feature = "gateway")]
 pub use self::event_handler::{EventHandler, FullEvent, RawEventHandler};
 #[cfg(feature = "gateway")]
+pub use self::subscription::{EventSender, EventSubscription, Lagged};
+#[cfg(feature = "gateway")]
 use super::gateway::GatewayError;
 #[cfg(feature = "cache")]
 pub use crate::cache::Cache;
@@ -52,11 +60,17 @@ use crate::framework::Framework;
 use crate::gateway::VoiceGatewayManager;
 use crate::gateway::{ActivityData, PresenceData};
 #[cfg(feature = "gateway")]
-use crate::gateway::{ShardManager, ShardManagerOptions};
+use crate::gateway::{
+    IdentifyGate,
+    SessionState,
+    ShardManager,
+    ShardManagerOptions,
+    TransportCompression,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 #[cfg(feature = "gateway")]
-use crate::model::gateway::GatewayIntents;
+use crate::model::gateway::{ClientProperties, GatewayCapabilities, GatewayIntents};
 use crate::model::id::ApplicationId;
 use crate::model::user::OnlineStatus;
 
@@ -67,6 +81,14 @@ pub struct ClientBuilder {
     data: TypeMap,
     http: Http,
     intents: GatewayIntents,
+    capabilities: GatewayCapabilities,
+    compression: TransportCompression,
+    session_states: Vec<SessionState>,
+    identify_gate: Option<Arc<dyn IdentifyGate>>,
+    recording_dir: Option<PathBuf>,
+    dispatch_strategy: DispatchStrategy,
+    event_buffer: usize,
+    handler_limit: Option<HandlerLimit>,
     #[cfg(feature = "cache")]
     cache_settings: CacheSettings,
     #[cfg(feature = "framework")]
@@ -85,6 +107,14 @@ impl ClientBuilder {
             data: TypeMap::new(),
             http,
             intents,
+            capabilities: GatewayCapabilities::default(),
+            compression: TransportCompression::default(),
+            session_states: Vec::new(),
+            identify_gate: None,
+            recording_dir: None,
+            dispatch_strategy: DispatchStrategy::default(),
+            event_buffer: 1024,
+            handler_limit: None,
             #[cfg(feature = "cache")]
             cache_settings: CacheSettings::default(),
             #[cfg(feature = "framework")]
@@ -143,6 +173,19 @@ impl ClientBuilder {
         self.http.application_id()
     }
 
+    /// Sets the [`ClientProperties`] sent when identifying on the gateway and along with every
+    /// HTTP request.
+    pub fn client_properties(mut self, client_properties: ClientProperties) -> Self {
+        self.http.set_client_properties(client_properties);
+
+        self
+    }
+
+    /// Gets the client properties. See [`Self::client_properties`] for more info.
+    pub fn get_client_properties(&self) -> &ClientProperties {
+        self.http.client_properties()
+    }
+
     /// Sets the entire [`TypeMap`] that will be available in [`Context`]s. A [`TypeMap`] must not
     /// be constructed manually: [`Self::type_map_insert`] can be used to insert one type at a
     /// time.
@@ -269,6 +312,133 @@ impl ClientBuilder {
         self.intents
     }
 
+    /// Sets the gateway capabilities sent when identifying, which change the shape of some
+    /// payloads sent to user sessions. None are enabled by default.
+    ///
+    /// See [`GatewayCapabilities`] for what each one does.
+    pub fn capabilities(mut self, capabilities: GatewayCapabilities) -> Self {
+        self.capabilities = capabilities;
+
+        self
+    }
+
+    /// Gets the gateway capabilities. See [`Self::capabilities`] for more info.
+    pub fn get_capabilities(&self) -> GatewayCapabilities {
+        self.capabilities
+    }
+
+    /// Sets the compression of the gateway connections. Large payloads are compressed one by one
+    /// by default.
+    pub fn transport_compression(mut self, compression: TransportCompression) -> Self {
+        self.compression = compression;
+
+        self
+    }
+
+    /// Gets the gateway compression. See [`Self::transport_compression`] for more info.
+    pub fn get_transport_compression(&self) -> TransportCompression {
+        self.compression
+    }
+
+    /// Sets the sessions to resume instead of identifying, exported from a previous run with
+    /// [`ShardManager::session_states`].
+    pub fn session_states(mut self, session_states: Vec<SessionState>) -> Self {
+        self.session_states = session_states;
+
+        self
+    }
+
+    /// Gets the sessions to resume. See [`Self::session_states`] for more info.
+    pub fn get_session_states(&self) -> &[SessionState] {
+        &self.session_states
+    }
+
+    /// Sets the gate deciding when shards may IDENTIFY, such as one coordinating several
+    /// processes through an external lock. Defaults to a [`LocalIdentifyGate`].
+    ///
+    /// [`LocalIdentifyGate`]: crate::gateway::LocalIdentifyGate
+    pub fn identify_gate<G: IdentifyGate + 'static>(mut self, identify_gate: G) -> Self {
+        self.identify_gate = Some(Arc::new(identify_gate));
+
+        self
+    }
+
+    /// Gets the identify gate, if set. See [`Self::identify_gate`] for more info.
+    pub fn get_identify_gate(&self) -> Option<Arc<dyn IdentifyGate>> {
+        self.identify_gate.clone()
+    }
+
+    /// Records the payloads received by each shard to a file named `shard-{id}.jsonl` in the
+    /// given directory, which can be replayed with a [`SessionReplay`].
+    ///
+    /// [`SessionReplay`]: crate::gateway::SessionReplay
+    pub fn record_sessions(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.recording_dir = Some(dir.into());
+
+        self
+    }
+
+    /// Gets the directory sessions are recorded to, if set. See [`Self::record_sessions`] for
+    /// more info.
+    pub fn get_recording_dir(&self) -> Option<&Path> {
+        self.recording_dir.as_deref()
+    }
+
+    /// Sets the order the event handlers are called in for the events of each shard. Defaults to
+    /// [`DispatchStrategy::Concurrent`].
+    ///
+    /// For example, [`DispatchStrategy::Keyed`] by [`DispatchKey::Channel`] makes sure a message
+    /// update is never handled before the creation of the message, while events of different
+    /// channels are still handled at the same time.
+    pub fn dispatch_strategy(mut self, dispatch_strategy: DispatchStrategy) -> Self {
+        self.dispatch_strategy = dispatch_strategy;
+
+        self
+    }
+
+    /// Gets the dispatch strategy. See [`Self::dispatch_strategy`] for more info.
+    pub fn get_dispatch_strategy(&self) -> DispatchStrategy {
+        self.dispatch_strategy
+    }
+
+    /// Sets the number of events kept for each [`EventSubscription`] of the client. Subscriptions
+    /// falling further behind miss the oldest events. Defaults to 1024.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is 0.
+    pub fn event_buffer(mut self, size: usize) -> Self {
+        assert!(size > 0, "the event buffer must hold at least one event");
+        self.event_buffer = size;
+
+        self
+    }
+
+    /// Gets the event buffer size. See [`Self::event_buffer`] for more info.
+    pub fn get_event_buffer(&self) -> usize {
+        self.event_buffer
+    }
+
+    /// Limits the number of event handler calls running at once, across all shards. By default,
+    /// there is no limit.
+    ///
+    /// Once the backlog of a shard is full, the shard stops receiving events until there is room,
+    /// unless the limit is set to drop the oldest events instead with
+    /// [`BacklogPolicy::DropOldest`].
+    ///
+    /// Keep a clone of the limit to look at the number of events waiting or dropped. See
+    /// [`HandlerLimit`] for more info.
+    pub fn handler_limit(mut self, handler_limit: HandlerLimit) -> Self {
+        self.handler_limit = Some(handler_limit);
+
+        self
+    }
+
+    /// Gets the handler limit, if set. See [`Self::handler_limit`] for more info.
+    pub fn get_handler_limit(&self) -> Option<&HandlerLimit> {
+        self.handler_limit.as_ref()
+    }
+
     /// Adds an event handler with multiple methods for each possible event.
     pub fn event_handler<H: EventHandler + 'static>(mut self, event_handler: H) -> Self {
         self.event_handlers.push(Arc::new(event_handler));
@@ -338,6 +508,14 @@ impl IntoFuture for ClientBuilder {
         let event_handlers = self.event_handlers;
         let raw_event_handlers = self.raw_event_handlers;
         let intents = self.intents;
+        let capabilities = self.capabilities;
+        let compression = self.compression;
+        let session_states = self.session_states;
+        let identify_gate = self.identify_gate;
+        let recording_dir = self.recording_dir;
+        let dispatch_strategy = self.dispatch_strategy;
+        let (events, _) = broadcast::channel(self.event_buffer);
+        let handler_limit = self.handler_limit;
         let presence = self.presence;
 
         let mut http = self.http;
@@ -361,13 +539,17 @@ impl IntoFuture for ClientBuilder {
         let cache = Arc::new(Cache::new_with_settings(self.cache_settings));
 
         Box::pin(async move {
-            let ws_url = Arc::new(Mutex::new(match http.get_gateway().await {
-                Ok(response) => response.url,
-                Err(err) => {
-                    tracing::warn!("HTTP request to get gateway URL failed: {}", err);
-                    "wss://gateway.discord.gg".to_string()
+            let ws_url = match http.api_base().gateway_url() {
+                Some(url) => url.to_string(),
+                None => match http.get_gateway().await {
+                    Ok(response) => response.url,
+                    Err(err) => {
+                        tracing::warn!("HTTP request to get gateway URL failed: {}", err);
+                        "wss://gateway.discord.gg".to_string()
+                    },
                 },
-            }));
+            };
+            let ws_url = Arc::new(Mutex::new(ws_url));
 
             #[cfg(feature = "framework")]
             let framework_cell = Arc::new(OnceLock::new());
@@ -387,13 +569,22 @@ impl IntoFuture for ClientBuilder {
                 cache: Arc::clone(&cache),
                 http: Arc::clone(&http),
                 intents,
+                capabilities,
                 presence: Some(presence),
+                compression,
+                session_states,
+                identify_gate,
+                recording_dir,
+                dispatch_strategy,
+                events: Some(events.clone()),
+                handler_limit,
             });
 
             let client = Client {
                 data,
                 shard_manager,
                 shard_manager_return_value: shard_manager_ret_value,
+                events,
                 #[cfg(feature = "voice")]
                 voice_manager,
                 ws_url,
@@ -609,6 +800,7 @@ pub struct Client {
     /// ```
     pub shard_manager: Arc<ShardManager>,
     shard_manager_return_value: Receiver<Result<(), GatewayError>>,
+    events: EventSender,
     /// The voice manager for the client.
     ///
     /// This is an ergonomic structure for interfacing over shards' voice
@@ -633,6 +825,15 @@ impl Client {
         ClientBuilder::new(token, intents)
     }
 
+    /// Subscribes to the events received by the client's shards, to be consumed alongside or
+    /// instead of an [`EventHandler`]. See [`EventSubscription`] for more info.
+    ///
+    /// Only events received after subscribing are seen.
+    #[must_use]
+    pub fn subscribe(&self) -> EventSubscription {
+        EventSubscription::new(self.events.subscribe())
+    }
+
     /// Establish the connection and start listening for events.
     ///
     /// This will start receiving events in a loop and start dispatching the events to your
@@ -711,6 +912,7 @@ impl Client {
     pub async fn start_autosharded(&mut self) -> Result<()> {
         let (end, total) = {
             let res = self.http.get_bot_gateway().await?;
+            self.shard_manager.set_session_start_limit(res.session_start_limit);
 
             (res.shards - 1, res.shards)
         };
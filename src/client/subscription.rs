@@ -0,0 +1,161 @@
+use std::error::Error as StdError;
+use std::fmt;
+
+use futures::stream::{self, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{Receiver, Sender};
+
+use super::{Context, FullEvent};
+
+/// The sending half of the channel [`EventSubscription`]s receive from.
+pub type EventSender = Sender<(Context, FullEvent)>;
+
+/// A subscription to the events of a [`Client`], as an alternative to an [`EventHandler`].
+///
+/// Events are received in the order the shards received them, after the cache has been updated
+/// for them. Subscriptions that fall behind by more than the buffer size of the client miss the
+/// oldest events, which is reported through [`Lagged`].
+///
+/// [`Client`]: super::Client
+/// [`EventHandler`]: super::EventHandler
+#[derive(Debug)]
+pub struct EventSubscription {
+    rx: Receiver<(Context, FullEvent)>,
+}
+
+impl EventSubscription {
+    pub(crate) fn new(rx: Receiver<(Context, FullEvent)>) -> Self {
+        Self {
+            rx,
+        }
+    }
+
+    /// Waits for the next event.
+    ///
+    /// Returns [`None`] once the client has been dropped and every event has been received.
+    pub async fn recv(&mut self) -> Option<Result<(Context, FullEvent), Lagged>> {
+        match self.rx.recv().await {
+            Ok(event) => Some(Ok(event)),
+            Err(RecvError::Lagged(missed)) => Some(Err(Lagged(missed))),
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    /// Turns the subscription into a stream of its events, which ends once the client has been
+    /// dropped.
+    ///
+    /// ```rust,no_run
+    /// # use serenity::prelude::*;
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run(client: Client) {
+    /// let mut events = Box::pin(client.subscribe().into_stream());
+    /// while let Some(event) = events.next().await {
+    ///     match event {
+    ///         Ok((_ctx, event)) => println!("Received {}", event.snake_case_name()),
+    ///         Err(lagged) => println!("Missed {} events", lagged.0),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn into_stream(self) -> impl Stream<Item = Result<(Context, FullEvent), Lagged>> {
+        stream::unfold(self, |mut subscription| async move {
+            let event = subscription.recv().await?;
+            Some((event, subscription))
+        })
+    }
+}
+
+/// The number of events an [`EventSubscription`] missed because it fell behind.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Lagged(pub u64);
+
+impl fmt::Display for Lagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Missed {} events", self.0)
+    }
+}
+
+impl StdError for Lagged {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::channel::mpsc;
+    use tokio::sync::{broadcast, RwLock};
+    use typemap_rev::TypeMap;
+
+    use super::*;
+    use crate::gateway::ShardMessenger;
+    use crate::http::Http;
+    use crate::model::id::ShardId;
+
+    fn send(tx: &EventSender, total_shards: u32) {
+        let context = Context {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            shard: ShardMessenger {
+                tx: mpsc::unbounded().0,
+                #[cfg(feature = "collector")]
+                collectors: Arc::default(),
+            },
+            shard_id: ShardId(0),
+            http: Arc::new(Http::new("")),
+            #[cfg(feature = "cache")]
+            cache: Arc::default(),
+        };
+        tx.send((context, FullEvent::ShardsReady {
+            total_shards,
+        }))
+        .unwrap();
+    }
+
+    async fn recv(subscription: &mut EventSubscription) -> Option<Result<u32, Lagged>> {
+        let event = subscription.recv().await?;
+        Some(event.map(|(_, event)| match event {
+            FullEvent::ShardsReady {
+                total_shards,
+            } => total_shards,
+            _ => unreachable!(),
+        }))
+    }
+
+    #[tokio::test]
+    async fn in_order() {
+        let (tx, rx) = broadcast::channel(4);
+        let mut subscription = EventSubscription::new(rx);
+        for total_shards in 1..=3 {
+            send(&tx, total_shards);
+        }
+
+        for total_shards in 1..=3 {
+            assert_eq!(recv(&mut subscription).await, Some(Ok(total_shards)));
+        }
+    }
+
+    #[tokio::test]
+    async fn lagged() {
+        let (tx, rx) = broadcast::channel(2);
+        let mut subscription = EventSubscription::new(rx);
+        for total_shards in 1..=5 {
+            send(&tx, total_shards);
+        }
+
+        // Only the two newest events are left in the buffer.
+        assert_eq!(recv(&mut subscription).await, Some(Err(Lagged(3))));
+        assert_eq!(recv(&mut subscription).await, Some(Ok(4)));
+        assert_eq!(recv(&mut subscription).await, Some(Ok(5)));
+    }
+
+    #[tokio::test]
+    async fn closed() {
+        let (tx, rx) = broadcast::channel(2);
+        let mut subscription = EventSubscription::new(rx);
+        send(&tx, 1);
+        drop(tx);
+
+        // Events sent before the sender was dropped are still received.
+        assert_eq!(recv(&mut subscription).await, Some(Ok(1)));
+        assert_eq!(recv(&mut subscription).await, None);
+    }
+}
@@ -21,9 +21,11 @@ pub const MESSAGE_CODE_LIMIT: usize = 2000;
 /// The maximum number of members the bot can fetch at once
 pub const MEMBER_FETCH_LIMIT: u64 = 1000;
 
-/// The [UserAgent] sent along with every request.
+/// The default [UserAgent] sent along with every request, unless overridden by
+/// [`ClientProperties::browser_user_agent`].
 ///
 /// [UserAgent]: ::reqwest::header::USER_AGENT
+/// [`ClientProperties::browser_user_agent`]: crate::model::gateway::ClientProperties::browser_user_agent
 pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:132.0) Gecko/20100101 Firefox/132.0";
 
 enum_number! {
@@ -58,6 +60,10 @@ enum_number! {
         Hello = 10,
         /// Sent immediately following a client heartbeat that was received.
         HeartbeatAck = 11,
+        /// Used by user sessions to subscribe to a single guild's events and member list.
+        GuildSubscriptions = 14,
+        /// Used by user sessions to subscribe to several guilds' events and member lists at once.
+        GuildSubscriptionsBulk = 37,
         _ => Unknown(u8),
     }
 }
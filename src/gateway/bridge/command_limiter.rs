@@ -0,0 +1,96 @@
+use std::time::Duration as StdDuration;
+
+use tokio::time::{Duration, Instant};
+
+/// The number of commands the gateway accepts per [`PERIOD`] before closing the connection.
+const COMMANDS_PER_PERIOD: u32 = 120;
+const PERIOD: Duration = Duration::from_secs(60);
+/// The time it takes for a single command to be refilled.
+const REFILL_INTERVAL: Duration = Duration::from_millis(500);
+/// The heartbeat interval to plan for until the gateway has sent the actual one.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(41_250);
+
+/// A token bucket over the gateway's limit on outbound commands.
+///
+/// Heartbeats, IDENTIFYs and RESUMEs are sent by the [`Shard`] without going through the bucket,
+/// so part of the limit is held back for them.
+///
+/// [`Shard`]: crate::gateway::Shard
+#[derive(Debug)]
+pub(crate) struct CommandLimiter {
+    capacity: u32,
+    tokens: u32,
+    refilled_at: Instant,
+}
+
+impl CommandLimiter {
+    pub fn new() -> Self {
+        let capacity = Self::capacity(DEFAULT_HEARTBEAT_INTERVAL);
+
+        Self {
+            capacity,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// The number of commands left over after reserving room for every heartbeat sent over a
+    /// period, plus one for a heartbeat requested by the gateway or an IDENTIFY.
+    fn capacity(heartbeat_interval: StdDuration) -> u32 {
+        let interval = heartbeat_interval.as_millis().max(1);
+        let heartbeats = PERIOD.as_millis().div_ceil(interval);
+        let reserved = u32::try_from(heartbeats + 1).unwrap_or(COMMANDS_PER_PERIOD);
+
+        COMMANDS_PER_PERIOD.saturating_sub(reserved)
+    }
+
+    /// Adjusts the reserved budget to the heartbeat interval sent by the gateway.
+    pub fn set_heartbeat_interval(&mut self, heartbeat_interval: Option<StdDuration>) {
+        self.capacity = Self::capacity(heartbeat_interval.unwrap_or(DEFAULT_HEARTBEAT_INTERVAL));
+        self.tokens = self.tokens.min(self.capacity);
+    }
+
+    /// Takes a token for a command if one is available.
+    pub fn try_acquire(&mut self) -> bool {
+        self.refill();
+
+        if self.tokens == 0 {
+            return false;
+        }
+
+        self.tokens -= 1;
+        true
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let refills =
+            now.duration_since(self.refilled_at).as_millis() / REFILL_INTERVAL.as_millis();
+        let refills = u32::try_from(refills).unwrap_or(u32::MAX);
+
+        self.tokens = self.tokens.saturating_add(refills);
+        if self.tokens >= self.capacity {
+            self.tokens = self.capacity;
+            self.refilled_at = now;
+        } else {
+            self.refilled_at += REFILL_INTERVAL * refills;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heartbeat_reserve() {
+        assert_eq!(CommandLimiter::capacity(DEFAULT_HEARTBEAT_INTERVAL), 117);
+        assert_eq!(CommandLimiter::capacity(StdDuration::from_secs(10)), 113);
+
+        let mut limiter = CommandLimiter::new();
+        for _ in 0..117 {
+            assert!(limiter.try_acquire());
+        }
+        assert!(!limiter.try_acquire());
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::model::gateway::SessionStartLimit;
+
+/// The time that must pass between two IDENTIFYs sharing a rate limit key.
+const IDENTIFY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Decides when shards may IDENTIFY, keeping them within the session start limit.
+///
+/// Shards are grouped by their rate limit key, `shard_id % max_concurrency`. Shards with
+/// different keys may identify at the same time, while shards sharing one must wait 5 seconds
+/// between IDENTIFYs. Shards resuming a session don't go through the gate.
+///
+/// [`LocalIdentifyGate`] keeps track of this within a single process. Implement this trait to
+/// coordinate several processes sharing a token, for example through an external lock.
+#[async_trait]
+pub trait IdentifyGate: Send + Sync {
+    /// Called with the session start limit when it becomes known, such as when starting through
+    /// [`Client::start_autosharded`].
+    ///
+    /// [`Client::start_autosharded`]: crate::Client::start_autosharded
+    fn set_session_start_limit(&self, _limit: &SessionStartLimit) {}
+
+    /// Returns the number of shards that may identify at the same time.
+    fn max_concurrency(&self) -> u64;
+
+    /// Waits until a shard with the given rate limit key may identify, then claims the IDENTIFY.
+    async fn acquire(&self, key: u64);
+}
+
+/// An [`IdentifyGate`] keeping track of the session start limit within this process.
+///
+/// Until a session start limit is given, shards identify one at a time with no daily limit.
+#[derive(Debug, Default)]
+pub struct LocalIdentifyGate {
+    state: Mutex<LocalState>,
+}
+
+#[derive(Debug)]
+struct LocalState {
+    max_concurrency: u64,
+    total: u64,
+    remaining: u64,
+    reset_at: Option<Instant>,
+    last_identifies: HashMap<u64, Instant>,
+}
+
+impl Default for LocalState {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 1,
+            total: u64::MAX,
+            remaining: u64::MAX,
+            reset_at: None,
+            last_identifies: HashMap::new(),
+        }
+    }
+}
+
+impl LocalIdentifyGate {
+    /// Creates a gate seeded with the given [`SessionStartLimit`], such as the one returned by
+    /// [`Http::get_bot_gateway`].
+    ///
+    /// [`Http::get_bot_gateway`]: crate::http::Http::get_bot_gateway
+    #[must_use]
+    pub fn new(limit: &SessionStartLimit) -> Self {
+        let gate = Self::default();
+        gate.set_session_start_limit(limit);
+        gate
+    }
+
+    /// Claims an IDENTIFY for the key if possible, or returns how long to wait before retrying.
+    fn try_acquire(&self, key: u64) -> Option<Duration> {
+        let mut state = self.state.lock().expect("poison");
+        let now = Instant::now();
+
+        if let Some(reset_at) = state.reset_at {
+            if now >= reset_at {
+                state.remaining = state.total;
+                state.reset_at = Some(now + Duration::from_secs(24 * 60 * 60));
+            } else if state.remaining == 0 {
+                return Some(reset_at.duration_since(now));
+            }
+        }
+
+        let next = state.last_identifies.get(&key).map(|last| *last + IDENTIFY_INTERVAL);
+        if let Some(next) = next.filter(|next| *next > now) {
+            return Some(next.duration_since(now));
+        }
+
+        state.remaining = state.remaining.saturating_sub(1);
+        state.last_identifies.insert(key, now);
+
+        None
+    }
+}
+
+#[async_trait]
+impl IdentifyGate for LocalIdentifyGate {
+    fn set_session_start_limit(&self, limit: &SessionStartLimit) {
+        let mut state = self.state.lock().expect("poison");
+
+        state.max_concurrency = limit.max_concurrency.max(1);
+        state.total = limit.total;
+        state.remaining = limit.remaining;
+        state.reset_at = Some(Instant::now() + Duration::from_millis(limit.reset_after));
+    }
+
+    fn max_concurrency(&self) -> u64 {
+        self.state.lock().expect("poison").max_concurrency
+    }
+
+    async fn acquire(&self, key: u64) {
+        while let Some(wait) = self.try_acquire(key) {
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{from_value, json};
+
+    #[test]
+    fn keys_and_remaining() {
+        let limit: SessionStartLimit = from_value(json!({
+            "remaining": 2,
+            "reset_after": 60_000,
+            "total": 1000,
+            "max_concurrency": 2,
+        }))
+        .unwrap();
+        let gate = LocalIdentifyGate::new(&limit);
+        assert_eq!(gate.max_concurrency(), 2);
+
+        // Different keys may identify at once, the same key has to wait.
+        assert!(gate.try_acquire(0).is_none());
+        assert!(gate.try_acquire(1).is_none());
+        assert!(gate.try_acquire(0).is_some());
+
+        // The session starts are used up until the limit resets.
+        let wait = gate.try_acquire(2).unwrap();
+        assert!(wait > IDENTIFY_INTERVAL);
+    }
+}
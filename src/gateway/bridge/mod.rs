@@ -40,7 +40,9 @@
 //! [`Client`]: crate::Client
 //! [`Shard`]: crate::gateway::Shard
 
+mod command_limiter;
 mod event;
+mod identify_gate;
 mod shard_manager;
 mod shard_messenger;
 mod shard_queuer;
@@ -53,6 +55,7 @@ use std::fmt;
 use std::time::Duration as StdDuration;
 
 pub use self::event::ShardStageUpdateEvent;
+pub use self::identify_gate::{IdentifyGate, LocalIdentifyGate};
 pub use self::shard_manager::{ShardManager, ShardManagerOptions};
 pub use self::shard_messenger::ShardMessenger;
 pub use self::shard_queuer::ShardQueuer;
@@ -63,6 +66,7 @@ pub use self::voice::VoiceGatewayManager;
 use super::ChunkGuildFilter;
 use crate::gateway::ConnectionStage;
 use crate::model::event::Event;
+use crate::model::gateway::SessionStartLimit;
 use crate::model::id::ShardId;
 
 /// A message to be sent to the [`ShardQueuer`].
@@ -71,6 +75,8 @@ pub enum ShardQueuerMessage {
     /// Message to start a shard, where the 0-index element is the ID of the Shard to start and the
     /// 1-index element is the total shards in use.
     Start(ShardId, ShardId),
+    /// Message to pass the session start limit on to the [`IdentifyGate`].
+    SetSessionStartLimit(SessionStartLimit),
     /// Message to shutdown the shard queuer.
     Shutdown,
     /// Message to dequeue/shutdown a shard.
@@ -90,6 +96,8 @@ pub struct ShardRunnerInfo {
     pub runner_tx: ShardMessenger,
     /// The current connection stage of the shard.
     pub stage: ConnectionStage,
+    /// The number of gateway commands waiting for room under the outbound rate limit.
+    pub queued_commands: usize,
 }
 
 impl AsRef<ShardMessenger> for ShardRunnerInfo {
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 #[cfg(feature = "framework")]
@@ -14,17 +15,30 @@ use typemap_rev::TypeMap;
 
 #[cfg(feature = "voice")]
 use super::VoiceGatewayManager;
-use super::{ShardId, ShardQueuer, ShardQueuerMessage, ShardRunnerInfo};
+use super::{
+    IdentifyGate,
+    LocalIdentifyGate,
+    ShardId,
+    ShardQueuer,
+    ShardQueuerMessage,
+    ShardRunnerInfo,
+};
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
-use crate::client::{EventHandler, RawEventHandler};
+use crate::client::{DispatchStrategy, EventHandler, EventSender, HandlerLimit, RawEventHandler};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::{ConnectionStage, GatewayError, PresenceData};
+use crate::gateway::{
+    ConnectionStage,
+    GatewayError,
+    PresenceData,
+    SessionState,
+    TransportCompression,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
-use crate::model::gateway::GatewayIntents;
+use crate::model::gateway::{GatewayCapabilities, GatewayIntents, SessionStartLimit};
 
 /// A manager for handling the status of shards by starting them, restarting them, and stopping
 /// them when required.
@@ -51,11 +65,11 @@ use crate::model::gateway::GatewayIntents;
 /// use std::env;
 /// use std::sync::{Arc, OnceLock};
 ///
-/// use serenity::client::{EventHandler, RawEventHandler};
+/// use serenity::client::{DispatchStrategy, EventHandler, RawEventHandler};
 /// use serenity::framework::{Framework, StandardFramework};
-/// use serenity::gateway::{ShardManager, ShardManagerOptions};
+/// use serenity::gateway::{ShardManager, ShardManagerOptions, TransportCompression};
 /// use serenity::http::Http;
-/// use serenity::model::gateway::GatewayIntents;
+/// use serenity::model::gateway::{GatewayCapabilities, GatewayIntents};
 /// use serenity::prelude::*;
 /// use tokio::sync::{Mutex, RwLock};
 ///
@@ -88,7 +102,15 @@ use crate::model::gateway::GatewayIntents;
 ///     # cache: unimplemented!(),
 ///     # http,
 ///     intents: GatewayIntents::non_privileged(),
+///     capabilities: GatewayCapabilities::empty(),
 ///     presence: None,
+///     compression: TransportCompression::Zlib,
+///     session_states: vec![],
+///     identify_gate: None,
+///     recording_dir: None,
+///     dispatch_strategy: DispatchStrategy::Concurrent,
+///     events: None,
+///     handler_limit: None,
 /// });
 /// # Ok(())
 /// # }
@@ -116,6 +138,7 @@ pub struct ShardManager {
     shard_shutdown: Mutex<Receiver<ShardId>>,
     shard_shutdown_send: Sender<ShardId>,
     gateway_intents: GatewayIntents,
+    session_states: Mutex<HashMap<ShardId, SessionState>>,
 }
 
 impl ShardManager {
@@ -129,6 +152,9 @@ impl ShardManager {
         let runners = Arc::new(Mutex::new(HashMap::new()));
         let (shutdown_send, shutdown_recv) = mpsc::unbounded();
 
+        let sessions: HashMap<_, _> =
+            opt.session_states.into_iter().map(|state| (state.shard_info.id, state)).collect();
+
         let manager = Arc::new(Self {
             return_value_tx: Mutex::new(return_value_tx),
             shard_index: AtomicU32::new(opt.shard_index),
@@ -139,6 +165,7 @@ impl ShardManager {
             shard_shutdown_send: shutdown_send,
             runners: Arc::clone(&runners),
             gateway_intents: opt.intents,
+            session_states: Mutex::new(sessions.clone()),
         });
 
         let mut shard_queuer = ShardQueuer {
@@ -147,7 +174,6 @@ impl ShardManager {
             raw_event_handlers: opt.raw_event_handlers,
             #[cfg(feature = "framework")]
             framework: opt.framework,
-            last_start: None,
             manager: Arc::clone(&manager),
             queue: VecDeque::new(),
             runners,
@@ -159,7 +185,17 @@ impl ShardManager {
             cache: opt.cache,
             http: opt.http,
             intents: opt.intents,
+            capabilities: opt.capabilities,
             presence: opt.presence,
+            compression: opt.compression,
+            sessions,
+            identify_gate: opt
+                .identify_gate
+                .unwrap_or_else(|| Arc::new(LocalIdentifyGate::default())),
+            recording_dir: opt.recording_dir,
+            dispatch_strategy: opt.dispatch_strategy,
+            events: opt.events,
+            handler_limit: opt.handler_limit,
         };
 
         spawn_named("shard_queuer::run", async move {
@@ -333,6 +369,14 @@ impl ShardManager {
         drop(self.shard_queuer.unbounded_send(msg));
     }
 
+    /// Passes the session start limit, such as the one returned by [`Http::get_bot_gateway`], on
+    /// to the [`IdentifyGate`] deciding when shards may IDENTIFY.
+    ///
+    /// [`Http::get_bot_gateway`]: crate::http::Http::get_bot_gateway
+    pub fn set_session_start_limit(&self, limit: SessionStartLimit) {
+        drop(self.shard_queuer.unbounded_send(ShardQueuerMessage::SetSessionStartLimit(limit)));
+    }
+
     /// Returns the gateway intents used for this gateway connection.
     #[must_use]
     pub fn intents(&self) -> GatewayIntents {
@@ -358,6 +402,24 @@ impl ShardManager {
         }
     }
 
+    /// Returns what is needed to resume the sessions of the shards, as of their last stage change
+    /// or heartbeat acknowledgement, or their shutdown.
+    ///
+    /// Feed these back in with [`ShardManagerOptions::session_states`] to resume the sessions
+    /// after a restart. Shards shut down with close code 1000 or 1001, such as through
+    /// [`Self::shutdown_all`], can't be resumed and are left out.
+    pub async fn session_states(&self) -> Vec<SessionState> {
+        self.session_states.lock().await.values().cloned().collect()
+    }
+
+    pub(crate) async fn update_session_state(&self, id: ShardId, state: Option<SessionState>) {
+        let mut session_states = self.session_states.lock().await;
+        match state {
+            Some(state) => session_states.insert(id, state),
+            None => session_states.remove(&id),
+        };
+    }
+
     pub async fn update_shard_latency_and_stage(
         &self,
         id: ShardId,
@@ -369,6 +431,12 @@ impl ShardManager {
             runner.stage = stage;
         }
     }
+
+    pub(crate) async fn update_shard_queued_commands(&self, id: ShardId, queued_commands: usize) {
+        if let Some(runner) = self.runners.lock().await.get_mut(&id) {
+            runner.queued_commands = queued_commands;
+        }
+    }
 }
 
 impl Drop for ShardManager {
@@ -399,5 +467,24 @@ pub struct ShardManagerOptions {
     pub cache: Arc<Cache>,
     pub http: Arc<Http>,
     pub intents: GatewayIntents,
+    pub capabilities: GatewayCapabilities,
     pub presence: Option<PresenceData>,
+    pub compression: TransportCompression,
+    /// Sessions to resume instead of identifying, see [`ShardManager::session_states`].
+    pub session_states: Vec<SessionState>,
+    /// The gate deciding when shards may IDENTIFY, defaulting to a [`LocalIdentifyGate`].
+    pub identify_gate: Option<Arc<dyn IdentifyGate>>,
+    /// The directory to record the payloads received by each shard to, as `shard-{id}.jsonl`.
+    /// See [`SessionRecorder`].
+    ///
+    /// [`SessionRecorder`]: crate::gateway::SessionRecorder
+    pub recording_dir: Option<PathBuf>,
+    /// The order to call the event handlers of each shard in.
+    pub dispatch_strategy: DispatchStrategy,
+    /// Sends events to subscriptions, such as those of [`Client::subscribe`].
+    ///
+    /// [`Client::subscribe`]: crate::Client::subscribe
+    pub events: Option<EventSender>,
+    /// Limits the handler calls of all shards running at once, if set.
+    pub handler_limit: Option<HandlerLimit>,
 }
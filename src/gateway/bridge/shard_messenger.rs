@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 #[cfg(feature = "collector")]
 use std::sync::Arc;
 
@@ -7,7 +8,7 @@ use tokio_tungstenite::tungstenite::Message;
 #[cfg(feature = "collector")]
 use super::CollectorCallback;
 use super::{ChunkGuildFilter, ShardRunner, ShardRunnerMessage};
-use crate::gateway::ActivityData;
+use crate::gateway::{ActivityData, GuildSubscription};
 use crate::model::prelude::*;
 
 /// A handle to a [`ShardRunner`].
@@ -129,6 +130,50 @@ impl ShardMessenger {
         });
     }
 
+    /// Subscribes to a single guild's events and member list ranges.
+    ///
+    /// This sends the older, single-guild form of [`Self::update_guild_subscriptions`].
+    pub fn update_guild_subscription(&self, guild_id: GuildId, subscription: GuildSubscription) {
+        self.send_to_shard(ShardRunnerMessage::GuildSubscription {
+            guild_id,
+            subscription,
+        });
+    }
+
+    /// Subscribes to the events and member list ranges of any number of guilds at once.
+    ///
+    /// User sessions can't request member chunks for large guilds like bots can. Instead, the
+    /// ranges of each channel's member list that are subscribed to are sent as
+    /// [`Event::GuildMemberListUpdate`]s, and kept up to date until the ranges are changed. If the
+    /// `cache` feature is enabled, the resulting lists can be read with
+    /// [`Cache::channel_member_list`].
+    ///
+    /// # Examples
+    ///
+    /// Subscribing to the first 100 rows of a channel's member list, along with typing events:
+    ///
+    /// ```rust,no_run
+    /// # use serenity::gateway::ShardMessenger;
+    /// # fn run(shard: &ShardMessenger) {
+    /// use std::collections::HashMap;
+    ///
+    /// use serenity::gateway::GuildSubscription;
+    /// use serenity::model::id::{ChannelId, GuildId};
+    ///
+    /// let mut subscription = GuildSubscription::default();
+    /// subscription.typing = Some(true);
+    /// subscription.channels.insert(ChannelId::new(381871767846780928), vec![[0, 99]]);
+    ///
+    /// let guild_id = GuildId::new(381870553235193857);
+    /// shard.update_guild_subscriptions(HashMap::from([(guild_id, subscription)]));
+    /// # }
+    /// ```
+    ///
+    /// [`Cache::channel_member_list`]: crate::cache::Cache::channel_member_list
+    pub fn update_guild_subscriptions(&self, subscriptions: HashMap<GuildId, GuildSubscription>) {
+        self.send_to_shard(ShardRunnerMessage::GuildSubscriptions(subscriptions));
+    }
+
     /// Sets the user's current activity, if any.
     ///
     /// Other presence settings are maintained.
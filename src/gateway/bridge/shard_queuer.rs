@@ -1,18 +1,21 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
 #[cfg(feature = "framework")]
 use std::sync::OnceLock;
 
 use futures::channel::mpsc::UnboundedReceiver as Receiver;
-use futures::StreamExt;
+use futures::future::join_all;
+use futures::{FutureExt, StreamExt};
 use tokio::sync::{Mutex, RwLock};
-use tokio::time::{sleep, timeout, Duration, Instant};
+use tokio::time::{timeout, Duration};
 use tracing::{debug, info, instrument, warn};
 use typemap_rev::TypeMap;
 
 #[cfg(feature = "voice")]
 use super::VoiceGatewayManager;
 use super::{
+    IdentifyGate,
     ShardId,
     ShardManager,
     ShardMessenger,
@@ -23,21 +26,30 @@ use super::{
 };
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
-use crate::client::{EventHandler, RawEventHandler};
+use crate::client::{DispatchStrategy, EventHandler, EventSender, HandlerLimit, RawEventHandler};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::{ConnectionStage, PresenceData, Shard, ShardRunnerMessage};
+use crate::gateway::{
+    ConnectionStage,
+    PresenceData,
+    SessionRecorder,
+    SessionState,
+    Shard,
+    ShardRunnerMessage,
+    TransportCompression,
+    TungsteniteConnector,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
-use crate::model::gateway::{GatewayIntents, ShardInfo};
+use crate::model::gateway::{GatewayCapabilities, GatewayIntents, ShardInfo};
 
 const WAIT_BETWEEN_BOOTS_IN_SECONDS: u64 = 5;
 
 /// The shard queuer is a simple loop that runs indefinitely to manage the startup of shards.
 ///
 /// A shard queuer instance _should_ be run in its own thread, due to the blocking nature of the
-/// loop itself as well as a 5 second thread sleep between shard starts.
+/// loop itself as well as the waits between shard starts imposed by the [`IdentifyGate`].
 pub struct ShardQueuer {
     /// A copy of [`Client::data`] to be given to runners for contextual dispatching.
     ///
@@ -54,10 +66,16 @@ pub struct ShardQueuer {
     /// A copy of the framework
     #[cfg(feature = "framework")]
     pub framework: Arc<OnceLock<Arc<dyn Framework>>>,
-    /// The instant that a shard was last started.
-    ///
-    /// This is used to determine how long to wait between shard IDENTIFYs.
-    pub last_start: Option<Instant>,
+    /// The gate deciding when shards may IDENTIFY.
+    pub identify_gate: Arc<dyn IdentifyGate>,
+    /// The directory to record the payloads received by each shard to, if any.
+    pub recording_dir: Option<PathBuf>,
+    /// The order to call the event handlers in.
+    pub dispatch_strategy: DispatchStrategy,
+    /// Sends events to the subscriptions of the client, if set.
+    pub events: Option<EventSender>,
+    /// Limits the handler calls of all shards running at once, if set.
+    pub handler_limit: Option<HandlerLimit>,
     /// A copy of the [`ShardManager`] to communicate with it.
     pub manager: Arc<ShardManager>,
     /// The shards that are queued for booting.
@@ -77,7 +95,11 @@ pub struct ShardQueuer {
     pub cache: Arc<Cache>,
     pub http: Arc<Http>,
     pub intents: GatewayIntents,
+    pub capabilities: GatewayCapabilities,
     pub presence: Option<PresenceData>,
+    pub compression: TransportCompression,
+    /// Sessions to resume when their shard is first started.
+    pub sessions: HashMap<ShardId, SessionState>,
 }
 
 impl ShardQueuer {
@@ -86,12 +108,9 @@ impl ShardQueuer {
     /// This will loop over the internal [`Self::rx`] for [`ShardQueuerMessage`]s, blocking for
     /// messages on what to do.
     ///
-    /// If a [`ShardQueuerMessage::Start`] is received, this will:
-    ///
-    /// 1. Check how much time has passed since the last shard was started
-    /// 2. If the amount of time is less than the ratelimit, it will sleep until that time has
-    ///    passed
-    /// 3. Start the shard by ID
+    /// If a [`ShardQueuerMessage::Start`] is received, the shard is queued. Queued shards are
+    /// started in batches of at most one shard per rate limit key, `shard_id % max_concurrency`,
+    /// each waiting on the [`Self::identify_gate`] before connecting.
     ///
     /// If a [`ShardQueuerMessage::Shutdown`] is received, this will return and the loop will be
     /// over.
@@ -105,79 +124,157 @@ impl ShardQueuer {
         const TIMEOUT: Duration = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
 
         loop {
-            match timeout(TIMEOUT, self.rx.next()).await {
-                Ok(Some(ShardQueuerMessage::Shutdown)) => {
+            // Take in the messages that are already waiting before starting a batch, so that shards
+            // booted together can be started together.
+            let message = if self.queue.is_empty() {
+                match timeout(TIMEOUT, self.rx.next()).await {
+                    Ok(message) => message,
+                    Err(_) => continue,
+                }
+            } else if let Some(message) = self.rx.next().now_or_never() {
+                message
+            } else {
+                self.start_batch().await;
+                continue;
+            };
+
+            match message {
+                Some(ShardQueuerMessage::Shutdown) => {
                     debug!("[Shard Queuer] Received to shutdown.");
                     self.shutdown_runners().await;
 
                     break;
                 },
-                Ok(Some(ShardQueuerMessage::ShutdownShard(shard, code))) => {
+                Some(ShardQueuerMessage::ShutdownShard(shard, code)) => {
                     debug!("[Shard Queuer] Received to shutdown shard {} with {}.", shard.0, code);
                     self.shutdown(shard, code).await;
                 },
-                Ok(Some(ShardQueuerMessage::Start(id, total))) => {
+                Some(ShardQueuerMessage::Start(id, total)) => {
                     debug!("[Shard Queuer] Received to start shard {} of {}.", id.0, total.0);
-                    self.checked_start(id, total.0).await;
+                    self.queue.push_back(ShardInfo::new(id, total.0));
                 },
-                Ok(None) => break,
-                Err(_) => {
-                    if let Some(shard) = self.queue.pop_front() {
-                        self.checked_start(shard.id, shard.total).await;
-                    }
+                Some(ShardQueuerMessage::SetSessionStartLimit(limit)) => {
+                    debug!("[Shard Queuer] Received session start limit {:?}.", limit);
+                    self.identify_gate.set_session_start_limit(&limit);
                 },
+                None => break,
             }
         }
     }
 
+    /// Starts the queued shards, at most one per rate limit key, in parallel.
     #[instrument(skip(self))]
-    async fn check_last_start(&mut self) {
-        let Some(instant) = self.last_start else { return };
-
-        // We must wait 5 seconds between IDENTIFYs to avoid session invalidations.
-        let duration = Duration::from_secs(WAIT_BETWEEN_BOOTS_IN_SECONDS);
-        let elapsed = instant.elapsed();
+    async fn start_batch(&mut self) {
+        let max_concurrency = self.identify_gate.max_concurrency().max(1);
+
+        let mut keys = HashSet::new();
+        let mut batch = Vec::new();
+        self.queue.retain(|shard_info| {
+            if keys.insert(u64::from(shard_info.id.0) % max_concurrency) {
+                batch.push(*shard_info);
+                false
+            } else {
+                true
+            }
+        });
 
-        if elapsed >= duration {
-            return;
-        }
+        let batch = batch
+            .into_iter()
+            .map(|shard_info| (shard_info, self.sessions.remove(&shard_info.id)))
+            .collect::<Vec<_>>();
+        let starts = batch
+            .into_iter()
+            .map(|(shard_info, session)| self.checked_start(shard_info, max_concurrency, session));
 
-        let to_sleep = duration - elapsed;
+        for failed in join_all(starts).await.into_iter().flatten() {
+            info!("[Shard Queuer] Re-queueing start of shard {}", failed.id);
 
-        sleep(to_sleep).await;
+            self.queue.push_back(failed);
+        }
     }
 
-    #[instrument(skip(self))]
-    async fn checked_start(&mut self, id: ShardId, total: u32) {
+    /// Starts a shard, returning it back if that failed.
+    ///
+    /// A shard given a session resumes it instead of identifying. If that fails, such as when the
+    /// resume gateway URL can no longer be reached, the shard is returned without the session, so
+    /// that it identifies anew once it is started again.
+    #[instrument(skip(self, session))]
+    async fn checked_start(
+        &self,
+        shard_info: ShardInfo,
+        max_concurrency: u64,
+        session: Option<SessionState>,
+    ) -> Option<ShardInfo> {
+        let ShardInfo {
+            id,
+            total,
+        } = shard_info;
         debug!("[Shard Queuer] Checked start for shard {} out of {}", id, total);
-        self.check_last_start().await;
 
-        if let Err(why) = self.start(id, total).await {
+        // Resuming a session doesn't count towards the session start limit.
+        let session = session.filter(|state| state.shard_info.total == total);
+        if session.is_none() {
+            self.identify_gate.acquire(u64::from(id.0) % max_concurrency).await;
+        }
+
+        if let Err(why) = self.start(id, total, session).await {
             warn!("[Shard Queuer] Err starting shard {}: {:?}", id, why);
-            info!("[Shard Queuer] Re-queueing start of shard {}", id);
 
-            self.queue.push_back(ShardInfo::new(id, total));
+            return Some(shard_info);
         }
 
-        self.last_start = Some(Instant::now());
+        None
     }
 
-    #[instrument(skip(self))]
-    async fn start(&mut self, id: ShardId, total: u32) -> Result<()> {
+    #[instrument(skip(self, session))]
+    async fn start(&self, id: ShardId, total: u32, session: Option<SessionState>) -> Result<()> {
         let shard_info = ShardInfo::new(id, total);
 
-        let mut shard = Shard::new(
-            Arc::clone(&self.ws_url),
-            self.http.token(),
-            shard_info,
-            self.intents,
-            self.presence.clone(),
-        )
-        .await?;
+        let ws_url = Arc::clone(&self.ws_url);
+        let token = self.http.token();
+        let presence = self.presence.clone();
+        let mut shard = match session {
+            Some(state) => {
+                let connector = Arc::new(TungsteniteConnector);
+                Shard::from_session(
+                    ws_url,
+                    token,
+                    state,
+                    self.intents,
+                    presence,
+                    self.compression,
+                    connector,
+                )
+                .await?
+            },
+            None => {
+                Shard::new_with_compression(
+                    ws_url,
+                    token,
+                    shard_info,
+                    self.intents,
+                    presence,
+                    self.compression,
+                )
+                .await?
+            },
+        };
+        shard.capabilities = self.capabilities;
+        shard.client_properties = self.http.client_properties().clone();
 
         let cloned_http = Arc::clone(&self.http);
         shard.set_application_id_callback(move |id| cloned_http.set_application_id(id));
 
+        let mut recorder = None;
+        if let Some(dir) = &self.recording_dir {
+            match SessionRecorder::create(dir.join(format!("shard-{id}.jsonl"))).await {
+                Ok(opened) => recorder = Some(opened),
+                Err(why) => {
+                    warn!("[Shard Queuer] Err opening recording for shard {}: {:?}", id, why);
+                },
+            }
+        }
+
         let mut runner = ShardRunner::new(ShardRunnerOptions {
             data: Arc::clone(&self.data),
             event_handlers: self.event_handlers.clone(),
@@ -188,6 +285,10 @@ impl ShardQueuer {
             #[cfg(feature = "voice")]
             voice_manager: self.voice_manager.clone(),
             shard,
+            recorder,
+            dispatch_strategy: self.dispatch_strategy,
+            events: self.events.clone(),
+            handler_limit: self.handler_limit.clone(),
             #[cfg(feature = "cache")]
             cache: Arc::clone(&self.cache),
             http: Arc::clone(&self.http),
@@ -197,6 +298,7 @@ impl ShardQueuer {
             latency: None,
             runner_tx: ShardMessenger::new(&runner),
             stage: ConnectionStage::Disconnected,
+            queued_commands: 0,
         };
 
         spawn_named("shard_queuer::stop", async move {
@@ -1,14 +1,17 @@
 use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::sync::Arc;
 
 use futures::channel::mpsc::{self, UnboundedReceiver as Receiver, UnboundedSender as Sender};
 use tokio::sync::RwLock;
+use tokio::time::{timeout, Duration};
 use tokio_tungstenite::tungstenite;
 use tokio_tungstenite::tungstenite::error::Error as TungsteniteError;
 use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
 use tracing::{debug, error, info, instrument, trace, warn};
 use typemap_rev::TypeMap;
 
+use super::command_limiter::CommandLimiter;
 use super::event::ShardStageUpdateEvent;
 #[cfg(feature = "collector")]
 use super::CollectorCallback;
@@ -17,16 +20,34 @@ use super::VoiceGatewayManager;
 use super::{ShardId, ShardManager, ShardRunnerMessage};
 #[cfg(feature = "cache")]
 use crate::cache::Cache;
-use crate::client::dispatch::dispatch_model;
-use crate::client::{Context, EventHandler, RawEventHandler};
+use crate::client::dispatch::{dispatch_model, Dispatcher};
+use crate::client::{
+    Context,
+    DispatchStrategy,
+    EventHandler,
+    EventSender,
+    HandlerLimit,
+    RawEventHandler,
+};
 #[cfg(feature = "framework")]
 use crate::framework::Framework;
-use crate::gateway::{GatewayError, ReconnectType, Shard, ShardAction};
+use crate::gateway::{
+    ConnectionStage,
+    GatewayError,
+    ReconnectType,
+    SessionRecorder,
+    Shard,
+    ShardAction,
+};
 use crate::http::Http;
 use crate::internal::prelude::*;
 use crate::internal::tokio::spawn_named;
 use crate::model::event::{Event, GatewayEvent};
 
+/// How long to wait for room in the handler backlog before checking for messages and heartbeating
+/// again.
+const ROOM_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// A runner for managing a [`Shard`] and its respective WebSocket client.
 pub struct ShardRunner {
     data: Arc<RwLock<TypeMap>>,
@@ -40,6 +61,12 @@ pub struct ShardRunner {
     // channel to send messages to the shard runner from the shard manager
     runner_tx: Sender<ShardRunnerMessage>,
     pub(crate) shard: Shard,
+    // commands waiting for room under the gateway's outbound limit
+    commands: VecDeque<QueuedCommand>,
+    command_limiter: CommandLimiter,
+    reported_queue_len: usize,
+    recorder: Option<SessionRecorder>,
+    dispatcher: Dispatcher,
     #[cfg(feature = "voice")]
     voice_manager: Option<Arc<dyn VoiceGatewayManager + 'static>>,
     #[cfg(feature = "cache")]
@@ -64,6 +91,11 @@ impl ShardRunner {
             framework: opt.framework,
             manager: opt.manager,
             shard: opt.shard,
+            commands: VecDeque::new(),
+            command_limiter: CommandLimiter::new(),
+            reported_queue_len: 0,
+            recorder: opt.recorder,
+            dispatcher: Dispatcher::new(opt.dispatch_strategy, opt.events, opt.handler_limit),
             #[cfg(feature = "voice")]
             voice_manager: opt.voice_manager,
             #[cfg(feature = "cache")]
@@ -79,7 +111,8 @@ impl ShardRunner {
     /// This runs a loop that performs the following in each iteration:
     ///
     /// 1. checks the receiver for [`ShardRunnerMessage`]s, possibly from the [`ShardManager`], and
-    ///    if there is one, acts on it.
+    ///    if there is one, acts on it. Gateway commands are queued and sent as the outbound rate
+    ///    limit allows, with presence updates coalesced into the latest one.
     ///
     /// 2. checks if a heartbeat should be sent to the discord Gateway, and if so, sends one.
     ///
@@ -100,7 +133,7 @@ impl ShardRunner {
 
         loop {
             trace!("[ShardRunner {:?}] loop iteration started.", self.shard.shard_info());
-            if !self.recv().await {
+            if !self.recv().await || !self.send_queued_commands().await {
                 return Ok(());
             }
 
@@ -112,6 +145,14 @@ impl ShardRunner {
                 return Ok(());
             }
 
+            // Leave events waiting on the connection while the handlers can't keep up, except
+            // for the acknowledgement of a heartbeat, without which the shard would reconnect.
+            if self.shard.last_heartbeat_acknowledged()
+                && timeout(ROOM_TIMEOUT, self.dispatcher.wait_for_room()).await.is_err()
+            {
+                continue;
+            }
+
             let pre = self.shard.stage();
             let (event, action, successful) = self.recv_event().await?;
             let post = self.shard.stage();
@@ -179,6 +220,7 @@ impl ShardRunner {
                     self.framework.clone(),
                     self.event_handlers.clone(),
                     self.raw_event_handlers.clone(),
+                    &self.dispatcher,
                 );
             }
 
@@ -230,6 +272,10 @@ impl ShardRunner {
             return true;
         }
 
+        // Closing with 1000 or 1001 invalidates the session, otherwise it can be resumed later.
+        let state = self.shard.session_state().filter(|_| !matches!(close_code, 1000 | 1001));
+        self.manager.update_session_state(id, state).await;
+
         // Send a Close Frame to Discord, which allows a bot to "log off"
         drop(
             self.shard
@@ -284,17 +330,6 @@ impl ShardRunner {
         match msg {
             ShardRunnerMessage::Restart(id) => self.checked_shutdown(id, 4000).await,
             ShardRunnerMessage::Shutdown(id, code) => self.checked_shutdown(id, code).await,
-            ShardRunnerMessage::ChunkGuild {
-                guild_id,
-                limit,
-                presences,
-                filter,
-                nonce,
-            } => self
-                .shard
-                .chunk_guild(guild_id, limit, presences, filter, nonce.as_deref())
-                .await
-                .is_ok(),
             ShardRunnerMessage::Close(code, reason) => {
                 let reason = reason.unwrap_or_default();
                 let close = CloseFrame {
@@ -303,19 +338,98 @@ impl ShardRunner {
                 };
                 self.shard.client.close(Some(close)).await.is_ok()
             },
-            ShardRunnerMessage::Message(msg) => self.shard.client.send(msg).await.is_ok(),
             ShardRunnerMessage::SetActivity(activity) => {
                 self.shard.set_activity(activity);
-                self.shard.update_presence().await.is_ok()
+                self.queue_presence_update();
+                true
             },
             ShardRunnerMessage::SetPresence(activity, status) => {
                 self.shard.set_presence(activity, status);
-                self.shard.update_presence().await.is_ok()
+                self.queue_presence_update();
+                true
             },
             ShardRunnerMessage::SetStatus(status) => {
                 self.shard.set_status(status);
-                self.shard.update_presence().await.is_ok()
+                self.queue_presence_update();
+                true
+            },
+            msg @ (ShardRunnerMessage::ChunkGuild {
+                ..
+            }
+            | ShardRunnerMessage::GuildSubscription {
+                ..
+            }
+            | ShardRunnerMessage::GuildSubscriptions(_)
+            | ShardRunnerMessage::Message(_)) => {
+                self.commands.push_back(QueuedCommand::Message(msg));
+                true
+            },
+        }
+    }
+
+    // Queues an update of the shard's presence, unless one is queued already. As the presence is
+    // read from the shard when sending, that one will carry the latest changes.
+    fn queue_presence_update(&mut self) {
+        if !self.commands.iter().any(|command| matches!(command, QueuedCommand::Presence)) {
+            self.commands.push_back(QueuedCommand::Presence);
+        }
+    }
+
+    // Sends queued commands for as long as the outbound rate limit allows, once the shard is
+    // connected.
+    //
+    // Returns whether the shard runner can continue, which is only false if sending failed.
+    #[instrument(skip(self))]
+    async fn send_queued_commands(&mut self) -> bool {
+        if self.shard.stage() == ConnectionStage::Connected {
+            self.command_limiter.set_heartbeat_interval(self.shard.heartbeat_interval());
+
+            while !self.commands.is_empty() && self.command_limiter.try_acquire() {
+                let sent = match self.commands.pop_front() {
+                    Some(QueuedCommand::Presence) => self.shard.update_presence().await.is_ok(),
+                    Some(QueuedCommand::Message(msg)) => self.send_command(msg).await,
+                    None => true,
+                };
+
+                if !sent {
+                    return false;
+                }
+            }
+        }
+
+        if self.commands.len() != self.reported_queue_len {
+            self.reported_queue_len = self.commands.len();
+            let id = self.shard.shard_info().id;
+            self.manager.update_shard_queued_commands(id, self.reported_queue_len).await;
+        }
+
+        true
+    }
+
+    // Sends a queued command, returning whether that was successful.
+    async fn send_command(&mut self, msg: ShardRunnerMessage) -> bool {
+        match msg {
+            ShardRunnerMessage::ChunkGuild {
+                guild_id,
+                limit,
+                presences,
+                filter,
+                nonce,
+            } => self
+                .shard
+                .chunk_guild(guild_id, limit, presences, filter, nonce.as_deref())
+                .await
+                .is_ok(),
+            ShardRunnerMessage::GuildSubscription {
+                guild_id,
+                subscription,
+            } => self.shard.update_guild_subscription(guild_id, &subscription).await.is_ok(),
+            ShardRunnerMessage::GuildSubscriptions(subscriptions) => {
+                self.shard.update_guild_subscriptions(&subscriptions).await.is_ok()
             },
+            ShardRunnerMessage::Message(msg) => self.shard.client.send(msg).await.is_ok(),
+            // Only the messages above are queued.
+            _ => true,
         }
     }
 
@@ -382,7 +496,7 @@ impl ShardRunner {
     /// successful.
     #[instrument(skip(self))]
     async fn recv_event(&mut self) -> Result<(Option<Event>, Option<ShardAction>, bool)> {
-        let gw_event = match self.shard.client.recv_json().await {
+        let gw_event = match self.shard.client.recv_json(self.recorder.as_mut()).await {
             Ok(inner) => Ok(inner),
             Err(Error::Tungstenite(TungsteniteError::Io(_))) => {
                 debug!("Attempting to auto-reconnect");
@@ -469,16 +583,22 @@ impl ShardRunner {
 
     #[instrument(skip(self))]
     async fn update_manager(&self) {
+        let id = self.shard.shard_info().id;
         self.manager
-            .update_shard_latency_and_stage(
-                self.shard.shard_info().id,
-                self.shard.latency(),
-                self.shard.stage(),
-            )
+            .update_shard_latency_and_stage(id, self.shard.latency(), self.shard.stage())
             .await;
+        self.manager.update_session_state(id, self.shard.session_state()).await;
     }
 }
 
+/// A gateway command waiting for room under the outbound rate limit.
+#[derive(Debug)]
+enum QueuedCommand {
+    /// An update of the shard's presence, sending whatever it is at that point.
+    Presence,
+    Message(ShardRunnerMessage),
+}
+
 /// Options to be passed to [`ShardRunner::new`].
 pub struct ShardRunnerOptions {
     pub data: Arc<RwLock<TypeMap>>,
@@ -488,6 +608,14 @@ pub struct ShardRunnerOptions {
     pub framework: Option<Arc<dyn Framework>>,
     pub manager: Arc<ShardManager>,
     pub shard: Shard,
+    /// Records the payloads received by the shard, if set.
+    pub recorder: Option<SessionRecorder>,
+    /// The order to call the event handlers in.
+    pub dispatch_strategy: DispatchStrategy,
+    /// Sends the events of the shard to the subscriptions of the client, if set.
+    pub events: Option<EventSender>,
+    /// Limits the handler calls running at once, shared with the other shards of the client.
+    pub handler_limit: Option<HandlerLimit>,
     #[cfg(feature = "voice")]
     pub voice_manager: Option<Arc<dyn VoiceGatewayManager>>,
     #[cfg(feature = "cache")]
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use tokio_tungstenite::tungstenite::Message;
 
 use super::ShardId;
-use crate::gateway::{ActivityData, ChunkGuildFilter};
+use crate::gateway::{ActivityData, ChunkGuildFilter, GuildSubscription};
 use crate::model::id::GuildId;
 use crate::model::user::OnlineStatus;
 
@@ -34,6 +36,18 @@ pub enum ShardRunnerMessage {
         /// [`GuildMembersChunkEvent`]: crate::model::event::GuildMembersChunkEvent
         nonce: Option<String>,
     },
+    /// Indicates that the client is to subscribe to a single guild's events and member list.
+    GuildSubscription {
+        /// The Id of the [`Guild`] to subscribe to.
+        ///
+        /// [`Guild`]: crate::model::guild::Guild
+        guild_id: GuildId,
+        /// What to subscribe to.
+        subscription: GuildSubscription,
+    },
+    /// Indicates that the client is to subscribe to the events and member lists of several
+    /// guilds at once.
+    GuildSubscriptions(HashMap<GuildId, GuildSubscription>),
     /// Indicates that the client is to close with the given status code and reason.
     ///
     /// You should rarely - if _ever_ - need this, but the option is available. Prefer to use the
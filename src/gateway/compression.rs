@@ -0,0 +1,232 @@
+//! Decompression of the binary messages received from the gateway.
+
+use std::io::{Error as IoError, ErrorKind, Read};
+
+use flate2::read::ZlibDecoder;
+use flate2::{Decompress, FlushDecompress, Status};
+#[cfg(feature = "zstd")]
+use zstd_safe::{DCtx, InBuffer, OutBuffer};
+
+use super::TransportCompression;
+
+const DECOMPRESSION_MULTIPLIER: usize = 3;
+/// The suffix of the last frame of each message sent over a zlib-stream connection, left by the
+/// `Z_SYNC_FLUSH` it ends with.
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+/// Decompresses binary messages according to the [`TransportCompression`] of a connection.
+pub(crate) enum Decompressor {
+    /// Large payloads are compressed one by one, each as a zlib stream of its own.
+    Payload(Vec<u8>),
+    Zlib(ZlibInflater),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdDecoder),
+}
+
+impl Decompressor {
+    pub fn new(compression: TransportCompression) -> Self {
+        match compression {
+            TransportCompression::None => Self::Payload(Vec::new()),
+            TransportCompression::Zlib => Self::Zlib(ZlibInflater::new()),
+            #[cfg(feature = "zstd")]
+            TransportCompression::Zstd => Self::Zstd(ZstdDecoder::new()),
+        }
+    }
+
+    /// Decompresses a binary message, returning [`None`] if it is only part of one.
+    pub fn decompress(&mut self, message: &[u8]) -> Result<Option<&[u8]>, IoError> {
+        match self {
+            Self::Payload(decompressed) => {
+                decompressed.clear();
+                decompressed.reserve(message.len() * DECOMPRESSION_MULTIPLIER);
+                ZlibDecoder::new(message).read_to_end(decompressed)?;
+                Ok(Some(decompressed))
+            },
+            Self::Zlib(inflater) => inflater.inflate(message),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(decoder) => decoder.decode(message).map(Some),
+        }
+    }
+}
+
+/// The inflate context of a zlib-stream connection, which every message depends on.
+pub(crate) struct ZlibInflater {
+    decompress: Decompress,
+    /// Frames of a message that hasn't been received in full yet.
+    compressed: Vec<u8>,
+    /// The last decompressed message, kept around to reuse its allocation.
+    decompressed: Vec<u8>,
+}
+
+impl ZlibInflater {
+    fn new() -> Self {
+        Self {
+            decompress: Decompress::new(true),
+            compressed: Vec::new(),
+            decompressed: Vec::new(),
+        }
+    }
+
+    /// Feeds a frame into the stream, returning the decompressed message once its last frame has
+    /// been received.
+    fn inflate(&mut self, frame: &[u8]) -> Result<Option<&[u8]>, IoError> {
+        // Messages nearly always fit in a single frame, which can then be inflated in place.
+        let input = if self.compressed.is_empty() && frame.ends_with(&ZLIB_SUFFIX) {
+            frame
+        } else {
+            self.compressed.extend_from_slice(frame);
+            if !self.compressed.ends_with(&ZLIB_SUFFIX) {
+                return Ok(None);
+            }
+            &self.compressed
+        };
+
+        let result = inflate_into(&mut self.decompress, input, &mut self.decompressed);
+        self.compressed.clear();
+        result?;
+
+        Ok(Some(&self.decompressed))
+    }
+}
+
+/// Inflates a flushed chunk of a zlib stream, replacing the contents of `output`.
+fn inflate_into(
+    decompress: &mut Decompress,
+    mut input: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), IoError> {
+    output.clear();
+
+    loop {
+        if output.len() == output.capacity() {
+            output.reserve(input.len().max(1024));
+        }
+
+        let total_in = decompress.total_in();
+        let status = decompress.decompress_vec(input, output, FlushDecompress::Sync)?;
+        #[allow(clippy::cast_possible_truncation)]
+        let consumed = (decompress.total_in() - total_in) as usize;
+        input = &input[consumed..];
+
+        // Keep going while there's input left, or output that didn't fit.
+        let pending = !input.is_empty() || output.len() == output.capacity();
+        match status {
+            Status::Ok if pending => {},
+            // A BufError means nothing was left to inflate after filling the output exactly.
+            Status::Ok | Status::StreamEnd | Status::BufError if input.is_empty() => return Ok(()),
+            Status::Ok | Status::StreamEnd | Status::BufError => {
+                let error = "zlib stream ended or stalled in the middle of a message";
+                return Err(IoError::new(ErrorKind::InvalidData, error));
+            },
+        }
+    }
+}
+
+/// The decompression context of a zstd-stream connection, which every message depends on.
+#[cfg(feature = "zstd")]
+pub(crate) struct ZstdDecoder {
+    context: DCtx<'static>,
+    /// The last decompressed message, kept around to reuse its allocation.
+    decompressed: Vec<u8>,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDecoder {
+    fn new() -> Self {
+        Self {
+            context: DCtx::create(),
+            decompressed: Vec::new(),
+        }
+    }
+
+    /// Decompresses a message, each of which is flushed in full by the gateway.
+    fn decode(&mut self, message: &[u8]) -> Result<&[u8], IoError> {
+        self.decompressed.clear();
+        let mut input = InBuffer::around(message);
+
+        loop {
+            if self.decompressed.len() == self.decompressed.capacity() {
+                self.decompressed.reserve((message.len() * DECOMPRESSION_MULTIPLIER).max(1024));
+            }
+
+            let pos = self.decompressed.len();
+            let mut output = OutBuffer::around_pos(&mut self.decompressed, pos);
+            self.context.decompress_stream(&mut output, &mut input).map_err(|code| {
+                IoError::new(ErrorKind::InvalidData, zstd_safe::get_error_name(code))
+            })?;
+            let full = output.pos() == output.capacity();
+
+            // Keep going while there's input left, or output that didn't fit.
+            if input.pos() == message.len() && !full {
+                return Ok(&self.decompressed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{Compress, Compression, FlushCompress};
+
+    use super::*;
+
+    fn compress(compress: &mut Compress, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(input.len() + 64);
+        compress.compress_vec(input, &mut output, FlushCompress::Sync).unwrap();
+        output
+    }
+
+    #[test]
+    fn zlib_stream() {
+        let mut compressor = Compress::new(Compression::default(), true);
+        let mut inflater = ZlibInflater::new();
+
+        let first = compress(&mut compressor, br#"{"op":11}"#);
+        assert!(first.ends_with(&ZLIB_SUFFIX));
+        assert_eq!(inflater.inflate(&first).unwrap().unwrap(), br#"{"op":11}"#);
+
+        // Later messages depend on the earlier ones, and may be split across frames.
+        let message = r#"{"op":0,"d":"x"}"#.repeat(1000);
+        let second = compress(&mut compressor, message.as_bytes());
+        let (head, tail) = second.split_at(second.len() / 2);
+        assert!(inflater.inflate(head).unwrap().is_none());
+        assert_eq!(inflater.inflate(tail).unwrap().unwrap(), message.as_bytes());
+    }
+
+    #[test]
+    fn zlib_stream_end() {
+        let mut compressor = Compress::new(Compression::default(), true);
+        let mut message = Vec::with_capacity(64);
+        compressor.compress_vec(b"{}", &mut message, FlushCompress::Finish).unwrap();
+        message.extend_from_slice(&ZLIB_SUFFIX);
+
+        // Data after the end of the stream is an error rather than part of the message.
+        let error = ZlibInflater::new().inflate(&message).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_stream() {
+        use zstd_safe::zstd_sys::ZSTD_EndDirective;
+        use zstd_safe::CCtx;
+
+        let mut compressor = CCtx::create();
+        let mut compress = |input: &[u8]| {
+            let mut output = Vec::with_capacity(input.len() + 64);
+            let mut out_buffer = OutBuffer::around(&mut output);
+            let mut in_buffer = InBuffer::around(input);
+            let flush = ZSTD_EndDirective::ZSTD_e_flush;
+            compressor.compress_stream2(&mut out_buffer, &mut in_buffer, flush).unwrap();
+            output
+        };
+        let mut decoder = ZstdDecoder::new();
+
+        let first = compress(br#"{"op":11}"#);
+        assert_eq!(decoder.decode(&first).unwrap(), br#"{"op":11}"#);
+
+        let message = r#"{"op":0,"d":"x"}"#.repeat(1000);
+        let second = compress(message.as_bytes());
+        assert_eq!(decoder.decode(&second).unwrap(), message.as_bytes());
+    }
+}
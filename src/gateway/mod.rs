@@ -40,10 +40,15 @@
 //! [docs]: https://discordapp.com/developers/docs/topics/gateway#sharding
 
 mod bridge;
+#[cfg(feature = "client")]
+mod compression;
 mod error;
+#[cfg(feature = "client")]
+mod recording;
 mod shard;
 mod ws;
 
+use std::collections::HashMap;
 use std::fmt;
 
 #[cfg(feature = "http")]
@@ -52,12 +57,14 @@ use reqwest::Url;
 
 pub use self::bridge::*;
 pub use self::error::Error as GatewayError;
+#[cfg(feature = "client")]
+pub use self::recording::{RecordedPayload, SessionRecorder, SessionReplay, SessionReplayOptions};
 pub use self::shard::Shard;
-pub use self::ws::WsClient;
+pub use self::ws::{GatewayConnector, TungsteniteConnector, WsClient, WsTransport};
 #[cfg(feature = "http")]
 use crate::internal::prelude::*;
-use crate::model::gateway::{Activity, ActivityType};
-use crate::model::id::UserId;
+use crate::model::gateway::{Activity, ActivityType, ShardInfo};
+use crate::model::id::{ChannelId, UserId};
 use crate::model::user::OnlineStatus;
 
 /// Presence data of the current user.
@@ -69,6 +76,28 @@ pub struct PresenceData {
     pub status: OnlineStatus,
 }
 
+/// What a shard needs to resume its gateway session, e.g. after a restart of the process.
+///
+/// Export it with [`Shard::session_state`] or [`ShardManager::session_states`], and feed it back in
+/// with [`Shard::restore_session`] or [`ShardManagerOptions::session_states`].
+///
+/// **Note**: Closing the connection with close code 1000 or 1001 invalidates the session, so shut
+/// shards down with another code, e.g. 4000, to be able to resume them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct SessionState {
+    /// The Id of the session.
+    pub session_id: String,
+    /// The sequence number of the last dispatch received.
+    pub seq: u64,
+    /// The URL to resume the session at, given in [`Ready::resume_gateway_url`].
+    ///
+    /// [`Ready::resume_gateway_url`]: crate::model::gateway::Ready::resume_gateway_url
+    pub resume_gateway_url: Option<String>,
+    /// The shard the session belongs to.
+    pub shard_info: ShardInfo,
+}
+
 /// Activity data of the current user.
 #[derive(Clone, Debug, Serialize)]
 pub struct ActivityData {
@@ -268,3 +297,67 @@ pub enum ChunkGuildFilter {
     /// Will return a maximum of 100 members.
     UserIds(Vec<UserId>),
 }
+
+/// The compression applied to the whole gateway connection.
+///
+/// [Discord docs](https://discord.com/developers/docs/topics/gateway#transport-compression).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum TransportCompression {
+    /// No transport compression. Large payloads are still compressed one by one.
+    #[default]
+    None,
+    /// A single zlib stream shared by every message of the connection.
+    Zlib,
+    /// A single zstd stream shared by every message of the connection, which takes less CPU to
+    /// decompress than [`Self::Zlib`].
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl TransportCompression {
+    /// The value of the `compress` query parameter of the gateway URL, if any.
+    pub(crate) fn query_value(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Zlib => Some("zlib-stream"),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Some("zstd-stream"),
+        }
+    }
+}
+
+/// What a user session wants to receive for a guild, sent with
+/// [`ShardMessenger::update_guild_subscriptions`].
+///
+/// Fields left as [`None`] or empty keep whatever was subscribed to previously.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#guild-subscriptions-structure).
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GuildSubscription {
+    /// Whether to receive typing events for all channels in the guild.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typing: Option<bool>,
+    /// Whether to receive presence updates for members with activities.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activities: Option<bool>,
+    /// Whether to receive thread events for all threads in the guild.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<bool>,
+    /// Whether to receive member add and remove events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member_updates: Option<bool>,
+    /// Members to receive member and presence updates for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<UserId>,
+    /// Inclusive ranges of each channel's member list to receive
+    /// [`GuildMemberListUpdateEvent`]s for, usually in chunks of 100 such as `[[0, 99], [100,
+    /// 199]]`.
+    ///
+    /// [`GuildMemberListUpdateEvent`]: crate::model::event::GuildMemberListUpdateEvent
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub channels: HashMap<ChannelId, Vec<[u32; 2]>>,
+    /// Threads to receive thread member list updates for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thread_member_lists: Vec<ChannelId>,
+}
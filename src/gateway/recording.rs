@@ -0,0 +1,413 @@
+//! Recording of the payloads received from the gateway, and replaying them without a connection.
+
+use std::io::Error as IoError;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::mpsc::{self, UnboundedReceiver as Receiver};
+use futures::{Sink, Stream};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{sleep_until, Instant};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tracing::warn;
+use typemap_rev::TypeMap;
+use url::Url;
+
+use super::{
+    GatewayConnector,
+    Shard,
+    ShardMessenger,
+    ShardRunnerMessage,
+    TransportCompression,
+    WsTransport,
+};
+#[cfg(feature = "cache")]
+use crate::cache::Cache;
+use crate::client::dispatch::{dispatch_model, Dispatcher};
+use crate::client::{
+    Context,
+    DispatchStrategy,
+    EventHandler,
+    EventSender,
+    HandlerLimit,
+    RawEventHandler,
+};
+#[cfg(feature = "framework")]
+use crate::framework::Framework;
+use crate::http::Http;
+use crate::internal::prelude::*;
+use crate::json::{from_str, to_string};
+use crate::model::event::GatewayEvent;
+use crate::model::gateway::{GatewayIntents, ShardInfo};
+
+/// A payload received from the gateway, as written by a [`SessionRecorder`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RecordedPayload {
+    /// The milliseconds between the start of the recording and receiving the payload.
+    pub elapsed_ms: u64,
+    /// The sequence number of the payload, if it is a dispatch.
+    pub seq: Option<u64>,
+    /// The JSON payload, as received after decompression.
+    pub payload: String,
+}
+
+impl RecordedPayload {
+    #[must_use]
+    pub fn new(elapsed_ms: u64, seq: Option<u64>, payload: String) -> Self {
+        Self {
+            elapsed_ms,
+            seq,
+            payload,
+        }
+    }
+}
+
+/// Writes every payload received by a shard to a file, one [`RecordedPayload`] per line.
+///
+/// Recordings are appended to, so a shard restarting continues its file. Replay them with a
+/// [`SessionReplay`].
+#[derive(Debug)]
+pub struct SessionRecorder {
+    file: File,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Opens the file to record to, creating it if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    pub async fn create(path: impl AsRef<Path>) -> StdResult<Self, IoError> {
+        let file = OpenOptions::new().create(true).append(true).open(path).await?;
+
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    pub(crate) async fn record(&mut self, payload: &str, seq: Option<u64>) {
+        let elapsed_ms = u64::try_from(self.started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let record = RecordedPayload::new(elapsed_ms, seq, payload.to_string());
+
+        let result = match to_string(&record) {
+            Ok(mut line) => {
+                line.push('\n');
+                self.write(line.as_bytes()).await.map_err(Error::from)
+            },
+            Err(why) => Err(why),
+        };
+
+        if let Err(why) = result {
+            warn!("Err recording gateway payload: {:?}", why);
+        }
+    }
+
+    async fn write(&mut self, line: &[u8]) -> StdResult<(), IoError> {
+        self.file.write_all(line).await?;
+        // Writes of a tokio file only finish in the background otherwise.
+        self.file.flush().await
+    }
+}
+
+/// Options to be passed to [`SessionReplay::new`].
+pub struct SessionReplayOptions {
+    pub data: Arc<RwLock<TypeMap>>,
+    pub event_handlers: Vec<Arc<dyn EventHandler>>,
+    pub raw_event_handlers: Vec<Arc<dyn RawEventHandler>>,
+    #[cfg(feature = "framework")]
+    pub framework: Option<Arc<dyn Framework>>,
+    pub shard_info: ShardInfo,
+    pub intents: GatewayIntents,
+    pub dispatch_strategy: DispatchStrategy,
+    pub events: Option<EventSender>,
+    pub handler_limit: Option<HandlerLimit>,
+    #[cfg(feature = "cache")]
+    pub cache: Arc<Cache>,
+    pub http: Arc<Http>,
+}
+
+/// Feeds recorded payloads through a [`Shard`], the cache and the event handlers, the same way a
+/// [`ShardRunner`] does with live ones.
+///
+/// The shard isn't connected to anything, so the actions it asks for, such as heartbeating or
+/// reconnecting, aren't taken, and messages sent through the [`Context::shard`] are dropped.
+///
+/// [`ShardRunner`]: super::ShardRunner
+pub struct SessionReplay {
+    data: Arc<RwLock<TypeMap>>,
+    event_handlers: Vec<Arc<dyn EventHandler>>,
+    raw_event_handlers: Vec<Arc<dyn RawEventHandler>>,
+    #[cfg(feature = "framework")]
+    framework: Option<Arc<dyn Framework>>,
+    shard: Shard,
+    messenger: ShardMessenger,
+    // Kept so that messages sent to the shard are dropped rather than failing.
+    _runner_rx: Receiver<ShardRunnerMessage>,
+    dispatcher: Dispatcher,
+    #[cfg(feature = "cache")]
+    cache: Arc<Cache>,
+    http: Arc<Http>,
+}
+
+impl SessionReplay {
+    /// Creates a replay with a shard that isn't connected to the gateway.
+    ///
+    /// # Errors
+    ///
+    /// Never errors in practice, as the shard doesn't connect to anything.
+    pub async fn new(opt: SessionReplayOptions) -> Result<Self> {
+        let shard = Shard::new_with_connector(
+            Arc::new(Mutex::new("wss://gateway.invalid".to_string())),
+            "",
+            opt.shard_info,
+            opt.intents,
+            None,
+            TransportCompression::None,
+            Arc::new(OfflineConnector),
+        )
+        .await?;
+        let (tx, rx) = mpsc::unbounded();
+
+        Ok(Self {
+            data: opt.data,
+            event_handlers: opt.event_handlers,
+            raw_event_handlers: opt.raw_event_handlers,
+            #[cfg(feature = "framework")]
+            framework: opt.framework,
+            shard,
+            messenger: ShardMessenger {
+                tx,
+                #[cfg(feature = "collector")]
+                collectors: Arc::new(std::sync::Mutex::new(vec![])),
+            },
+            _runner_rx: rx,
+            dispatcher: Dispatcher::new(opt.dispatch_strategy, opt.events, opt.handler_limit),
+            #[cfg(feature = "cache")]
+            cache: opt.cache,
+            http: opt.http,
+        })
+    }
+
+    /// Returns the shard the payloads are fed through.
+    #[must_use]
+    pub fn shard(&self) -> &Shard {
+        &self.shard
+    }
+
+    /// Replays every payload of a file written by a [`SessionRecorder`], in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, or a line or payload can't be deserialized.
+    pub async fn replay_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.replay_lines(path.as_ref(), false).await
+    }
+
+    /// Replays every payload of a file like [`Self::replay_file`], but waits for the time at which
+    /// each payload was received, relative to the start of the replay.
+    ///
+    /// Payloads received earlier than the time already spent replaying, such as the ones of a
+    /// shard that restarted and continued its recording, are replayed straight away.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, or a line or payload can't be deserialized.
+    pub async fn replay_file_timed(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.replay_lines(path.as_ref(), true).await
+    }
+
+    async fn replay_lines(&mut self, path: &Path, timed: bool) -> Result<()> {
+        let started = Instant::now();
+        let mut lines = BufReader::new(File::open(path).await?).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: RecordedPayload = from_str(line)?;
+            if timed {
+                sleep_until(started + Duration::from_millis(record.elapsed_ms)).await;
+            }
+            self.dispatcher.wait_for_room().await;
+            self.replay(&record).map_err(|e| *e)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replays a single payload.
+    ///
+    /// Updates of the cache are done once this returns, while the event handlers are spawned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload can't be deserialized, or the shard fails to handle it.
+    pub fn replay(&mut self, record: &RecordedPayload) -> StdResult<(), Box<Error>> {
+        let event = from_str::<GatewayEvent>(record.payload.as_str());
+        self.shard.handle_event(&event)?;
+
+        if let Ok(GatewayEvent::Dispatch(_, event)) = event {
+            #[cfg(feature = "collector")]
+            self.messenger
+                .collectors
+                .lock()
+                .expect("poison")
+                .retain_mut(|callback| (callback.0)(&event));
+
+            let context = Context {
+                data: Arc::clone(&self.data),
+                shard: self.messenger.clone(),
+                shard_id: self.shard.shard_info().id,
+                http: Arc::clone(&self.http),
+                #[cfg(feature = "cache")]
+                cache: Arc::clone(&self.cache),
+            };
+
+            dispatch_model(
+                event,
+                &context,
+                #[cfg(feature = "framework")]
+                self.framework.clone(),
+                self.event_handlers.clone(),
+                self.raw_event_handlers.clone(),
+                &self.dispatcher,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens connections on which nothing is ever received.
+struct OfflineConnector;
+
+#[async_trait]
+impl GatewayConnector for OfflineConnector {
+    async fn connect(&self, _: Url) -> Result<Box<dyn WsTransport>> {
+        Ok(Box::new(OfflineTransport))
+    }
+}
+
+/// A connection on which nothing is ever received, and anything sent is dropped.
+struct OfflineTransport;
+
+impl Stream for OfflineTransport {
+    type Item = StdResult<Message, WsError>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+impl Sink<Message> for OfflineTransport {
+    type Error = WsError;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<StdResult<(), WsError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, _: Message) -> StdResult<(), WsError> {
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<StdResult<(), WsError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<StdResult<(), WsError>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(test, feature = "cache"))]
+mod tests {
+    use super::*;
+    use crate::client::{EventSubscription, FullEvent, Lagged};
+    use crate::json::json;
+    use crate::model::gateway::{ready_json, user_json};
+    use crate::model::id::{ChannelId, ShardId, UserId};
+
+    async fn session_replay(cache: &Arc<Cache>, events: Option<EventSender>) -> SessionReplay {
+        SessionReplay::new(SessionReplayOptions {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+            event_handlers: vec![],
+            raw_event_handlers: vec![],
+            #[cfg(feature = "framework")]
+            framework: None,
+            shard_info: ShardInfo::new(ShardId(0), 1),
+            intents: GatewayIntents::empty(),
+            dispatch_strategy: DispatchStrategy::Concurrent,
+            events,
+            handler_limit: None,
+            cache: Arc::clone(cache),
+            http: Arc::new(Http::new("")),
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("serenity-{}.jsonl", std::process::id()));
+        let mut recorder = SessionRecorder::create(&path).await.unwrap();
+
+        let ready = ready_json([
+            ("private_channels", json!([{"id": "3", "type": 1, "recipient_ids": ["2"]}])),
+            ("users", json!([user_json("2")])),
+        ]);
+        let ready = json!({"op": 0, "s": 1, "t": "READY", "d": ready});
+        recorder.record(&ready.to_string(), Some(1)).await;
+        recorder.record(&json!({"op": 11}).to_string(), None).await;
+        drop(recorder);
+
+        let cache = Arc::new(Cache::new());
+        let (events, rx) = tokio::sync::broadcast::channel(1);
+        let mut subscription = EventSubscription::new(rx);
+        let mut replay = session_replay(&cache, Some(events)).await;
+        let result = replay.replay_file(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        assert_eq!(replay.shard().seq(), 1);
+        assert_eq!(replay.shard().session_id().map(String::as_str), Some("session"));
+        assert_eq!(cache.current_user().id, UserId::new(1));
+        assert!(cache.private_channel(ChannelId::new(3)).is_some());
+
+        // READY is followed by CacheReady and ShardsReady, the last of which is left in the
+        // single slot of the buffer.
+        assert_eq!(subscription.recv().await.unwrap().unwrap_err(), Lagged(2));
+        let (_, event) = subscription.recv().await.unwrap().unwrap();
+        assert!(matches!(event, FullEvent::ShardsReady { .. }));
+    }
+
+    #[tokio::test]
+    async fn replay_timed() {
+        let path =
+            std::env::temp_dir().join(format!("serenity-timed-{}.jsonl", std::process::id()));
+        let ack = json!({"op": 11}).to_string();
+        let lines =
+            [RecordedPayload::new(0, None, ack.clone()), RecordedPayload::new(50, None, ack)]
+                .iter()
+                .map(|record| to_string(record).unwrap() + "\n")
+                .collect::<String>();
+        std::fs::write(&path, lines).unwrap();
+
+        let mut replay = session_replay(&Arc::new(Cache::new()), None).await;
+        let started = Instant::now();
+        let result = replay.replay_file_timed(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration as StdDuration, Instant};
 
@@ -11,16 +12,21 @@ use super::{
     ActivityData,
     ChunkGuildFilter,
     ConnectionStage,
+    GatewayConnector,
     GatewayError,
+    GuildSubscription,
     PresenceData,
     ReconnectType,
+    SessionState,
     ShardAction,
+    TransportCompression,
+    TungsteniteConnector,
     WsClient,
 };
 use crate::constants::{self, close_codes};
 use crate::internal::prelude::*;
 use crate::model::event::{Event, GatewayEvent};
-use crate::model::gateway::{GatewayIntents, ShardInfo};
+use crate::model::gateway::{ClientProperties, GatewayCapabilities, GatewayIntents, ShardInfo};
 use crate::model::id::{ApplicationId, GuildId};
 use crate::model::user::OnlineStatus;
 
@@ -67,6 +73,7 @@ pub struct Shard {
     last_heartbeat_acknowledged: bool,
     seq: u64,
     session_id: Option<String>,
+    resume_ws_url: Option<String>,
     shard_info: ShardInfo,
     stage: ConnectionStage,
     /// Instant of when the shard was started.
@@ -76,6 +83,13 @@ pub struct Shard {
     pub token: String,
     ws_url: Arc<Mutex<String>>,
     pub intents: GatewayIntents,
+    /// The capabilities sent when identifying. Changes take effect on the next identify.
+    pub capabilities: GatewayCapabilities,
+    /// The client properties sent when identifying. Changes take effect on the next identify.
+    pub client_properties: ClientProperties,
+    /// The compression of the connection. Changes take effect on the next connection.
+    pub compression: TransportCompression,
+    connector: Arc<dyn GatewayConnector>,
 }
 
 impl Shard {
@@ -125,10 +139,130 @@ impl Shard {
         shard_info: ShardInfo,
         intents: GatewayIntents,
         presence: Option<PresenceData>,
+    ) -> Result<Shard> {
+        let compression = TransportCompression::Zlib;
+        Self::new_with_compression(ws_url, token, shard_info, intents, presence, compression).await
+    }
+
+    /// Instantiates a new Shard like [`Self::new`], with the given [`TransportCompression`]
+    /// instead of zlib-stream.
+    ///
+    /// # Errors
+    ///
+    /// On Error, will return either [`Error::Gateway`], [`Error::Tungstenite`] or a Rustls/native
+    /// TLS error.
+    pub async fn new_with_compression(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: ShardInfo,
+        intents: GatewayIntents,
+        presence: Option<PresenceData>,
+        compression: TransportCompression,
+    ) -> Result<Shard> {
+        let connector = Arc::new(TungsteniteConnector);
+        Self::new_with_connector(
+            ws_url,
+            token,
+            shard_info,
+            intents,
+            presence,
+            compression,
+            connector,
+        )
+        .await
+    }
+
+    /// Instantiates a new Shard like [`Self::new`], opening its connections through the given
+    /// [`GatewayConnector`] instead of dialing the gateway.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the connector, or [`Error::Gateway`] if the gateway URL is invalid.
+    pub async fn new_with_connector(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: ShardInfo,
+        intents: GatewayIntents,
+        presence: Option<PresenceData>,
+        compression: TransportCompression,
+        connector: Arc<dyn GatewayConnector>,
     ) -> Result<Shard> {
         let url = ws_url.lock().await.clone();
-        let client = connect(&url).await?;
+        let client = connect(&*connector, &url, compression).await?;
 
+        Ok(Self::with_client(
+            client,
+            ws_url,
+            token,
+            shard_info,
+            intents,
+            presence,
+            compression,
+            connector,
+        ))
+    }
+
+    /// Instantiates a Shard that resumes a session exported with [`Self::session_state`],
+    /// connecting straight to the resume gateway URL of the session, or to `ws_url` if it has
+    /// none, and sending the resume.
+    ///
+    /// If the session turns out to be no longer valid, the shard will identify again as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error of the connector, [`Error::Gateway`] if the gateway URL is invalid, or
+    /// [`Error::Tungstenite`] if the resume could not be sent.
+    pub async fn from_session(
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        state: SessionState,
+        intents: GatewayIntents,
+        presence: Option<PresenceData>,
+        compression: TransportCompression,
+        connector: Arc<dyn GatewayConnector>,
+    ) -> Result<Shard> {
+        debug!("[{:?}] Resuming a restored session", state.shard_info);
+
+        // Sessions can only be resumed at the URL given in Ready.
+        let url = match &state.resume_gateway_url {
+            Some(url) => url.clone(),
+            None => ws_url.lock().await.clone(),
+        };
+        let client = connect(&*connector, &url, compression).await?;
+
+        let mut shard = Self::with_client(
+            client,
+            ws_url,
+            token,
+            state.shard_info,
+            intents,
+            presence,
+            compression,
+            connector,
+        );
+        shard.stage = ConnectionStage::Resuming;
+        shard.seq = state.seq;
+        shard.resume_ws_url = state.resume_gateway_url;
+        shard
+            .client
+            .send_resume(&shard.shard_info, &state.session_id, shard.seq, &shard.token)
+            .await?;
+        shard.session_id = Some(state.session_id);
+
+        Ok(shard)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn with_client(
+        client: WsClient,
+        ws_url: Arc<Mutex<String>>,
+        token: &str,
+        shard_info: ShardInfo,
+        intents: GatewayIntents,
+        presence: Option<PresenceData>,
+        compression: TransportCompression,
+        connector: Arc<dyn GatewayConnector>,
+    ) -> Shard {
         let presence = presence.unwrap_or_default();
         let last_heartbeat_sent = None;
         let last_heartbeat_ack = None;
@@ -138,7 +272,7 @@ impl Shard {
         let stage = ConnectionStage::Handshake;
         let session_id = None;
 
-        Ok(Shard {
+        Shard {
             client,
             presence,
             last_heartbeat_sent,
@@ -151,10 +285,15 @@ impl Shard {
             started: Instant::now(),
             token: token.to_string(),
             session_id,
+            resume_ws_url: None,
             shard_info,
             ws_url,
             intents,
-        })
+            capabilities: GatewayCapabilities::default(),
+            client_properties: ClientProperties::default(),
+            compression,
+            connector,
+        }
     }
 
     /// Sets a callback to be called when the gateway receives the application's ID from Discord.
@@ -240,6 +379,29 @@ impl Shard {
         self.session_id.as_ref()
     }
 
+    /// Returns what is needed to resume the current session later on, if there is one.
+    #[must_use]
+    pub fn session_state(&self) -> Option<SessionState> {
+        Some(SessionState {
+            session_id: self.session_id.clone()?,
+            seq: self.seq,
+            resume_gateway_url: self.resume_ws_url.clone(),
+            shard_info: self.shard_info,
+        })
+    }
+
+    /// Restores a session exported with [`Self::session_state`], which the next [`Self::resume`]
+    /// will attempt to resume.
+    ///
+    /// The session should belong to the same shard. If it turns out to be no longer valid, the
+    /// shard will identify again as usual. A new shard should rather be created with
+    /// [`Self::from_session`], which connects to the gateway only once.
+    pub fn restore_session(&mut self, state: SessionState) {
+        self.session_id = Some(state.session_id);
+        self.seq = state.seq;
+        self.resume_ws_url = state.resume_gateway_url;
+    }
+
     #[inline]
     #[instrument(skip(self))]
     pub fn set_activity(&mut self, activity: Option<ActivityData>) {
@@ -287,6 +449,8 @@ impl Shard {
                 debug!("[{:?}] Received Ready", self.shard_info);
 
                 self.session_id = Some(ready.ready.session_id.clone());
+                self.resume_ws_url =
+                    Some(ready.ready.resume_gateway_url.clone()).filter(|url| !url.is_empty());
                 self.stage = ConnectionStage::Connected;
 
                 if let Some(application_id) = &ready.ready.application {
@@ -456,12 +620,12 @@ impl Shard {
             &Ok(GatewayEvent::Hello(interval)) => {
                 debug!("[{:?}] Received a Hello; interval: {}", self.shard_info, interval);
 
+                self.heartbeat_interval = Some(std::time::Duration::from_millis(interval));
+
                 if self.stage == ConnectionStage::Resuming {
                     return Ok(None);
                 }
 
-                self.heartbeat_interval = Some(std::time::Duration::from_millis(interval));
-
                 Ok(Some(if self.stage == ConnectionStage::Handshake {
                     ShardAction::Identify
                 } else {
@@ -674,13 +838,55 @@ impl Shard {
             .await
     }
 
+    /// Subscribes to a single guild's events and member list ranges.
+    ///
+    /// Member list ranges are sent as the [`Event::GuildMemberListUpdate`] event. If the `cache`
+    /// feature is enabled, the cache will keep the subscribed lists up to date.
+    ///
+    /// Prefer [`Self::update_guild_subscriptions`], which is what the official client uses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Tungstenite`] if the payload could not be sent.
+    #[instrument(skip(self))]
+    pub async fn update_guild_subscription(
+        &mut self,
+        guild_id: GuildId,
+        subscription: &GuildSubscription,
+    ) -> Result<()> {
+        self.client.send_guild_subscription(&self.shard_info, guild_id, subscription).await
+    }
+
+    /// Subscribes to the events and member list ranges of any number of guilds at once.
+    ///
+    /// Member list ranges are sent as the [`Event::GuildMemberListUpdate`] event. If the `cache`
+    /// feature is enabled, the cache will keep the subscribed lists up to date.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Tungstenite`] if the payload could not be sent.
+    #[instrument(skip(self))]
+    pub async fn update_guild_subscriptions(
+        &mut self,
+        subscriptions: &HashMap<GuildId, GuildSubscription>,
+    ) -> Result<()> {
+        self.client.send_guild_subscriptions(&self.shard_info, subscriptions).await
+    }
+
     /// Sets the shard as going into identifying stage, which sets:
     /// - the time that the last heartbeat sent as being now
     /// - the `stage` to [`ConnectionStage::Identifying`]
     #[instrument(skip(self))]
     pub async fn identify(&mut self) -> Result<()> {
         self.client
-            .send_identify(&self.shard_info, &self.token, self.intents, &self.presence)
+            .send_identify(
+                &self.shard_info,
+                &self.token,
+                self.intents,
+                self.capabilities,
+                &self.client_properties,
+                &self.presence,
+            )
             .await?;
 
         self.last_heartbeat_sent = Some(Instant::now());
@@ -696,6 +902,11 @@ impl Shard {
     pub async fn initialize(&mut self) -> Result<WsClient> {
         debug!("[{:?}] Initializing.", self.shard_info);
 
+        let url = self.ws_url.lock().await.clone();
+        self.connect_to(&url).await
+    }
+
+    async fn connect_to(&mut self, url: &str) -> Result<WsClient> {
         // We need to do two, sort of three things here:
         // - set the stage of the shard as opening the websocket connection
         // - open the websocket connection
@@ -705,8 +916,7 @@ impl Shard {
         // Hello is received.
         self.stage = ConnectionStage::Connecting;
         self.started = Instant::now();
-        let url = &self.ws_url.lock().await.clone();
-        let client = connect(url).await?;
+        let client = connect(&*self.connector, url, self.compression).await?;
         self.stage = ConnectionStage::Handshake;
 
         Ok(client)
@@ -719,6 +929,7 @@ impl Shard {
         self.heartbeat_interval = None;
         self.last_heartbeat_acknowledged = true;
         self.session_id = None;
+        self.resume_ws_url = None;
         self.stage = ConnectionStage::Disconnected;
         self.seq = 0;
     }
@@ -727,7 +938,11 @@ impl Shard {
     pub async fn resume(&mut self) -> Result<()> {
         debug!("[{:?}] Attempting to resume", self.shard_info);
 
-        self.client = self.initialize().await?;
+        // Sessions can only be resumed at the URL given in Ready.
+        self.client = match self.resume_ws_url.clone() {
+            Some(url) => self.connect_to(&url).await?,
+            None => self.initialize().await?,
+        };
         self.stage = ConnectionStage::Resuming;
 
         match &self.session_id {
@@ -754,13 +969,154 @@ impl Shard {
     }
 }
 
-async fn connect(base_url: &str) -> Result<WsClient> {
-    let url =
+async fn connect(
+    connector: &dyn GatewayConnector,
+    base_url: &str,
+    compression: TransportCompression,
+) -> Result<WsClient> {
+    let mut url =
         Url::parse(&format!("{base_url}?v={}", constants::GATEWAY_VERSION)).map_err(|why| {
             warn!("Error building gateway URL with base `{}`: {:?}", base_url, why);
 
             Error::Gateway(GatewayError::BuildingUrl)
         })?;
 
-    WsClient::connect(url).await
+    if let Some(compress) = compression.query_value() {
+        url.query_pairs_mut().append_pair("compress", compress);
+    }
+
+    WsClient::connect(connector, url, compression).await
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use async_trait::async_trait;
+    use futures::channel::mpsc::{self, UnboundedSender};
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{duplex, DuplexStream};
+    use tokio_tungstenite::tungstenite::protocol::Role;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    use super::*;
+    use crate::gateway::WsTransport;
+    use crate::json::{from_str, json, Value};
+    use crate::model::gateway::ready_json;
+    use crate::model::id::ShardId;
+
+    type FakeGateway = WebSocketStream<DuplexStream>;
+
+    /// Connects to in-process fake gateways, handing out their ends along with the URL dialed.
+    struct FakeConnector(UnboundedSender<(Url, FakeGateway)>);
+
+    #[async_trait]
+    impl GatewayConnector for FakeConnector {
+        async fn connect(&self, url: Url) -> Result<Box<dyn WsTransport>> {
+            let (client, server) = duplex(64 * 1024);
+            let (client, server) = tokio::join!(
+                WebSocketStream::from_raw_socket(client, Role::Client, None),
+                WebSocketStream::from_raw_socket(server, Role::Server, None),
+            );
+            self.0.unbounded_send((url, server)).unwrap();
+            Ok(Box::new(client))
+        }
+    }
+
+    async fn send(gateway: &mut FakeGateway, payload: Value) {
+        gateway.send(Message::Text(payload.to_string())).await.unwrap();
+    }
+
+    async fn recv(gateway: &mut FakeGateway) -> Value {
+        let message = gateway.next().await.unwrap().unwrap();
+        from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    async fn handle(shard: &mut Shard) -> Option<ShardAction> {
+        let event = shard.client.recv_json(None).await.unwrap().unwrap();
+        shard.handle_event(&Ok(event)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn fake_gateway() {
+        let (tx, mut gateways) = mpsc::unbounded();
+        let mut shard = Shard::new_with_connector(
+            Arc::new(Mutex::new("wss://gateway.test".to_string())),
+            "token",
+            ShardInfo::new(ShardId(0), 1),
+            GatewayIntents::empty(),
+            None,
+            TransportCompression::None,
+            Arc::new(FakeConnector(tx)),
+        )
+        .await
+        .unwrap();
+        let (url, mut gateway) = gateways.next().await.unwrap();
+        assert_eq!(url.host_str(), Some("gateway.test"));
+
+        send(&mut gateway, json!({"op": 10, "d": {"heartbeat_interval": 41250}})).await;
+        assert!(matches!(handle(&mut shard).await, Some(ShardAction::Identify)));
+        shard.identify().await.unwrap();
+        let identify = recv(&mut gateway).await;
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["token"], "token");
+
+        send(&mut gateway, json!({"op": 0, "s": 1, "t": "READY", "d": ready_json([])})).await;
+        assert!(handle(&mut shard).await.is_none());
+        assert_eq!(shard.stage(), ConnectionStage::Connected);
+
+        send(&mut gateway, json!({"op": 0, "s": 2, "t": "SOMETHING_NEW", "d": {}})).await;
+        assert!(handle(&mut shard).await.is_none());
+
+        // Sessions are resumed at the URL given in Ready, from the last sequence number.
+        send(&mut gateway, json!({"op": 7, "d": null})).await;
+        let action = handle(&mut shard).await;
+        assert!(matches!(action, Some(ShardAction::Reconnect(ReconnectType::Resume))));
+        shard.resume().await.unwrap();
+        let (url, mut gateway) = gateways.next().await.unwrap();
+        assert_eq!(url.host_str(), Some("resume.test"));
+        let resume = recv(&mut gateway).await;
+        assert_eq!(resume["op"], 6);
+        assert_eq!(resume["d"]["session_id"], "session");
+        assert_eq!(resume["d"]["seq"], 2);
+
+        send(&mut gateway, json!({"op": 9, "d": false})).await;
+        let action = handle(&mut shard).await;
+        assert!(matches!(action, Some(ShardAction::Reconnect(ReconnectType::Reidentify))));
+    }
+
+    #[tokio::test]
+    async fn restored_session() {
+        let (tx, mut gateways) = mpsc::unbounded();
+        let state = SessionState {
+            session_id: "session".into(),
+            seq: 2,
+            resume_gateway_url: Some("wss://resume.test".into()),
+            shard_info: ShardInfo::new(ShardId(0), 1),
+        };
+        let mut shard = Shard::from_session(
+            Arc::new(Mutex::new("wss://gateway.test".to_string())),
+            "token",
+            state,
+            GatewayIntents::empty(),
+            None,
+            TransportCompression::None,
+            Arc::new(FakeConnector(tx)),
+        )
+        .await
+        .unwrap();
+
+        // The session is resumed over the only connection opened, at its resume gateway URL.
+        let (url, mut gateway) = gateways.next().await.unwrap();
+        assert_eq!(url.host_str(), Some("resume.test"));
+        assert!(gateways.try_recv().is_err());
+        let resume = recv(&mut gateway).await;
+        assert_eq!(resume["op"], 6);
+        assert_eq!(resume["d"]["session_id"], "session");
+        assert_eq!(resume["d"]["seq"], 2);
+
+        send(&mut gateway, json!({"op": 10, "d": {"heartbeat_interval": 41250}})).await;
+        assert!(handle(&mut shard).await.is_none());
+        assert_eq!(shard.stage(), ConnectionStage::Resuming);
+        assert_eq!(shard.session_id().map(String::as_str), Some("session"));
+    }
 }
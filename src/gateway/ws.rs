@@ -1,47 +1,55 @@
-use std::env::consts;
 #[cfg(feature = "client")]
-use std::io::Read;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::time::SystemTime;
 
-#[cfg(feature = "client")]
-use flate2::read::ZlibDecoder;
-use futures::SinkExt;
+use async_trait::async_trait;
 #[cfg(feature = "client")]
 use futures::StreamExt;
-use tokio::net::TcpStream;
+use futures::{Sink, SinkExt, Stream};
 #[cfg(feature = "client")]
 use tokio::time::{timeout, Duration};
+use tokio_tungstenite::connect_async_with_config;
 #[cfg(feature = "client")]
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
-#[cfg(feature = "client")]
-use tokio_tungstenite::tungstenite::Error as WsError;
-use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{connect_async_with_config, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
 #[cfg(feature = "client")]
 use tracing::warn;
 use tracing::{debug, instrument, trace};
 use url::Url;
 
-use super::{ActivityData, ChunkGuildFilter, PresenceData};
+#[cfg(feature = "client")]
+use super::compression::Decompressor;
+#[cfg(feature = "client")]
+use super::recording::SessionRecorder;
+use super::{
+    ActivityData,
+    ChunkGuildFilter,
+    GuildSubscription,
+    PresenceData,
+    TransportCompression,
+};
 use crate::constants::{self, Opcode};
 #[cfg(feature = "client")]
 use crate::gateway::GatewayError;
-#[cfg(feature = "client")]
-use crate::json::from_str;
+use crate::internal::prelude::StdResult;
 use crate::json::to_string;
 #[cfg(feature = "client")]
+use crate::json::{from_slice, from_str};
+#[cfg(feature = "client")]
 use crate::model::event::GatewayEvent;
-use crate::model::gateway::{GatewayIntents, ShardInfo};
+use crate::model::gateway::{ClientProperties, GatewayCapabilities, GatewayIntents, ShardInfo};
 use crate::model::id::{GuildId, UserId};
 #[cfg(feature = "client")]
 use crate::Error;
 use crate::Result;
 
-#[derive(Serialize)]
-struct IdentifyProperties {
-    browser: &'static str,
-    os: &'static str,
+/// The state cached by the client from previous sessions, which is always empty as the library
+/// doesn't persist any.
+#[derive(Default, Serialize)]
+struct ClientState {
+    guild_versions: HashMap<GuildId, u64>,
 }
 
 #[derive(Serialize)]
@@ -56,6 +64,13 @@ struct ChunkGuildMessage<'a> {
     nonce: &'a str,
 }
 
+#[derive(Serialize)]
+struct GuildSubscriptionMessage<'a> {
+    guild_id: GuildId,
+    #[serde(flatten)]
+    subscription: &'a GuildSubscription,
+}
+
 #[derive(Serialize)]
 struct PresenceUpdateMessage<'a> {
     afk: bool,
@@ -69,13 +84,19 @@ struct PresenceUpdateMessage<'a> {
 enum WebSocketMessageData<'a> {
     Heartbeat(Option<u64>),
     ChunkGuild(ChunkGuildMessage<'a>),
+    GuildSubscription(GuildSubscriptionMessage<'a>),
+    GuildSubscriptionsBulk {
+        subscriptions: &'a HashMap<GuildId, GuildSubscription>,
+    },
     Identify {
         compress: bool,
         token: &'a str,
         large_threshold: u8,
         shard: &'a ShardInfo,
         intents: GatewayIntents,
-        properties: IdentifyProperties,
+        capabilities: GatewayCapabilities,
+        client_state: ClientState,
+        properties: &'a ClientProperties,
         presence: PresenceUpdateMessage<'a>,
     },
     PresenceUpdate(PresenceUpdateMessage<'a>),
@@ -92,15 +113,45 @@ struct WebSocketMessage<'a> {
     d: WebSocketMessageData<'a>,
 }
 
-pub struct WsClient(WebSocketStream<MaybeTlsStream<TcpStream>>);
+/// A WebSocket connection to the gateway, as a stream of received messages and a sink for sent
+/// ones.
+///
+/// This is implemented for any such type, including a [`WebSocketStream`] over any transport.
+///
+/// [`WebSocketStream`]: tokio_tungstenite::WebSocketStream
+pub trait WsTransport:
+    Stream<Item = StdResult<Message, WsError>> + Sink<Message, Error = WsError> + Send + Sync + Unpin
+{
+}
 
-#[cfg(feature = "client")]
-const TIMEOUT: Duration = Duration::from_millis(500);
-#[cfg(feature = "client")]
-const DECOMPRESSION_MULTIPLIER: usize = 3;
+impl<T> WsTransport for T where
+    T: Stream<Item = StdResult<Message, WsError>>
+        + Sink<Message, Error = WsError>
+        + Send
+        + Sync
+        + Unpin
+{
+}
 
-impl WsClient {
-    pub(crate) async fn connect(url: Url) -> Result<Self> {
+/// Opens the WebSocket connections of a [`Shard`].
+///
+/// [`TungsteniteConnector`] connects to the actual gateway. Implement this to run shards over
+/// another transport, such as an in-process fake gateway in tests.
+///
+/// [`Shard`]: super::Shard
+#[async_trait]
+pub trait GatewayConnector: Send + Sync {
+    /// Connects to the gateway at the given URL, which already includes the query parameters.
+    async fn connect(&self, url: Url) -> Result<Box<dyn WsTransport>>;
+}
+
+/// A [`GatewayConnector`] opening connections through [`tokio_tungstenite`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TungsteniteConnector;
+
+#[async_trait]
+impl GatewayConnector for TungsteniteConnector {
+    async fn connect(&self, url: Url) -> Result<Box<dyn WsTransport>> {
         let config = WebSocketConfig {
             max_message_size: None,
             max_frame_size: None,
@@ -108,74 +159,119 @@ impl WsClient {
         };
         let (stream, _) = connect_async_with_config(url, Some(config), false).await?;
 
-        Ok(Self(stream))
+        Ok(Box::new(stream))
+    }
+}
+
+pub struct WsClient {
+    stream: Box<dyn WsTransport>,
+    compression: TransportCompression,
+    #[cfg(feature = "client")]
+    decompressor: Decompressor,
+}
+
+#[cfg(feature = "client")]
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+impl WsClient {
+    pub(crate) async fn connect(
+        connector: &dyn GatewayConnector,
+        url: Url,
+        compression: TransportCompression,
+    ) -> Result<Self> {
+        let stream = connector.connect(url).await?;
+
+        Ok(Self {
+            stream,
+            compression,
+            #[cfg(feature = "client")]
+            decompressor: Decompressor::new(compression),
+        })
     }
 
+    /// Receives a payload, writing it to the recorder if there is one.
     #[cfg(feature = "client")]
-    pub(crate) async fn recv_json(&mut self) -> Result<Option<GatewayEvent>> {
-        let message = match timeout(TIMEOUT, self.0.next()).await {
+    pub(crate) async fn recv_json(
+        &mut self,
+        recorder: Option<&mut SessionRecorder>,
+    ) -> Result<Option<GatewayEvent>> {
+        let message = match timeout(TIMEOUT, self.stream.next()).await {
             Ok(Some(Ok(msg))) => msg,
             Ok(Some(Err(e))) => return Err(e.into()),
             Ok(None) | Err(_) => return Ok(None),
         };
 
-        let value = match message {
+        let (payload, value) = match &message {
             Message::Binary(bytes) => {
-                let mut decompressed =
-                    String::with_capacity(bytes.len() * DECOMPRESSION_MULTIPLIER);
-
-                ZlibDecoder::new(&bytes[..]).read_to_string(&mut decompressed).map_err(|why| {
+                let decompressed = self.decompressor.decompress(bytes).map_err(|why| {
                     warn!("Err decompressing bytes: {why:?}");
                     debug!("Failing bytes: {bytes:?}");
 
                     why
                 })?;
 
-                from_str(&decompressed).map_err(|why| {
+                // The rest of the message is still to come.
+                let Some(decompressed) = decompressed else {
+                    return Ok(None);
+                };
+
+                let value = from_slice(decompressed).map_err(|why| {
                     warn!("Err deserializing bytes: {why:?}");
                     debug!("Failing bytes: {bytes:?}");
 
                     why
-                })?
+                });
+                (String::from_utf8_lossy(decompressed), value)
             },
-            Message::Text(payload) => from_str(&payload).map_err(|why| {
-                warn!("Err deserializing text: {why:?}; text: {payload}");
+            Message::Text(payload) => {
+                let value = from_str(payload.as_str()).map_err(|why| {
+                    warn!("Err deserializing text: {why:?}; text: {payload}");
 
-                why
-            })?,
+                    why
+                });
+                (Cow::from(payload.as_str()), value)
+            },
             Message::Close(Some(frame)) => {
-                return Err(Error::Gateway(GatewayError::Closed(Some(frame))));
+                return Err(Error::Gateway(GatewayError::Closed(Some(frame.clone()))));
             },
             _ => return Ok(None),
         };
 
-        Ok(Some(value))
+        if let Some(recorder) = recorder {
+            let seq = match &value {
+                Ok(GatewayEvent::Dispatch(seq, _)) => Some(*seq),
+                _ => None,
+            };
+            recorder.record(&payload, seq).await;
+        }
+
+        Ok(Some(value?))
     }
 
     pub(crate) async fn send_json(&mut self, value: &impl serde::Serialize) -> Result<()> {
         let message = to_string(value).map(Message::Text)?;
 
-        self.0.send(message).await?;
+        self.stream.send(message).await?;
         Ok(())
     }
 
     /// Delegate to `StreamExt::next`
     #[cfg(feature = "client")]
     pub(crate) async fn next(&mut self) -> Option<std::result::Result<Message, WsError>> {
-        self.0.next().await
+        self.stream.next().await
     }
 
     /// Delegate to `SinkExt::send`
     #[cfg(feature = "client")]
     pub(crate) async fn send(&mut self, message: Message) -> Result<()> {
-        self.0.send(message).await?;
+        self.stream.send(message).await?;
         Ok(())
     }
 
-    /// Delegate to `WebSocketStream::close`
+    /// Sends a Close frame, like `WebSocketStream::close`
     #[cfg(feature = "client")]
-    pub(crate) async fn close(&mut self, msg: Option<CloseFrame<'_>>) -> Result<()> {
-        self.0.close(msg).await?;
+    pub(crate) async fn close(&mut self, msg: Option<CloseFrame<'static>>) -> Result<()> {
+        self.stream.send(Message::Close(msg)).await?;
         Ok(())
     }
 
@@ -211,6 +307,50 @@ impl WsClient {
         .await
     }
 
+    /// Sends a guild subscription for a single guild (opcode 14).
+    #[allow(clippy::missing_errors_doc)]
+    #[instrument(skip(self))]
+    pub async fn send_guild_subscription(
+        &mut self,
+        shard_info: &ShardInfo,
+        guild_id: GuildId,
+        subscription: &GuildSubscription,
+    ) -> Result<()> {
+        debug!("[{:?}] Updating guild subscription for {}", shard_info, guild_id);
+
+        self.send_json(&WebSocketMessage {
+            op: Opcode::GuildSubscriptions,
+            d: WebSocketMessageData::GuildSubscription(GuildSubscriptionMessage {
+                guild_id,
+                subscription,
+            }),
+        })
+        .await
+    }
+
+    /// Sends guild subscriptions for any number of guilds at once (opcode 37).
+    #[allow(clippy::missing_errors_doc)]
+    #[instrument(skip(self))]
+    pub async fn send_guild_subscriptions(
+        &mut self,
+        shard_info: &ShardInfo,
+        subscriptions: &HashMap<GuildId, GuildSubscription>,
+    ) -> Result<()> {
+        debug!(
+            "[{:?}] Updating guild subscriptions for {} guilds",
+            shard_info,
+            subscriptions.len()
+        );
+
+        self.send_json(&WebSocketMessage {
+            op: Opcode::GuildSubscriptionsBulk,
+            d: WebSocketMessageData::GuildSubscriptionsBulk {
+                subscriptions,
+            },
+        })
+        .await
+    }
+
     #[instrument(skip(self))]
     pub async fn send_heartbeat(&mut self, shard_info: &ShardInfo, seq: Option<u64>) -> Result<()> {
         trace!("[{:?}] Sending heartbeat d: {:?}", shard_info, seq);
@@ -228,6 +368,8 @@ impl WsClient {
         shard: &ShardInfo,
         token: &str,
         intents: GatewayIntents,
+        capabilities: GatewayCapabilities,
+        properties: &ClientProperties,
         presence: &PresenceData,
     ) -> Result<()> {
         let activities: Vec<_> = presence.activity.iter().collect();
@@ -241,12 +383,12 @@ impl WsClient {
                 token,
                 shard,
                 intents,
-                compress: true,
+                capabilities,
+                client_state: ClientState::default(),
+                // Compressing payloads on top of the transport would compress them twice.
+                compress: self.compression == TransportCompression::None,
                 large_threshold: constants::LARGE_THRESHOLD,
-                properties: IdentifyProperties {
-                    browser: "Firefox",
-                    os: consts::OS,
-                },
+                properties,
                 presence: PresenceUpdateMessage {
                     afk: false,
                     since: now,
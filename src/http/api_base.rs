@@ -0,0 +1,215 @@
+use std::fmt;
+
+/// The URL that requests are sent to by default, including the version of the API.
+const DEFAULT_API_URL: &str = api!("");
+const DEFAULT_CDN_URL: &str = "https://cdn.discordapp.com";
+
+/// A version of Discord's HTTP API.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ApiVersion {
+    V9,
+    #[default]
+    V10,
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::V9 => "v9",
+            Self::V10 => "v10",
+        })
+    }
+}
+
+/// Where [`Http`] sends its requests, such as to point a client at a stand-in for Discord in
+/// tests.
+///
+/// Set through [`HttpBuilder::api_base`]. The base is used for every request, whether it goes
+/// through the [`Ratelimiter`] or not, and for the gateway URL a [`Client`] connects to.
+///
+/// ```rust
+/// use serenity::http::{ApiBase, ApiVersion, HttpBuilder};
+///
+/// let api_base = ApiBase::new()
+///     .api("http://127.0.0.1:3000/api")
+///     .version(ApiVersion::V9)
+///     .cdn("http://127.0.0.1:3000/cdn")
+///     .gateway("ws://127.0.0.1:3001");
+/// let http = HttpBuilder::new("token").api_base(api_base).build();
+/// ```
+///
+/// [`Http`]: super::Http
+/// [`HttpBuilder::api_base`]: super::HttpBuilder::api_base
+/// [`Ratelimiter`]: super::Ratelimiter
+/// [`Client`]: crate::Client
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct ApiBase {
+    api: String,
+    version: ApiVersion,
+    cdn: String,
+    gateway: Option<String>,
+}
+
+impl Default for ApiBase {
+    fn default() -> Self {
+        Self {
+            api: default_api().into(),
+            version: ApiVersion::default(),
+            cdn: DEFAULT_CDN_URL.into(),
+            gateway: None,
+        }
+    }
+}
+
+/// The URL of Discord's API without the version, taken from [`DEFAULT_API_URL`] so that the two
+/// can't drift apart.
+fn default_api() -> &'static str {
+    let version = ApiVersion::default().to_string();
+    DEFAULT_API_URL
+        .strip_suffix(&version)
+        .and_then(|api| api.strip_suffix('/'))
+        .expect("the default API URL ends with the default version")
+}
+
+impl ApiBase {
+    /// Creates a base pointing at Discord.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the URL of the API, without the version, such as `https://discord.com/api`.
+    pub fn api(mut self, api: impl Into<String>) -> Self {
+        self.api = api.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// Sets the version of the API requests are sent to, which is [`ApiVersion::V10`] by default.
+    pub fn version(mut self, version: ApiVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the URL of the CDN, such as `https://cdn.discordapp.com`.
+    ///
+    /// This is used for files downloaded through [`Http`], such as by [`CreateAttachment::url`].
+    /// The URLs returned by models, such as [`User::avatar_url`], always point at Discord's CDN,
+    /// and can be mapped onto this one through [`Self::cdn_url`].
+    ///
+    /// [`Http`]: super::Http
+    /// [`CreateAttachment::url`]: crate::builder::CreateAttachment::url
+    /// [`User::avatar_url`]: crate::model::user::User::avatar_url
+    pub fn cdn(mut self, cdn: impl Into<String>) -> Self {
+        self.cdn = cdn.into().trim_end_matches('/').into();
+        self
+    }
+
+    /// Sets the URL of the gateway a [`Client`] connects to, instead of asking the API for it.
+    ///
+    /// [`Client`]: crate::Client
+    pub fn gateway(mut self, gateway: impl Into<String>) -> Self {
+        self.gateway = Some(gateway.into());
+        self
+    }
+
+    /// Sets the API to that of a proxy in the form of the protocol and hostname, such as
+    /// `http://127.0.0.1:3000`, as done by [`HttpBuilder::proxy`].
+    ///
+    /// [`HttpBuilder::proxy`]: super::HttpBuilder::proxy
+    pub(super) fn proxy(self, proxy: &str) -> Self {
+        // trim_end_matches to prevent double slashes after the domain
+        self.api(format!("{}/api", proxy.trim_end_matches('/')))
+    }
+
+    /// Returns the proxy set through [`Self::proxy`], which is the API without its `/api` path if
+    /// it isn't Discord's.
+    pub(super) fn proxy_url(&self) -> Option<&str> {
+        (self.api != default_api()).then(|| self.api.strip_suffix("/api").unwrap_or(&self.api))
+    }
+
+    /// Returns the version of the API requests are sent to.
+    #[must_use]
+    pub fn api_version(&self) -> ApiVersion {
+        self.version
+    }
+
+    /// Returns the URL of the gateway set through [`Self::gateway`].
+    #[must_use]
+    pub fn gateway_url(&self) -> Option<&str> {
+        self.gateway.as_deref()
+    }
+
+    /// Maps a URL of Discord's API, such as the [`Route::path`] of a request, onto this base.
+    /// Other URLs are returned as is.
+    ///
+    /// [`Route::path`]: super::Route::path
+    #[must_use]
+    pub fn api_url(&self, url: &str) -> String {
+        match url.strip_prefix(DEFAULT_API_URL) {
+            Some(path) => format!("{}/{}{path}", self.api, self.version),
+            None => url.into(),
+        }
+    }
+
+    /// Maps a URL of Discord's CDN, such as one returned by [`User::avatar_url`], onto this base.
+    /// Other URLs are returned as is.
+    ///
+    /// [`User::avatar_url`]: crate::model::user::User::avatar_url
+    #[must_use]
+    pub fn cdn_url(&self, url: &str) -> String {
+        match url.strip_prefix(DEFAULT_CDN_URL) {
+            Some(path) => format!("{}{path}", self.cdn),
+            None => url.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{test_server, HttpBuilder, Route};
+
+    #[test]
+    fn urls() {
+        let api_base = ApiBase::new();
+        let path = Route::Gateway.path();
+        assert_eq!(api_base.api_url(&path), path);
+        assert_eq!(api_base.proxy_url(), None);
+        assert_eq!(
+            ApiBase::new().proxy("http://127.0.0.1:3000/").proxy_url(),
+            Some("http://127.0.0.1:3000")
+        );
+
+        let api_base = api_base
+            .api("http://127.0.0.1:3000/api/")
+            .version(ApiVersion::V9)
+            .cdn("http://127.0.0.1:3000/cdn");
+        assert_eq!(api_base.api_url(&path), "http://127.0.0.1:3000/api/v9/gateway");
+        assert_eq!(
+            api_base.api_url("https://status.discord.com/api/v2/incidents/unresolved.json"),
+            "https://status.discord.com/api/v2/incidents/unresolved.json",
+        );
+        assert_eq!(
+            api_base.cdn_url("https://cdn.discordapp.com/avatars/1/a.webp"),
+            "http://127.0.0.1:3000/cdn/avatars/1/a.webp",
+        );
+    }
+
+    #[tokio::test]
+    async fn ratelimited_requests() {
+        let body = r#"{"url":"ws://127.0.0.1"}"#;
+        let (url, server) = test_server([format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        )]);
+
+        let api_base = ApiBase::new().api(format!("{url}/api")).version(ApiVersion::V9);
+        let http = HttpBuilder::new("token").api_base(api_base).build();
+        assert!(http.ratelimiter.is_some());
+
+        let gateway = http.get_gateway().await.unwrap();
+        assert_eq!(gateway.url, "ws://127.0.0.1");
+        assert!(server.join().unwrap()[0].starts_with("GET /api/v9/gateway HTTP/1.1\r\n"));
+    }
+}
@@ -0,0 +1,20 @@
+use async_trait::async_trait;
+
+use super::CaptchaRequired;
+
+/// Solves the captchas that some requests of user accounts are answered with, such as joining a
+/// guild or opening a DM, so that the requests can be retried.
+///
+/// Set through [`HttpBuilder::captcha_handler`]. When a request requires a captcha, the
+/// [`Ratelimiter`] asks the handler for a solution and replays the request once with it. Requests
+/// that still require a captcha afterwards fail with [`HttpError::CaptchaRequired`].
+///
+/// [`HttpBuilder::captcha_handler`]: super::HttpBuilder::captcha_handler
+/// [`Ratelimiter`]: super::Ratelimiter
+/// [`HttpError::CaptchaRequired`]: super::HttpError::CaptchaRequired
+#[async_trait]
+pub trait CaptchaHandler: Send + Sync {
+    /// Solves the captcha, returning the solution to send as the `X-Captcha-Key` header, or
+    /// [`None`] to fail the request instead.
+    async fn solve(&self, captcha: &CaptchaRequired) -> Option<String>;
+}
@@ -14,13 +14,17 @@ use secrecy::{ExposeSecret, SecretString};
 use serde::de::DeserializeOwned;
 use tracing::{debug, instrument, warn};
 
+use super::middleware::{Middleware, MiddlewareChain};
 use super::multipart::{Multipart, MultipartUpload};
+use super::ratelimit_store::RatelimitStore;
 use super::ratelimiting::Ratelimiter;
-use super::request::Request;
+use super::request::{Request, SuperProperties};
+use super::retry::{RetryInfo, RetryPolicy};
 use super::routing::Route;
 use super::typing::Typing;
 use super::{
-    ErrorResponse,
+    ApiBase,
+    CaptchaHandler,
     GuildPagination,
     HttpError,
     LightMethod,
@@ -55,9 +59,15 @@ pub struct HttpBuilder {
     ratelimiter: Option<Ratelimiter>,
     ratelimiter_disabled: bool,
     token: SecretString,
-    proxy: Option<String>,
+    api_base: ApiBase,
     application_id: Option<ApplicationId>,
     default_allowed_mentions: Option<CreateAllowedMentions>,
+    client_properties: ClientProperties,
+    captcha_handler: Option<Arc<dyn CaptchaHandler>>,
+    ratelimit_store: Option<Arc<dyn RatelimitStore>>,
+    retry_policy: Option<RetryPolicy>,
+    retry_callback: Option<Box<dyn Fn(RetryInfo) + Send + Sync>>,
+    middleware: MiddlewareChain,
 }
 
 impl HttpBuilder {
@@ -69,9 +79,15 @@ impl HttpBuilder {
             ratelimiter: None,
             ratelimiter_disabled: false,
             token: SecretString::new(parse_token(token)),
-            proxy: None,
+            api_base: ApiBase::default(),
             application_id: None,
             default_allowed_mentions: None,
+            client_properties: ClientProperties::default(),
+            captcha_handler: None,
+            ratelimit_store: None,
+            retry_policy: None,
+            retry_callback: None,
+            middleware: MiddlewareChain::default(),
         }
     }
 
@@ -100,6 +116,39 @@ impl HttpBuilder {
         self
     }
 
+    /// Sets the [`RatelimitStore`] the ratelimiter keeps track of ratelimits in, such as a
+    /// [`FileRatelimitStore`] to share them with other processes using the same token. If one
+    /// isn't provided, ratelimits are kept track of within this process.
+    ///
+    /// [`FileRatelimitStore`]: super::FileRatelimitStore
+    pub fn ratelimit_store(mut self, ratelimit_store: Arc<dyn RatelimitStore>) -> Self {
+        self.ratelimit_store = Some(ratelimit_store);
+        self
+    }
+
+    /// Sets the [`RetryPolicy`] for requests that failed transiently, such as those answered with a
+    /// status of 503 or whose connection was reset. If one isn't provided, such requests aren't
+    /// retried.
+    ///
+    /// Requests are retried by the ratelimiter, so this has no effect if it's disabled.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets a callback to be called before a request is retried according to the
+    /// [`Self::retry_policy`], such as to log or count retries.
+    pub fn retry_callback(mut self, retry_callback: Box<dyn Fn(RetryInfo) + Send + Sync>) -> Self {
+        self.retry_callback = Some(retry_callback);
+        self
+    }
+
+    /// Adds a [`Middleware`] that requests are run through, after the middleware added before it.
+    pub fn middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.0.push(middleware);
+        self
+    }
+
     /// Sets whether or not the ratelimiter is disabled. By default if this this not used, it is
     /// enabled. In most cases, this should be used in conjunction with [`Self::proxy`].
     ///
@@ -123,10 +172,19 @@ impl HttpBuilder {
     /// proxy's behavior where it will tunnel requests that use TLS via [`HTTP CONNECT`] method
     /// (e.g. using [`reqwest::Proxy`]).
     ///
+    /// This is a shorthand for setting the [`ApiBase::api`] of [`Self::api_base`] to the proxy's
+    /// `/api` path.
+    ///
     /// [`twilight-http-proxy`]: https://github.com/twilight-rs/http-proxy
     /// [`HTTP CONNECT`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Methods/CONNECT
     pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
-        self.proxy = Some(proxy.into());
+        self.api_base = self.api_base.proxy(&proxy.into());
+        self
+    }
+
+    /// Sets the [`ApiBase`] that requests are sent to, which points at Discord by default.
+    pub fn api_base(mut self, api_base: ApiBase) -> Self {
+        self.api_base = api_base;
         self
     }
 
@@ -139,6 +197,25 @@ impl HttpBuilder {
         self
     }
 
+    /// Sets the [`ClientProperties`] sent along with every request, and used when identifying on
+    /// the gateway through a [`Client`] built with this [`Http`].
+    ///
+    /// [`Client`]: crate::Client
+    pub fn client_properties(mut self, client_properties: ClientProperties) -> Self {
+        self.client_properties = client_properties;
+        self
+    }
+
+    /// Sets the [`CaptchaHandler`] asked to solve captchas that requests are answered with, after
+    /// which the request is replayed with the solution. Without one, such requests fail with
+    /// [`HttpError::CaptchaRequired`].
+    ///
+    /// Captchas are solved by the ratelimiter, so this has no effect if it's disabled.
+    pub fn captcha_handler(mut self, captcha_handler: Arc<dyn CaptchaHandler>) -> Self {
+        self.captcha_handler = Some(captcha_handler);
+        self
+    }
+
     /// Use the given configuration to build the `Http` client.
     #[must_use]
     pub fn build(self) -> Http {
@@ -150,17 +227,36 @@ impl HttpBuilder {
         });
 
         let ratelimiter = (!self.ratelimiter_disabled).then(|| {
-            self.ratelimiter
-                .unwrap_or_else(|| Ratelimiter::new(client.clone(), self.token.expose_secret()))
+            let mut ratelimiter = self
+                .ratelimiter
+                .unwrap_or_else(|| Ratelimiter::new(client.clone(), self.token.expose_secret()));
+            ratelimiter.set_client_properties(self.client_properties.clone());
+            ratelimiter.set_api_base(self.api_base.clone());
+            if let Some(captcha_handler) = self.captcha_handler {
+                ratelimiter.set_captcha_handler(captcha_handler);
+            }
+            if let Some(ratelimit_store) = self.ratelimit_store {
+                ratelimiter.set_store(ratelimit_store);
+            }
+            if let Some(retry_policy) = self.retry_policy {
+                ratelimiter.set_retry_policy(retry_policy);
+            }
+            if let Some(retry_callback) = self.retry_callback {
+                ratelimiter.set_retry_callback(retry_callback);
+            }
+            ratelimiter.set_middleware(self.middleware.clone());
+            ratelimiter
         });
 
         Http {
             client,
             ratelimiter,
-            proxy: self.proxy,
+            api_base: self.api_base,
             token: self.token,
             application_id,
             default_allowed_mentions: self.default_allowed_mentions,
+            client_properties: self.client_properties.into(),
+            middleware: self.middleware,
         }
     }
 }
@@ -186,16 +282,25 @@ fn reason_into_header(reason: &str) -> Headers {
     headers
 }
 
+/// The body of the responses of the protobuf settings endpoints.
+#[derive(Deserialize)]
+struct SettingsProtoResponse {
+    /// The base64-encoded protobuf settings.
+    settings: String,
+}
+
 /// **Note**: For all member functions that return a [`Result`], the Error kind will be either
 /// [`Error::Http`] or [`Error::Json`].
 #[derive(Debug)]
 pub struct Http {
     pub(crate) client: Client,
     pub ratelimiter: Option<Ratelimiter>,
-    pub proxy: Option<String>,
+    api_base: ApiBase,
     token: SecretString,
     application_id: AtomicU64,
     pub default_allowed_mentions: Option<CreateAllowedMentions>,
+    client_properties: SuperProperties,
+    middleware: MiddlewareChain,
 }
 
 impl Http {
@@ -221,6 +326,33 @@ impl Http {
         self.token.expose_secret()
     }
 
+    /// Returns the [`ClientProperties`] sent along with every request.
+    pub fn client_properties(&self) -> &ClientProperties {
+        &self.client_properties.properties
+    }
+
+    /// Sets the [`ClientProperties`] sent along with every request, including those made through
+    /// the [`Self::ratelimiter`].
+    pub fn set_client_properties(&mut self, client_properties: ClientProperties) {
+        if let Some(ratelimiter) = &mut self.ratelimiter {
+            ratelimiter.set_client_properties(client_properties.clone());
+        }
+        self.client_properties = client_properties.into();
+    }
+
+    /// Returns the [`ApiBase`] that requests are sent to.
+    pub fn api_base(&self) -> &ApiBase {
+        &self.api_base
+    }
+
+    /// Returns the proxy set through [`HttpBuilder::proxy`], or that of an [`ApiBase`] pointing
+    /// somewhere other than Discord, in the form of the protocol and hostname.
+    #[cfg_attr(not(ignore_serenity_deprecated), deprecated = "Use Http::api_base")]
+    #[must_use]
+    pub fn proxy(&self) -> Option<&str> {
+        self.api_base.proxy_url()
+    }
+
     /// Adds a [`User`] to a [`Guild`] with a valid OAuth2 access token.
     ///
     /// Returns the created [`Member`] object, or nothing if the user is already a guild member.
@@ -4457,6 +4589,146 @@ impl Http {
         .await
     }
 
+    /// Gets the current user's protobuf-encoded settings of the given kind, e.g.
+    /// [`PreloadedUserSettings`] or [`FrecencyUserSettings`].
+    pub async fn get_user_settings_proto<T: SettingsProto>(&self) -> Result<T> {
+        let response: SettingsProtoResponse = self
+            .fire(Request {
+                body: None,
+                multipart: None,
+                headers: None,
+                method: LightMethod::Get,
+                route: Route::UserMeSettingsProto {
+                    kind: T::KIND.into(),
+                },
+                params: None,
+            })
+            .await?;
+
+        Ok(T::from_base64(&response.settings)?)
+    }
+
+    /// Edits the current user's protobuf-encoded settings, returning the full updated settings.
+    ///
+    /// Only the fields that are set in `settings` are changed, so a partially filled message can
+    /// be passed to update individual settings.
+    pub async fn edit_user_settings_proto<T: SettingsProto>(&self, settings: &T) -> Result<T> {
+        let map = json!({ "settings": settings.to_base64() });
+        let body = to_vec(&map)?;
+
+        let response: SettingsProtoResponse = self
+            .fire(Request {
+                body: Some(body),
+                multipart: None,
+                headers: None,
+                method: LightMethod::Patch,
+                route: Route::UserMeSettingsProto {
+                    kind: T::KIND.into(),
+                },
+                params: None,
+            })
+            .await?;
+
+        Ok(T::from_base64(&response.settings)?)
+    }
+
+    /// Gets the current user's relationships, i.e. friends, blocked users and pending friend
+    /// requests.
+    pub async fn get_relationships(&self) -> Result<Vec<Relationship>> {
+        self.fire(Request {
+            body: None,
+            multipart: None,
+            headers: None,
+            method: LightMethod::Get,
+            route: Route::UserMeRelationships,
+            params: None,
+        })
+        .await
+    }
+
+    /// Sends a friend request to the user with the given username.
+    pub async fn send_friend_request(&self, username: &str) -> Result<()> {
+        let map = json!({
+            "username": username,
+            "discriminator": null,
+        });
+        let body = to_vec(&map)?;
+
+        self.wind(204, Request {
+            body: Some(body),
+            multipart: None,
+            headers: None,
+            method: LightMethod::Post,
+            route: Route::UserMeRelationships,
+            params: None,
+        })
+        .await
+    }
+
+    /// Accepts an incoming friend request from a user.
+    ///
+    /// If there is no pending request from the user, this sends them a friend request instead.
+    pub async fn accept_friend_request(&self, user_id: UserId) -> Result<()> {
+        self.put_relationship(user_id, None).await
+    }
+
+    /// Ignores an incoming friend request from a user.
+    pub async fn ignore_friend_request(&self, user_id: UserId) -> Result<()> {
+        self.remove_relationship(user_id).await
+    }
+
+    /// Blocks a user, replacing any existing relationship with them.
+    pub async fn block_user(&self, user_id: UserId) -> Result<()> {
+        self.put_relationship(user_id, Some(RelationshipType::Blocked)).await
+    }
+
+    /// Unblocks a previously blocked user.
+    pub async fn unblock_user(&self, user_id: UserId) -> Result<()> {
+        self.remove_relationship(user_id).await
+    }
+
+    async fn put_relationship(
+        &self,
+        user_id: UserId,
+        kind: Option<RelationshipType>,
+    ) -> Result<()> {
+        let map = match kind {
+            Some(kind) => json!({ "type": kind }),
+            None => json!({}),
+        };
+        let body = to_vec(&map)?;
+
+        self.wind(204, Request {
+            body: Some(body),
+            multipart: None,
+            headers: None,
+            method: LightMethod::Put,
+            route: Route::UserMeRelationship {
+                user_id,
+            },
+            params: None,
+        })
+        .await
+    }
+
+    /// Removes the relationship with a user.
+    ///
+    /// Depending on the kind of relationship, this removes a friend, cancels an outgoing friend
+    /// request, ignores an incoming one or unblocks the user.
+    pub async fn remove_relationship(&self, user_id: UserId) -> Result<()> {
+        self.wind(204, Request {
+            body: None,
+            multipart: None,
+            headers: None,
+            method: LightMethod::Delete,
+            route: Route::UserMeRelationship {
+                user_id,
+            },
+            params: None,
+        })
+        .await
+    }
+
     /// Gets all voice regions.
     pub async fn get_voice_regions(&self) -> Result<Vec<VoiceRegion>> {
         self.fire(Request {
@@ -4936,16 +5208,14 @@ impl Http {
         let response = if let Some(ratelimiter) = &self.ratelimiter {
             ratelimiter.perform(req).await?
         } else {
-            let request = req.build(&self.client, self.token(), self.proxy.as_deref())?.build()?;
-            self.client.execute(request).await?
+            let (api_base, properties) = (&self.api_base, &self.client_properties);
+            self.middleware.send(&self.client, req, self.token(), api_base, properties).await?
         };
 
         if response.status().is_success() {
             Ok(response)
         } else {
-            Err(Error::Http(HttpError::UnsuccessfulRequest(
-                ErrorResponse::from_response(response, method).await,
-            )))
+            Err(Error::Http(HttpError::from_response(response, method).await))
         }
     }
 
@@ -4970,9 +5240,7 @@ impl Http {
         }
 
         debug!("Unsuccessful response: {response:?}");
-        Err(Error::Http(HttpError::UnsuccessfulRequest(
-            ErrorResponse::from_response(response, method).await,
-        )))
+        Err(Error::Http(HttpError::from_response(response, method).await))
     }
 }
 
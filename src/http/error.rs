@@ -1,9 +1,11 @@
+use std::collections::BTreeMap;
 use std::error::Error as StdError;
 use std::fmt;
 
 use reqwest::header::InvalidHeaderValue;
 use reqwest::{Error as ReqwestError, Method, Response, StatusCode};
 use serde::de::{Deserialize, Deserializer, Error as _};
+use serde::ser::{Error as _, Serialize, Serializer};
 use url::ParseError as UrlError;
 
 use crate::internal::prelude::*;
@@ -13,22 +15,324 @@ use crate::json::*;
 #[non_exhaustive]
 pub struct DiscordJsonError {
     /// The error code.
-    pub code: isize,
+    pub code: JsonErrorCode,
     /// The error message.
     pub message: String,
-    /// The full explained errors with their path in the request body.
-    #[serde(default, deserialize_with = "deserialize_errors")]
-    pub errors: Vec<DiscordJsonSingleError>,
+    /// The full explained errors, by the path of their field in the request body.
+    #[serde(default, deserialize_with = "deserialize_errors", serialize_with = "serialize_errors")]
+    pub errors: BTreeMap<FieldPath, Vec<FieldError>>,
 }
 
+/// An error with a single field of the request body.
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
-pub struct DiscordJsonSingleError {
-    /// The error code.
+#[non_exhaustive]
+pub struct FieldError {
+    /// The error code, such as `BASE_TYPE_REQUIRED`.
     pub code: String,
     /// The error message.
     pub message: String,
-    /// The path to the error in the request body itself, dot separated.
-    pub path: String,
+}
+
+/// The path to a field in a request body, such as `embeds.0.title`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FieldPath(pub Vec<FieldPathSegment>);
+
+/// A segment of a [`FieldPath`].
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum FieldPathSegment {
+    /// The key of a field in an object.
+    Key(String),
+    /// The index of an element in an array.
+    Index(usize),
+}
+
+impl From<&str> for FieldPathSegment {
+    fn from(segment: &str) -> Self {
+        match segment.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Key(segment.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FieldPathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => f.write_str(key),
+            Self::Index(index) => write!(f, "{index}"),
+        }
+    }
+}
+
+impl From<&str> for FieldPath {
+    /// Parses a dot separated path, such as `embeds.0.title`.
+    fn from(path: &str) -> Self {
+        Self(path.split('.').filter(|s| !s.is_empty()).map(FieldPathSegment::from).collect())
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut segments = self.0.iter();
+        if let Some(segment) = segments.next() {
+            segment.fmt(f)?;
+        }
+        for segment in segments {
+            write!(f, ".{segment}")?;
+        }
+
+        Ok(())
+    }
+}
+
+enum_number! {
+    /// A JSON error code returned by Discord in the body of an unsuccessful request.
+    ///
+    /// [Discord docs](https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes).
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+    #[serde(from = "isize", into = "isize")]
+    #[non_exhaustive]
+    pub enum JsonErrorCode {
+        /// General error, such as a server error.
+        General = 0,
+        UnknownAccount = 10001,
+        UnknownApplication = 10002,
+        UnknownChannel = 10003,
+        UnknownGuild = 10004,
+        UnknownIntegration = 10005,
+        UnknownInvite = 10006,
+        UnknownMember = 10007,
+        UnknownMessage = 10008,
+        UnknownPermissionOverwrite = 10009,
+        UnknownProvider = 10010,
+        UnknownRole = 10011,
+        UnknownToken = 10012,
+        UnknownUser = 10013,
+        UnknownEmoji = 10014,
+        UnknownWebhook = 10015,
+        UnknownWebhookService = 10016,
+        UnknownSession = 10020,
+        UnknownBan = 10026,
+        UnknownSku = 10027,
+        UnknownStoreListing = 10028,
+        UnknownEntitlement = 10029,
+        UnknownBuild = 10030,
+        UnknownLobby = 10031,
+        UnknownBranch = 10032,
+        UnknownStoreDirectoryLayout = 10033,
+        UnknownRedistributable = 10036,
+        UnknownGiftCode = 10038,
+        UnknownStream = 10049,
+        UnknownPremiumServerSubscribeCooldown = 10050,
+        UnknownGuildTemplate = 10057,
+        UnknownDiscoverableServerCategory = 10059,
+        UnknownSticker = 10060,
+        UnknownStickerPack = 10061,
+        UnknownInteraction = 10062,
+        UnknownApplicationCommand = 10063,
+        UnknownVoiceState = 10065,
+        UnknownApplicationCommandPermissions = 10066,
+        UnknownStageInstance = 10067,
+        UnknownGuildMemberVerificationForm = 10068,
+        UnknownGuildWelcomeScreen = 10069,
+        UnknownGuildScheduledEvent = 10070,
+        UnknownGuildScheduledEventUser = 10071,
+        UnknownTag = 10087,
+        BotsCannotUseEndpoint = 20001,
+        OnlyBotsCanUseEndpoint = 20002,
+        ExplicitContentCannotBeSent = 20009,
+        NotAuthorizedForApplication = 20012,
+        SlowmodeRateLimit = 20016,
+        OnlyOwner = 20018,
+        AnnouncementRateLimit = 20022,
+        UnderMinimumAge = 20024,
+        ChannelWriteRateLimit = 20028,
+        ServerWriteRateLimit = 20029,
+        DisallowedWords = 20031,
+        GuildPremiumTierTooLow = 20035,
+        MaxGuilds = 30001,
+        MaxFriends = 30002,
+        MaxPins = 30003,
+        MaxRecipients = 30004,
+        MaxRoles = 30005,
+        MaxWebhooks = 30007,
+        MaxEmojis = 30008,
+        MaxReactions = 30010,
+        MaxGroupDms = 30011,
+        MaxGuildChannels = 30013,
+        MaxAttachments = 30015,
+        MaxInvites = 30016,
+        MaxAnimatedEmojis = 30018,
+        MaxServerMembers = 30019,
+        MaxServerCategories = 30030,
+        GuildAlreadyHasTemplate = 30031,
+        MaxApplicationCommands = 30032,
+        MaxThreadParticipants = 30033,
+        MaxDailyApplicationCommandCreates = 30034,
+        MaxNonMemberBans = 30035,
+        MaxBanFetches = 30037,
+        MaxUncompletedScheduledEvents = 30038,
+        MaxStickers = 30039,
+        MaxPruneRequests = 30040,
+        MaxWidgetSettingsUpdates = 30042,
+        MaxEditsToOldMessages = 30046,
+        MaxPinnedThreads = 30047,
+        MaxForumTags = 30048,
+        BitrateTooHigh = 30052,
+        MaxPremiumEmojis = 30056,
+        MaxGuildWebhooks = 30058,
+        MaxChannelPermissionOverwrites = 30060,
+        GuildChannelsTooLarge = 30061,
+        Unauthorized = 40001,
+        AccountVerificationRequired = 40002,
+        DirectMessagesTooFast = 40003,
+        SendMessagesTemporarilyDisabled = 40004,
+        RequestEntityTooLarge = 40005,
+        FeatureTemporarilyDisabled = 40006,
+        UserBannedFromGuild = 40007,
+        ConnectionRevoked = 40012,
+        TargetUserNotConnectedToVoice = 40032,
+        MessageAlreadyCrossposted = 40033,
+        ApplicationCommandNameExists = 40041,
+        InteractionFailedToSend = 40043,
+        CannotSendMessageInForumChannel = 40058,
+        InteractionAlreadyAcknowledged = 40060,
+        TagNamesMustBeUnique = 40061,
+        ServiceResourceRateLimited = 40062,
+        NoTagsAvailable = 40066,
+        TagRequired = 40067,
+        EntitlementAlreadyGranted = 40074,
+        MaxFollowUpMessages = 40094,
+        CloudflareBlocked = 40333,
+        MissingAccess = 50001,
+        InvalidAccountType = 50002,
+        CannotExecuteOnDm = 50003,
+        GuildWidgetDisabled = 50004,
+        CannotEditOtherUsersMessage = 50005,
+        CannotSendEmptyMessage = 50006,
+        CannotSendMessagesToUser = 50007,
+        CannotSendMessagesInVoiceChannel = 50008,
+        ChannelVerificationTooHigh = 50009,
+        OAuth2ApplicationNoBot = 50010,
+        OAuth2ApplicationLimit = 50011,
+        InvalidOAuth2State = 50012,
+        MissingPermissions = 50013,
+        InvalidToken = 50014,
+        NoteTooLong = 50015,
+        InvalidBulkDeleteCount = 50016,
+        InvalidMfaLevel = 50017,
+        CannotPinInDifferentChannel = 50019,
+        InvalidOrTakenInviteCode = 50020,
+        CannotExecuteOnSystemMessage = 50021,
+        CannotExecuteOnChannelType = 50024,
+        InvalidOAuth2AccessToken = 50025,
+        MissingOAuth2Scope = 50026,
+        InvalidWebhookToken = 50027,
+        InvalidRole = 50028,
+        InvalidRecipients = 50033,
+        MessageTooOldToBulkDelete = 50034,
+        InvalidFormBody = 50035,
+        InviteAcceptedToGuildWithoutBot = 50036,
+        InvalidActivityAction = 50039,
+        InvalidApiVersion = 50041,
+        FileTooLarge = 50045,
+        InvalidFileUploaded = 50046,
+        CannotSelfRedeemGift = 50054,
+        InvalidGuild = 50055,
+        InvalidSku = 50057,
+        InvalidRequestOrigin = 50067,
+        InvalidMessageType = 50068,
+        PaymentSourceRequired = 50070,
+        CannotModifySystemWebhook = 50073,
+        CannotDeleteCommunityChannel = 50074,
+        CannotEditStickerMessage = 50080,
+        InvalidStickerSent = 50081,
+        ThreadArchived = 50083,
+        InvalidThreadNotificationSettings = 50084,
+        BeforeEarlierThanThreadCreation = 50085,
+        CommunityChannelsMustBeText = 50086,
+        EntityTypeMismatch = 50091,
+        ServerNotAvailableInLocation = 50095,
+        MonetizationRequired = 50097,
+        BoostsRequired = 50101,
+        InvalidJson = 50109,
+        OwnershipCannotBeTransferredToBot = 50131,
+        UploadedFileNotFound = 50132,
+        FailedToResizeAsset = 50138,
+        MaxOwnedGuilds = 50144,
+        CannotTransferOwnershipToSelf = 50145,
+        VoiceMessagesUnsupported = 50146,
+        VoiceMessagesNeedAudio = 50159,
+        VoiceMessagesSingleAttachment = 50161,
+        TwoFactorRequired = 60003,
+        NoUsersWithDiscordTag = 80004,
+        ReactionBlocked = 90001,
+        CannotUseBurstReactions = 90002,
+        ApplicationNotYetAvailable = 110001,
+        ApiResourceOverloaded = 130000,
+        StageAlreadyOpen = 150006,
+        CannotReplyWithoutReadHistory = 160002,
+        ThreadAlreadyCreated = 160004,
+        ThreadLocked = 160005,
+        MaxActiveThreads = 160006,
+        MaxActiveAnnouncementThreads = 160007,
+        InvalidLottieJson = 170001,
+        LottieContainsRasterizedImages = 170002,
+        StickerMaxFramerateExceeded = 170003,
+        StickerFrameCountExceeded = 170004,
+        LottieMaxDimensionsExceeded = 170005,
+        StickerFramerateOutOfRange = 170006,
+        StickerAnimationDurationExceeded = 170007,
+        CannotUpdateFinishedEvent = 180000,
+        FailedToCreateStageForEvent = 180002,
+        AutoModBlockedMessage = 200000,
+        AutoModBlockedTitle = 200001,
+        ForumWebhookThreadNameRequired = 220001,
+        ForumWebhookThreadNameAndId = 220002,
+        WebhookThreadsOnlyInForum = 220003,
+        WebhookServicesNotInForum = 220004,
+        HarmfulLinksBlocked = 240000,
+        PollVotingBlocked = 520000,
+        PollExpired = 520001,
+        InvalidChannelTypeForPoll = 520002,
+        CannotEditPollMessage = 520003,
+        CannotUseEmojiInPoll = 520004,
+        CannotExpireNonPollMessage = 520006,
+        _ => Unknown(isize),
+    }
+}
+
+impl JsonErrorCode {
+    /// Returns whether the code is one of those for a resource that doesn't exist or isn't
+    /// visible, such as [`Self::UnknownChannel`], in the range of 10001 to 10999.
+    #[must_use]
+    pub fn is_unknown_resource(self) -> bool {
+        (10001..11000).contains(&isize::from(self))
+    }
+}
+
+/// The challenge some requests of user accounts are answered with, such as joining a guild or
+/// sending a friend request, until a captcha has been solved for them.
+///
+/// The solution is sent back by retrying the request with the `X-Captcha-Key` header, which a
+/// [`CaptchaHandler`] does automatically.
+///
+/// [`CaptchaHandler`]: super::CaptchaHandler
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct CaptchaRequired {
+    /// Why the captcha is required, such as `captcha-required`.
+    pub captcha_key: Vec<String>,
+    /// The site key of the captcha to solve.
+    pub captcha_sitekey: Option<String>,
+    /// The service the captcha has to be solved with, such as `hcaptcha`.
+    pub captcha_service: Option<String>,
+    /// Additional data the service needs to show the captcha.
+    pub captcha_rqdata: Option<String>,
+    /// The token to send back along with the solution, as the `X-Captcha-Rqtoken` header.
+    pub captcha_rqtoken: Option<String>,
+    /// The session to send back along with the solution, as the `X-Captcha-Session-Id` header.
+    pub captcha_session_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -43,14 +347,25 @@ pub struct ErrorResponse {
 impl ErrorResponse {
     // We need a freestanding from-function since we cannot implement an async From-trait.
     pub async fn from_response(r: Response, method: Method) -> Self {
+        let status_code = r.status();
+        let url = r.url().to_string();
+        Self::from_decoded(status_code, url, method, decode_resp(r).await)
+    }
+
+    fn from_decoded(
+        status_code: StatusCode,
+        url: String,
+        method: Method,
+        error: Result<DiscordJsonError>,
+    ) -> Self {
         ErrorResponse {
-            status_code: r.status(),
-            url: r.url().to_string(),
+            status_code,
+            url,
             method,
-            error: decode_resp(r).await.unwrap_or_else(|e| DiscordJsonError {
-                code: -1,
+            error: error.unwrap_or_else(|e| DiscordJsonError {
+                code: JsonErrorCode::Unknown(-1),
                 message: format!("[Serenity] Could not decode json when receiving error response from discord:, {e}"),
-                errors: vec![],
+                errors: BTreeMap::new(),
             }),
         }
     }
@@ -80,9 +395,31 @@ pub enum HttpError {
     InvalidPort,
     /// When an application id was expected but missing.
     ApplicationIdMissing,
+    /// When a captcha has to be solved for the request, and no [`CaptchaHandler`] solved it.
+    ///
+    /// [`CaptchaHandler`]: super::CaptchaHandler
+    CaptchaRequired(Box<CaptchaRequired>),
 }
 
 impl HttpError {
+    /// Creates the error for a non-successful response, which is [`Self::CaptchaRequired`] if the
+    /// response asks for a captcha and [`Self::UnsuccessfulRequest`] otherwise.
+    pub async fn from_response(r: Response, method: Method) -> Self {
+        let status_code = r.status();
+        let url = r.url().to_string();
+        let error = match r.bytes().await {
+            Ok(body) => {
+                if let Ok(captcha) = from_slice::<CaptchaRequired>(&body) {
+                    return Self::CaptchaRequired(Box::new(captcha));
+                }
+                from_slice(&body)
+            },
+            Err(e) => Err(e.into()),
+        };
+
+        Self::UnsuccessfulRequest(ErrorResponse::from_decoded(status_code, url, method, error))
+    }
+
     /// Returns true when the error is caused by an unsuccessful request
     #[must_use]
     pub fn is_unsuccessful_request(&self) -> bool {
@@ -101,6 +438,12 @@ impl HttpError {
         matches!(self, Self::InvalidHeader(_))
     }
 
+    /// Returns true when a captcha has to be solved for the request
+    #[must_use]
+    pub fn is_captcha_required(&self) -> bool {
+        matches!(self, Self::CaptchaRequired(_))
+    }
+
     /// Returns the status code if the error is an unsuccessful request
     #[must_use]
     pub fn status_code(&self) -> Option<StatusCode> {
@@ -109,6 +452,40 @@ impl HttpError {
             _ => None,
         }
     }
+
+    /// Returns the JSON error code if the error is an unsuccessful request
+    #[must_use]
+    pub fn json_error_code(&self) -> Option<JsonErrorCode> {
+        match self {
+            Self::UnsuccessfulRequest(res) => Some(res.error.code),
+            _ => None,
+        }
+    }
+
+    /// Returns true when the request was refused for missing permissions, either those of a role
+    /// ([`JsonErrorCode::MissingPermissions`]) or access to the resource as a whole
+    /// ([`JsonErrorCode::MissingAccess`])
+    #[must_use]
+    pub fn is_missing_permissions(&self) -> bool {
+        matches!(
+            self.json_error_code(),
+            Some(JsonErrorCode::MissingPermissions | JsonErrorCode::MissingAccess)
+        )
+    }
+
+    /// Returns true when the request was about a resource that doesn't exist, such as a deleted
+    /// message. See [`JsonErrorCode::is_unknown_resource`].
+    #[must_use]
+    pub fn is_unknown_resource(&self) -> bool {
+        self.json_error_code().is_some_and(JsonErrorCode::is_unknown_resource)
+    }
+
+    /// Returns true when fields of the request body were rejected, as explained by
+    /// [`DiscordJsonError::errors`]
+    #[must_use]
+    pub fn is_invalid_form_body(&self) -> bool {
+        self.json_error_code() == Some(JsonErrorCode::InvalidFormBody)
+    }
 }
 
 impl From<ErrorResponse> for HttpError {
@@ -142,17 +519,15 @@ impl fmt::Display for HttpError {
                 f.write_str(&e.error.message)?;
 
                 // Put Discord's human readable error explanations in parentheses
-                let mut errors_iter = e.error.errors.iter();
-                if let Some(error) = errors_iter.next() {
-                    f.write_str(" (")?;
-                    f.write_str(&error.path)?;
-                    f.write_str(": ")?;
-                    f.write_str(&error.message)?;
-                    for error in errors_iter {
-                        f.write_str(", ")?;
-                        f.write_str(&error.path)?;
-                        f.write_str(": ")?;
-                        f.write_str(&error.message)?;
+                let mut errors_iter = e
+                    .error
+                    .errors
+                    .iter()
+                    .flat_map(|(path, errors)| errors.iter().map(move |error| (path, error)));
+                if let Some((path, error)) = errors_iter.next() {
+                    write!(f, " ({path}: {}", error.message)?;
+                    for (path, error) in errors_iter {
+                        write!(f, ", {path}: {}", error.message)?;
                     }
                     f.write_str(")")?;
                 }
@@ -168,6 +543,7 @@ impl fmt::Display for HttpError {
             Self::InvalidScheme => f.write_str("Invalid Url scheme."),
             Self::InvalidPort => f.write_str("Invalid port."),
             Self::ApplicationIdMissing => f.write_str("Application id was expected but missing."),
+            Self::CaptchaRequired(_) => f.write_str("A captcha is required for the request."),
         }
     }
 }
@@ -185,59 +561,55 @@ impl StdError for HttpError {
 #[allow(clippy::missing_errors_doc)]
 pub fn deserialize_errors<'de, D: Deserializer<'de>>(
     deserializer: D,
-) -> StdResult<Vec<DiscordJsonSingleError>, D::Error> {
+) -> StdResult<BTreeMap<FieldPath, Vec<FieldError>>, D::Error> {
     let map: Value = Value::deserialize(deserializer)?;
 
     if !map.is_object() {
-        return Ok(vec![]);
+        return Ok(BTreeMap::new());
     }
 
-    let mut errors = Vec::new();
+    let mut errors = BTreeMap::new();
     let mut path = Vec::new();
     loop_errors(&map, &mut errors, &mut path).map_err(D::Error::custom)?;
 
     Ok(errors)
 }
 
-fn make_error(
-    errors_value: &Value,
-    errors: &mut Vec<DiscordJsonSingleError>,
-    path: &[&str],
-) -> StdResult<(), &'static str> {
-    let found_errors = errors_value.as_array().ok_or("expected array")?;
-
-    for error in found_errors {
-        let error_object = error.as_object().ok_or("expected object")?;
-
-        errors.push(DiscordJsonSingleError {
-            code: error_object
-                .get("code")
-                .ok_or("expected code")?
-                .as_str()
-                .ok_or("expected string")?
-                .to_owned(),
-            message: error_object
-                .get("message")
-                .ok_or("expected message")?
-                .as_str()
-                .ok_or("expected string")?
-                .to_owned(),
-            path: path.join("."),
-        });
+/// Serializes the errors back into the nested objects Discord sends them as.
+#[allow(clippy::missing_errors_doc)]
+pub fn serialize_errors<S: Serializer>(
+    errors: &BTreeMap<FieldPath, Vec<FieldError>>,
+    serializer: S,
+) -> StdResult<S::Ok, S::Error> {
+    let conflict = || S::Error::custom("conflicting error paths");
+    let mut root = json!({});
+
+    for (path, field_errors) in errors {
+        let mut object = root.as_object_mut().ok_or_else(conflict)?;
+        for segment in &path.0 {
+            let entry = object.entry(segment.to_string()).or_insert_with(|| json!({}));
+            object = entry.as_object_mut().ok_or_else(conflict)?;
+        }
+
+        let value = to_value(field_errors).map_err(S::Error::custom)?;
+        object.insert("_errors".to_string(), value);
     }
-    Ok(())
+
+    root.serialize(serializer)
 }
 
-fn loop_errors<'a>(
-    value: &'a Value,
-    errors: &mut Vec<DiscordJsonSingleError>,
-    path: &mut Vec<&'a str>,
-) -> StdResult<(), &'static str> {
+fn loop_errors(
+    value: &Value,
+    errors: &mut BTreeMap<FieldPath, Vec<FieldError>>,
+    path: &mut Vec<FieldPathSegment>,
+) -> StdResult<(), String> {
     for (key, value) in value.as_object().ok_or("expected object")? {
         if key == "_errors" {
-            make_error(value, errors, path)?;
+            let found_errors: Vec<FieldError> =
+                from_value(value.clone()).map_err(|e| e.to_string())?;
+            errors.entry(FieldPath(path.clone())).or_default().extend(found_errors);
         } else {
-            path.push(key);
+            path.push(FieldPathSegment::from(key.as_str()));
             loop_errors(value, errors, path)?;
             path.pop();
         }
@@ -255,9 +627,9 @@ mod test {
     #[tokio::test]
     async fn test_error_response_into() {
         let error = DiscordJsonError {
-            code: 43121215,
+            code: JsonErrorCode::Unknown(43121215),
             message: String::from("This is a Ferris error"),
-            errors: vec![],
+            errors: BTreeMap::new(),
         };
 
         let mut builder = Builder::new();
@@ -278,4 +650,76 @@ mod test {
 
         assert_eq!(error_response, known);
     }
+
+    #[tokio::test]
+    async fn captcha_required() {
+        let body = json!({
+            "captcha_key": ["captcha-required"],
+            "captcha_sitekey": "a9b5fb07-92ff-493f-86fe-352a2803b3df",
+            "captcha_service": "hcaptcha",
+            "captcha_rqdata": "data",
+            "captcha_rqtoken": "token",
+        });
+        let response = Builder::new().status(400).body(to_vec(&body).unwrap()).unwrap();
+
+        let error = HttpError::from_response(response.into(), Method::POST).await;
+        let HttpError::CaptchaRequired(captcha) = &error else {
+            panic!("expected a captcha, got {error:?}");
+        };
+        assert_eq!(captcha.captcha_key, ["captcha-required"]);
+        assert_eq!(captcha.captcha_service.as_deref(), Some("hcaptcha"));
+        assert_eq!(captcha.captcha_rqtoken.as_deref(), Some("token"));
+        assert_eq!(captcha.captcha_session_id, None);
+        assert!(error.is_captcha_required());
+
+        let body = json!({"code": 50035, "message": "Invalid Form Body"});
+        let response = Builder::new().status(400).body(to_vec(&body).unwrap()).unwrap();
+        let error = HttpError::from_response(response.into(), Method::POST).await;
+        assert!(error.is_invalid_form_body());
+    }
+
+    #[test]
+    fn invalid_form_body() {
+        let body = json!({
+            "code": 50035,
+            "message": "Invalid Form Body",
+            "errors": {
+                "embeds": {"0": {"title": {"_errors": [
+                    {"code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 256 or fewer in length."},
+                ]}}},
+                "content": {"_errors": [
+                    {"code": "BASE_TYPE_REQUIRED", "message": "This field is required"},
+                ]},
+            },
+        });
+        let error: DiscordJsonError = from_value(body.clone()).unwrap();
+
+        assert_eq!(error.code, JsonErrorCode::InvalidFormBody);
+        let title = FieldPath(vec![
+            FieldPathSegment::Key("embeds".into()),
+            FieldPathSegment::Index(0),
+            FieldPathSegment::Key("title".into()),
+        ]);
+        assert_eq!(FieldPath::from("embeds.0.title"), title);
+        assert_eq!(error.errors[&title][0].code, "BASE_TYPE_MAX_LENGTH");
+        assert_eq!(error.errors[&FieldPath::from("content")].len(), 1);
+        assert_eq!(to_value(&error).unwrap(), body);
+
+        let error = HttpError::UnsuccessfulRequest(ErrorResponse {
+            status_code: StatusCode::BAD_REQUEST,
+            url: String::new(),
+            method: Method::POST,
+            error,
+        });
+        assert!(error.is_invalid_form_body());
+        assert!(!error.is_missing_permissions());
+        assert!(!error.is_unknown_resource());
+        assert_eq!(
+            error.to_string(),
+            "Invalid Form Body (content: This field is required, embeds.0.title: Must be 256 or \
+             fewer in length.)"
+        );
+        assert!(JsonErrorCode::UnknownChannel.is_unknown_resource());
+        assert_eq!(JsonErrorCode::from(12345), JsonErrorCode::Unknown(12345));
+    }
 }
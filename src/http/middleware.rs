@@ -0,0 +1,224 @@
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Error as ReqwestError, Response, StatusCode};
+use tokio::time::{Duration, Instant};
+
+use super::request::SuperProperties;
+use super::{ApiBase, LightMethod, Request, Route};
+use crate::internal::prelude::*;
+
+/// Passed to [`Middleware::after_receive`] for each response received.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ResponseInfo<'a> {
+    pub route: Route<'a>,
+    pub method: LightMethod,
+    pub status: StatusCode,
+    pub headers: &'a HeaderMap,
+    /// The time between sending the request and receiving the headers of the response.
+    pub elapsed: Duration,
+}
+
+/// Passed to [`Middleware::after_error`] for each request that failed to be sent or receive a
+/// response, such as when the connection was refused or timed out.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ErrorInfo<'a> {
+    pub route: Route<'a>,
+    pub method: LightMethod,
+    pub error: &'a ReqwestError,
+    /// The time between sending the request and it failing.
+    pub elapsed: Duration,
+}
+
+/// Hooks into the requests [`Http`] sends, such as to add headers or record metrics.
+///
+/// Middleware is added through [`HttpBuilder::middleware`], and runs in the order it was added.
+/// It runs for every request that is sent, including each retry, whether the requests go through
+/// the [`Ratelimiter`] or not.
+///
+/// ```rust
+/// # use serenity::http::{Middleware, Request, ResponseInfo};
+/// # use serenity::Result;
+/// #[derive(Debug)]
+/// struct Latency;
+///
+/// #[serenity::async_trait]
+/// impl Middleware for Latency {
+///     async fn after_receive(&self, response: &ResponseInfo<'_>) {
+///         println!("{:?} took {:?}", response.route, response.elapsed);
+///     }
+/// }
+/// ```
+///
+/// [`Http`]: super::Http
+/// [`HttpBuilder::middleware`]: super::HttpBuilder::middleware
+/// [`Ratelimiter`]: super::Ratelimiter
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent, with the request and its headers. Returning an error
+    /// fails the request with it instead of sending it.
+    async fn before_send(&self, _req: &mut Request<'_>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once the response to a request has been received, before its body is read.
+    async fn after_receive(&self, _response: &ResponseInfo<'_>) {}
+
+    /// Called instead of [`Self::after_receive`] if no response to a request was received.
+    async fn after_error(&self, _error: &ErrorInfo<'_>) {}
+}
+
+/// The middleware of an [`Http`] client, in the order it runs.
+///
+/// [`Http`]: super::Http
+#[derive(Clone, Default)]
+pub(super) struct MiddlewareChain(pub(super) Vec<Arc<dyn Middleware>>);
+
+impl fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter().map(|_| "Middleware")).finish()
+    }
+}
+
+impl MiddlewareChain {
+    /// Runs the request through the middleware and sends it, then runs the response, or the error
+    /// if none was received, through the middleware.
+    pub(super) async fn send(
+        &self,
+        client: &Client,
+        mut req: Request<'_>,
+        token: &str,
+        api_base: &ApiBase,
+        properties: &SuperProperties,
+    ) -> Result<Response> {
+        for middleware in &self.0 {
+            middleware.before_send(&mut req).await?;
+        }
+
+        let (route, method) = (req.route, req.method);
+        let request =
+            req.build_with(client, token, api_base, properties).map_err(|e| *e)?.build()?;
+
+        let started = Instant::now();
+        let response = match client.execute(request).await {
+            Ok(response) => response,
+            Err(error) => {
+                let info = ErrorInfo {
+                    route,
+                    method,
+                    error: &error,
+                    elapsed: started.elapsed(),
+                };
+                for middleware in &self.0 {
+                    middleware.after_error(&info).await;
+                }
+
+                return Err(error.into());
+            },
+        };
+        let info = ResponseInfo {
+            route,
+            method,
+            status: response.status(),
+            headers: response.headers(),
+            elapsed: started.elapsed(),
+        };
+        for middleware in &self.0 {
+            middleware.after_receive(&info).await;
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error as IoError, ErrorKind};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+    use crate::http::test_server;
+
+    #[derive(Default)]
+    struct Recorder {
+        statuses: Mutex<Vec<StatusCode>>,
+        errors: Mutex<usize>,
+    }
+
+    #[async_trait]
+    impl Middleware for Recorder {
+        async fn before_send(&self, req: &mut Request<'_>) -> Result<()> {
+            let headers = req.headers_mut().get_or_insert_with(HeaderMap::new);
+            headers.insert("X-Test", HeaderValue::from_static("1"));
+            Ok(())
+        }
+
+        async fn after_receive(&self, response: &ResponseInfo<'_>) {
+            assert_eq!(response.method, LightMethod::Get);
+            self.statuses.lock().unwrap().push(response.status);
+        }
+
+        async fn after_error(&self, error: &ErrorInfo<'_>) {
+            assert!(error.error.is_connect());
+            *self.errors.lock().unwrap() += 1;
+        }
+    }
+
+    struct Fault;
+
+    #[async_trait]
+    impl Middleware for Fault {
+        async fn before_send(&self, _req: &mut Request<'_>) -> Result<()> {
+            Err(IoError::new(ErrorKind::Other, "fault").into())
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_chain() {
+        let (url, server) = test_server(["HTTP/1.1 204 No Content\r\n\r\n"]);
+        let api_base = ApiBase::new().api(format!("{url}/api"));
+
+        let client = Client::new();
+        let properties = SuperProperties::default();
+        let recorder = Arc::new(Recorder::default());
+        let chain = MiddlewareChain(vec![recorder.clone()]);
+        let req = Request::new(Route::Gateway, LightMethod::Get);
+
+        let response = chain.send(&client, req.clone(), "token", &api_base, &properties).await;
+        assert_eq!(response.unwrap().status(), StatusCode::NO_CONTENT);
+        assert!(server.join().unwrap()[0].to_lowercase().contains("x-test: 1\r\n"));
+        assert_eq!(*recorder.statuses.lock().unwrap(), [StatusCode::NO_CONTENT]);
+
+        // Failing middleware keeps the request from being sent.
+        let chain = MiddlewareChain(vec![Arc::new(Fault), recorder.clone()]);
+        let response = chain.send(&client, req, "token", &api_base, &properties).await;
+        assert!(matches!(response, Err(Error::Io(_))));
+        assert_eq!(recorder.statuses.lock().unwrap().len(), 1);
+        assert_eq!(*recorder.errors.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn middleware_errors() {
+        // Nothing listens at the address once the listener is dropped.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let api_base = ApiBase::new().api(format!("http://{}/api", listener.local_addr().unwrap()));
+        drop(listener);
+
+        let recorder = Arc::new(Recorder::default());
+        let chain = MiddlewareChain(vec![recorder.clone()]);
+        let req = Request::new(Route::Gateway, LightMethod::Get);
+        let properties = SuperProperties::default();
+
+        let response = chain.send(&Client::new(), req, "token", &api_base, &properties).await;
+        assert!(matches!(response, Err(Error::Http(_))));
+        assert!(recorder.statuses.lock().unwrap().is_empty());
+        assert_eq!(*recorder.errors.lock().unwrap(), 1);
+    }
+}
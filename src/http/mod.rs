@@ -19,11 +19,16 @@
 //! [`Client`]: crate::Client
 //! [model]: crate::model
 
+mod api_base;
+mod captcha;
 mod client;
 mod error;
+mod middleware;
 mod multipart;
+mod ratelimit_store;
 mod ratelimiting;
 mod request;
+mod retry;
 mod routing;
 mod typing;
 
@@ -32,11 +37,16 @@ use std::sync::Arc;
 use reqwest::Method;
 pub use reqwest::StatusCode;
 
+pub use self::api_base::*;
+pub use self::captcha::*;
 pub use self::client::*;
 pub use self::error::*;
+pub use self::middleware::{ErrorInfo, Middleware, ResponseInfo};
 pub use self::multipart::*;
+pub use self::ratelimit_store::*;
 pub use self::ratelimiting::*;
 pub use self::request::*;
+pub use self::retry::*;
 pub use self::routing::*;
 pub use self::typing::*;
 #[cfg(feature = "cache")]
@@ -191,3 +201,35 @@ pub enum MessagePagination {
     Around(MessageId),
     Before(MessageId),
 }
+
+/// Serves each of the raw HTTP responses to a connection of its own, in order, on a local port.
+///
+/// Returns the URL of the server, and a handle to the raw requests it received.
+#[cfg(test)]
+pub(crate) fn test_server(
+    responses: impl IntoIterator<Item = impl Into<String>>,
+) -> (String, std::thread::JoinHandle<Vec<String>>) {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let responses: Vec<String> = responses.into_iter().map(Into::into).collect();
+    let server = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..read]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            requests.push(String::from_utf8(request).unwrap());
+        }
+        requests
+    });
+
+    (url, server)
+}
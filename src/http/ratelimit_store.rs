@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::num::NonZeroU64;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use reqwest::Response;
+use tokio::fs::{self, OpenOptions};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::{sleep, Duration, Instant};
+
+use super::ratelimiting::{wait_for_reset, wait_for_retry, RatelimitHeaders};
+use super::{Ratelimit, RatelimitInfo, RatelimitKey, RatelimitingBucket, Request};
+use crate::internal::prelude::*;
+use crate::json::{from_slice, to_vec};
+
+/// How long a bucket has to go unused, after it has reset, before it's forgotten.
+pub(super) const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long to wait before trying to lock a [`FileRatelimitStore`] again.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(5);
+/// How long a [`FileRatelimitStore`] may stay locked before the lock is considered left behind by
+/// a process that crashed.
+const STALE_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The ratelimits shared by the routes in a bucket, by its hash and the major parameter.
+type SharedBuckets = HashMap<(String, Option<NonZeroU64>), Arc<Mutex<Ratelimit>>>;
+
+/// Keeps track of ratelimits for a [`Ratelimiter`], which goes through the store before and after
+/// each request.
+///
+/// [`LocalRatelimitStore`] keeps track of them within a single process. Implement this trait to
+/// share them between several processes using the same token, as [`FileRatelimitStore`] does.
+///
+/// [`Ratelimiter`]: super::Ratelimiter
+#[async_trait]
+pub trait RatelimitStore: Send + Sync {
+    /// Waits until requests aren't held back by the global ratelimit.
+    async fn wait_global(&self) -> Result<()>;
+
+    /// Holds back every request for `retry_after`, after a response said the global ratelimit has
+    /// been hit. Returns once it's over.
+    async fn hold_global(&self, retry_after: Duration) -> Result<()>;
+
+    /// Takes one of the remaining requests of the bucket before a request, waiting for the bucket
+    /// to reset if there are none left. See [`Ratelimit::pre_hook`].
+    async fn pre_hook(
+        &self,
+        key: &RatelimitKey,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+    ) -> Result<()>;
+
+    /// Updates the bucket from the headers of the response to a request, returning whether the
+    /// request was ratelimited and has to be retried. See [`Ratelimit::post_hook`].
+    async fn post_hook(
+        &self,
+        key: &RatelimitKey,
+        response: &Response,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+        absolute_ratelimits: bool,
+    ) -> Result<bool>;
+}
+
+/// A [`RatelimitStore`] keeping track of ratelimits within this process.
+///
+/// Buckets that go unused for a few minutes after resetting are forgotten.
+#[derive(Debug)]
+pub struct LocalRatelimitStore {
+    global: Mutex<()>,
+    routes: Arc<RwLock<HashMap<RatelimitingBucket, Arc<Mutex<Ratelimit>>>>>,
+    buckets: StdMutex<SharedBuckets>,
+    evicted_at: StdMutex<Instant>,
+}
+
+impl Default for LocalRatelimitStore {
+    fn default() -> Self {
+        Self {
+            global: Mutex::default(),
+            routes: Arc::default(),
+            buckets: StdMutex::default(),
+            evicted_at: StdMutex::new(Instant::now()),
+        }
+    }
+}
+
+impl LocalRatelimitStore {
+    /// The routes mutex is a HashMap of each [`RatelimitingBucket`] and their respective
+    /// ratelimit information. Routes in the same bucket share their ratelimit information.
+    ///
+    /// # Examples
+    ///
+    /// View the `reset` time of the route for `ChannelsId(7)`:
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    ///
+    /// use serenity::http::{HttpBuilder, LocalRatelimitStore, Route};
+    /// # use serenity::model::prelude::*;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let store = LocalRatelimitStore::default();
+    /// let routes = store.routes();
+    /// let http = HttpBuilder::new("token").ratelimit_store(Arc::new(store)).build();
+    ///
+    /// // after some requests have been made through `http`
+    /// let reader = routes.read().await;
+    ///
+    /// let channel_id = ChannelId::new(7);
+    /// let route = Route::Channel {
+    ///     channel_id,
+    /// };
+    /// if let Some(route) = reader.get(&route.ratelimiting_bucket()) {
+    ///     if let Some(reset) = route.lock().await.reset() {
+    ///         println!("Reset time at: {:?}", reset);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn routes(&self) -> Arc<RwLock<HashMap<RatelimitingBucket, Arc<Mutex<Ratelimit>>>>> {
+        Arc::clone(&self.routes)
+    }
+
+    /// Returns the ratelimit of the bucket, moving the route into the bucket of its hash once it's
+    /// known.
+    async fn bucket(&self, key: &RatelimitKey) -> Arc<Mutex<Ratelimit>> {
+        let mut routes = self.routes.write().await;
+        let bucket = routes.entry(key.ratelimiting_bucket()).or_default();
+
+        if let Some(hash) = key.hash() {
+            let mut buckets = self.buckets.lock().expect("poison");
+            // The current ratelimit of the route becomes the shared one if there's none yet.
+            let shared = buckets
+                .entry((hash.to_string(), key.major_parameter()))
+                .or_insert_with(|| Arc::clone(bucket));
+            if !Arc::ptr_eq(bucket, shared) {
+                *bucket = Arc::clone(shared);
+            }
+        }
+
+        Arc::clone(bucket)
+    }
+
+    /// Forgets the buckets that have been idle for [`BUCKET_IDLE_TIMEOUT`], checking at most once
+    /// per timeout.
+    async fn evict_idle(&self, now: Instant) {
+        {
+            let mut evicted_at = self.evicted_at.lock().expect("poison");
+            if now.saturating_duration_since(*evicted_at) < BUCKET_IDLE_TIMEOUT {
+                return;
+            }
+            *evicted_at = now;
+        }
+
+        let mut routes = self.routes.write().await;
+        // Buckets that are locked are in use.
+        routes.retain(|_, bucket| bucket.try_lock().map_or(true, |bucket| !bucket.is_idle(now)));
+        // Shared ratelimits that no route uses anymore are only held onto here.
+        self.buckets.lock().expect("poison").retain(|_, bucket| Arc::strong_count(bucket) > 1);
+    }
+}
+
+#[async_trait]
+impl RatelimitStore for LocalRatelimitStore {
+    async fn wait_global(&self) -> Result<()> {
+        drop(self.global.lock().await);
+        Ok(())
+    }
+
+    async fn hold_global(&self, retry_after: Duration) -> Result<()> {
+        let _global = self.global.lock().await;
+        sleep(retry_after).await;
+        Ok(())
+    }
+
+    async fn pre_hook(
+        &self,
+        key: &RatelimitKey,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+    ) -> Result<()> {
+        self.evict_idle(Instant::now()).await;
+
+        self.bucket(key).await.lock().await.pre_hook(req, ratelimit_callback).await;
+        Ok(())
+    }
+
+    async fn post_hook(
+        &self,
+        key: &RatelimitKey,
+        response: &Response,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+        absolute_ratelimits: bool,
+    ) -> Result<bool> {
+        let bucket = self.bucket(key).await;
+        let mut bucket = bucket.lock().await;
+        bucket.post_hook(response, req, ratelimit_callback, absolute_ratelimits).await
+    }
+}
+
+/// A [`RatelimitStore`] keeping ratelimits in a file, which lets processes on the same machine
+/// that use the same token share them.
+///
+/// Every update locks the file by creating a lock file next to it, named after it with a `.lock`
+/// extension added. Lock files left behind by processes that crashed are removed after 10
+/// seconds.
+///
+/// ```rust,no_run
+/// # use std::sync::Arc;
+/// use serenity::http::{FileRatelimitStore, HttpBuilder};
+///
+/// let http = HttpBuilder::new("token")
+///     .ratelimit_store(Arc::new(FileRatelimitStore::new("/tmp/ratelimits.json")))
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct FileRatelimitStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl FileRatelimitStore {
+    /// Creates a store keeping ratelimits in the file at the given path, which is created once
+    /// it's first updated.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+
+        Self {
+            path,
+            lock_path: lock_path.into(),
+        }
+    }
+
+    /// Reads the ratelimits from the file, which are replaced all at once so that they don't need
+    /// to be locked for reading.
+    async fn read(&self) -> Result<StoredRatelimits> {
+        match fs::read(&self.path).await {
+            // A file that can't be decoded only loses the ratelimits it kept track of.
+            Ok(bytes) => Ok(from_slice(&bytes).unwrap_or_default()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(StoredRatelimits::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Updates the ratelimits in the file while holding the lock on it.
+    async fn update<T: Send>(
+        &self,
+        f: impl FnOnce(&mut StoredRatelimits) -> T + Send,
+    ) -> Result<T> {
+        self.lock().await?;
+
+        let result = self.update_locked(f).await;
+        let unlocked = fs::remove_file(&self.lock_path).await;
+
+        let result = result?;
+        unlocked?;
+        Ok(result)
+    }
+
+    async fn update_locked<T: Send>(
+        &self,
+        f: impl FnOnce(&mut StoredRatelimits) -> T + Send,
+    ) -> Result<T> {
+        let mut ratelimits = self.read().await?;
+        let result = f(&mut ratelimits);
+        ratelimits.evict_idle(SystemTime::now());
+
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(format!(".{}", std::process::id()));
+        fs::write(&temp_path, to_vec(&ratelimits)?).await?;
+        fs::rename(&temp_path, &self.path).await?;
+
+        Ok(result)
+    }
+
+    async fn lock(&self) -> Result<()> {
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&self.lock_path).await {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {},
+                Err(e) => return Err(e.into()),
+            }
+
+            let modified = fs::metadata(&self.lock_path).await.and_then(|m| m.modified());
+            let stale = modified
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|elapsed| elapsed >= STALE_LOCK_TIMEOUT);
+            if stale {
+                // Another process may have removed it already.
+                drop(fs::remove_file(&self.lock_path).await);
+            } else {
+                sleep(LOCK_RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RatelimitStore for FileRatelimitStore {
+    async fn wait_global(&self) -> Result<()> {
+        let global = self.read().await?.global;
+        if let Some(delay) = global.and_then(|global| global.duration_since(SystemTime::now()).ok())
+        {
+            sleep(delay).await;
+        }
+
+        Ok(())
+    }
+
+    async fn hold_global(&self, retry_after: Duration) -> Result<()> {
+        let until = SystemTime::now() + retry_after;
+        self.update(|ratelimits| {
+            ratelimits.global = Some(ratelimits.global.map_or(until, |global| global.max(until)));
+        })
+        .await?;
+
+        sleep(retry_after).await;
+        Ok(())
+    }
+
+    async fn pre_hook(
+        &self,
+        key: &RatelimitKey,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+    ) -> Result<()> {
+        let key = key.to_string();
+        let (delay, limit) = self
+            .update(|ratelimits| {
+                let bucket = ratelimits.buckets.entry(key).or_default();
+                let mut ratelimit = bucket.load();
+                let delay = ratelimit.take_ticket();
+                *bucket = StoredRatelimit::from(&ratelimit);
+                (delay, ratelimit.limit)
+            })
+            .await?;
+
+        // Wait with the file unlocked, so that other buckets can be used in the meantime.
+        if let Some(delay) = delay {
+            wait_for_reset(delay, limit, req, ratelimit_callback).await;
+        }
+
+        Ok(())
+    }
+
+    async fn post_hook(
+        &self,
+        key: &RatelimitKey,
+        response: &Response,
+        req: &Request<'_>,
+        ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+        absolute_ratelimits: bool,
+    ) -> Result<bool> {
+        let key = key.to_string();
+        // Parsed before locking the file, so that the update itself can't fail.
+        let headers = RatelimitHeaders::parse(response.headers()).map_err(|e| *e)?;
+        let limit = self
+            .update(|ratelimits| {
+                let bucket = ratelimits.buckets.entry(key).or_default();
+                let mut ratelimit = bucket.load();
+                ratelimit.update(&headers, absolute_ratelimits);
+                *bucket = StoredRatelimit::from(&ratelimit);
+                ratelimit.limit
+            })
+            .await?;
+
+        wait_for_retry(response, limit, req, ratelimit_callback).await
+    }
+}
+
+/// The contents of the file of a [`FileRatelimitStore`].
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct StoredRatelimits {
+    /// When the global ratelimit is over.
+    global: Option<SystemTime>,
+    buckets: HashMap<String, StoredRatelimit>,
+}
+
+impl StoredRatelimits {
+    /// Forgets the buckets that reset at least [`BUCKET_IDLE_TIMEOUT`] ago, along with those that
+    /// never had a reset time, as they're the same as new ones.
+    fn evict_idle(&mut self, now: SystemTime) {
+        self.buckets.retain(|_, bucket| {
+            bucket.reset.is_some_and(|reset| reset + BUCKET_IDLE_TIMEOUT > now)
+        });
+        self.global = self.global.filter(|&global| global > now);
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StoredRatelimit {
+    limit: i64,
+    remaining: i64,
+    reset: Option<SystemTime>,
+    reset_after: Option<Duration>,
+}
+
+impl StoredRatelimit {
+    fn load(&self) -> Ratelimit {
+        let mut ratelimit = Ratelimit::default();
+        ratelimit.limit = self.limit;
+        ratelimit.remaining = self.remaining;
+        ratelimit.reset = self.reset;
+        ratelimit.reset_after = self.reset_after;
+        ratelimit
+    }
+}
+
+impl Default for StoredRatelimit {
+    fn default() -> Self {
+        Self::from(&Ratelimit::default())
+    }
+}
+
+impl From<&Ratelimit> for StoredRatelimit {
+    fn from(ratelimit: &Ratelimit) -> Self {
+        Self {
+            limit: ratelimit.limit,
+            remaining: ratelimit.remaining,
+            reset: ratelimit.reset,
+            reset_after: ratelimit.reset_after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+
+    use super::*;
+    use crate::http::{Ratelimiter, Route};
+    use crate::model::id::{ChannelId, MessageId};
+
+    #[tokio::test]
+    async fn shared_buckets() {
+        let ratelimiter = Ratelimiter::new(Client::new(), "token");
+        let store = LocalRatelimitStore::default();
+        let key = |route: Route<'_>| ratelimiter.key(route.ratelimiting_bucket());
+        let channel_id = ChannelId::new(1);
+        let messages = Route::ChannelMessages {
+            channel_id,
+        };
+        let message = Route::ChannelMessage {
+            channel_id,
+            message_id: MessageId::new(2),
+        };
+        let other_channel = Route::ChannelMessages {
+            channel_id: ChannelId::new(3),
+        };
+
+        let bucket = store.bucket(&key(messages)).await;
+        assert!(!Arc::ptr_eq(&bucket, &store.bucket(&key(message)).await));
+        assert_eq!(key(message).to_string(), "ChannelMessage:1");
+
+        // Routes seen in a bucket share its ratelimit, which starts out as that of the first.
+        for route in [messages, message] {
+            let mut key = key(route);
+            ratelimiter.learn_hash(&mut key, "abcd");
+            assert!(Arc::ptr_eq(&bucket, &store.bucket(&key).await));
+        }
+        assert!(Arc::ptr_eq(&bucket, &store.bucket(&key(message)).await));
+        assert_eq!(key(message).to_string(), "abcd:1");
+
+        // Other major parameters share the hash of the route, but not its ratelimit.
+        let other_key = key(other_channel);
+        assert_eq!(other_key.hash(), Some("abcd"));
+        let other_bucket = store.bucket(&other_key).await;
+        assert!(!Arc::ptr_eq(&bucket, &other_bucket));
+
+        drop((bucket, other_bucket));
+        store.evict_idle(Instant::now()).await;
+        assert_eq!(store.routes.read().await.len(), 3);
+
+        store.evict_idle(Instant::now() + BUCKET_IDLE_TIMEOUT).await;
+        assert!(store.routes.read().await.is_empty());
+        assert!(store.buckets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn file_store() {
+        let path = std::env::temp_dir().join(format!("serenity-{}.json", std::process::id()));
+        let first = FileRatelimitStore::new(&path);
+        let second = FileRatelimitStore::new(&path);
+        let route = Route::ChannelMessages {
+            channel_id: ChannelId::new(1),
+        };
+        let key = RatelimitKey::new(route.ratelimiting_bucket(), Some("abcd".into()));
+
+        first
+            .update(|ratelimits| {
+                let mut ratelimit = StoredRatelimit {
+                    limit: 5,
+                    remaining: 1,
+                    reset: Some(SystemTime::now() + Duration::from_secs(60)),
+                    reset_after: None,
+                }
+                .load();
+                assert_eq!(ratelimit.take_ticket(), None);
+                ratelimits.buckets.insert(key.to_string(), StoredRatelimit::from(&ratelimit));
+            })
+            .await
+            .unwrap();
+
+        // The other store sees the bucket is exhausted.
+        let delay = second
+            .update(|ratelimits| ratelimits.buckets[&key.to_string()].load().take_ticket())
+            .await
+            .unwrap();
+        assert!(delay.is_some_and(|delay| delay > Duration::from_secs(50)));
+        assert!(!first.lock_path.exists());
+
+        fs::remove_file(&path).await.unwrap();
+    }
+}
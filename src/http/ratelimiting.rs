@@ -37,20 +37,35 @@
 
 use std::collections::HashMap;
 use std::fmt;
+use std::num::NonZeroU64;
 use std::str::{self, FromStr};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::SystemTime;
 
-use reqwest::header::HeaderMap;
+use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::{Client, Response, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
 use tokio::sync::{Mutex, RwLock};
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 use tracing::{debug, instrument};
 
+use super::middleware::MiddlewareChain;
+use super::ratelimit_store::BUCKET_IDLE_TIMEOUT;
+use super::request::SuperProperties;
 pub use super::routing::RatelimitingBucket;
-use super::{HttpError, LightMethod, Request};
+use super::{
+    ApiBase,
+    CaptchaHandler,
+    HttpError,
+    LightMethod,
+    LocalRatelimitStore,
+    RatelimitStore,
+    Request,
+    RetryInfo,
+    RetryPolicy,
+};
 use crate::internal::prelude::*;
+use crate::model::gateway::ClientProperties;
 
 /// Passed to the [`Ratelimiter::set_ratelimit_callback`] callback. If using Client, that callback
 /// is initialized to call the `EventHandler::ratelimit()` method.
@@ -78,29 +93,52 @@ pub struct RatelimitInfo {
 /// global ratelimit is never given through the API, so it can't be pre-emptively ratelimited. This
 /// only affects the largest of bots.
 ///
+/// Discord groups routes into buckets of its own, which are told apart by the
+/// `x-ratelimit-bucket` header of responses. Once a route has been seen in a bucket, it shares its
+/// [`Ratelimit`] with the other routes of the bucket that have the same major parameter.
+///
+/// The ratelimits are kept track of by a [`RatelimitStore`], which is a [`LocalRatelimitStore`]
+/// unless one is set through [`Self::set_store`]. Processes sharing a token can share their
+/// ratelimits through a store of their own, such as a [`FileRatelimitStore`].
+///
+/// [`FileRatelimitStore`]: super::FileRatelimitStore
+///
 /// [`limit`]: Ratelimit::limit
 /// [`remaining`]: Ratelimit::remaining
 /// [`reset`]: Ratelimit::reset
 pub struct Ratelimiter {
     client: Client,
-    global: Arc<Mutex<()>>,
-    // When futures is implemented, make tasks clear out their respective entry when the 'reset'
-    // passes.
+    store: Arc<dyn RatelimitStore>,
     routes: Arc<RwLock<HashMap<RatelimitingBucket, Arc<Mutex<Ratelimit>>>>>,
+    /// The hash of the bucket each route was last seen in.
+    hashes: StdMutex<HashMap<&'static str, String>>,
     token: SecretString,
+    client_properties: SuperProperties,
+    api_base: ApiBase,
     absolute_ratelimits: bool,
     ratelimit_callback: Box<dyn Fn(RatelimitInfo) + Send + Sync>,
+    retry_policy: Option<RetryPolicy>,
+    retry_callback: Box<dyn Fn(RetryInfo) + Send + Sync>,
+    captcha_handler: Option<Arc<dyn CaptchaHandler>>,
+    middleware: MiddlewareChain,
 }
 
 impl fmt::Debug for Ratelimiter {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Ratelimiter")
             .field("client", &self.client)
-            .field("global", &self.global)
+            .field("store", &"RatelimitStore")
             .field("routes", &self.routes)
+            .field("hashes", &self.hashes)
             .field("token", &self.token)
+            .field("client_properties", &self.client_properties)
+            .field("api_base", &self.api_base)
             .field("absolute_ratelimits", &self.absolute_ratelimits)
             .field("ratelimit_callback", &"Fn(RatelimitInfo)")
+            .field("retry_policy", &self.retry_policy)
+            .field("retry_callback", &"Fn(RetryInfo)")
+            .field("captcha_handler", &self.captcha_handler.as_ref().map(|_| "CaptchaHandler"))
+            .field("middleware", &self.middleware)
             .finish()
     }
 }
@@ -115,13 +153,22 @@ impl Ratelimiter {
     }
 
     fn new_(client: Client, token: String) -> Self {
+        let store = LocalRatelimitStore::default();
+
         Self {
             client,
-            global: Arc::default(),
-            routes: Arc::default(),
+            routes: store.routes(),
+            store: Arc::new(store),
+            hashes: StdMutex::default(),
             token: SecretString::new(token),
+            client_properties: SuperProperties::default(),
+            api_base: ApiBase::default(),
             ratelimit_callback: Box::new(|_| {}),
+            retry_policy: None,
+            retry_callback: Box::new(|_| {}),
             absolute_ratelimits: false,
+            captcha_handler: None,
+            middleware: MiddlewareChain::default(),
         }
     }
 
@@ -133,43 +180,75 @@ impl Ratelimiter {
         self.ratelimit_callback = ratelimit_callback;
     }
 
-    // Sets whether absolute ratelimits should be used.
-    pub fn set_absolute_ratelimits(&mut self, absolute_ratelimits: bool) {
-        self.absolute_ratelimits = absolute_ratelimits;
+    /// Sets the policy for retrying requests that failed transiently, such as those answered with
+    /// a status of 503. Requests aren't retried by default.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = Some(retry_policy);
     }
 
-    /// The routes mutex is a HashMap of each [`RatelimitingBucket`] and their respective ratelimit
-    /// information.
+    /// Sets a callback to be called before a request is retried according to the
+    /// [`RetryPolicy`].
+    pub fn set_retry_callback(&mut self, retry_callback: Box<dyn Fn(RetryInfo) + Send + Sync>) {
+        self.retry_callback = retry_callback;
+    }
+
+    /// Sets the handler asked to solve captchas that requests are answered with, after which the
+    /// request is replayed with the solution.
     ///
-    /// See the documentation for [`Ratelimit`] for more information on how the library handles
-    /// ratelimiting.
+    /// With a handler set, [`Self::perform`] returns [`HttpError`]s for non-successful responses
+    /// with a status of 400 instead of the response itself, as their body has to be read.
+    pub fn set_captcha_handler(&mut self, captcha_handler: Arc<dyn CaptchaHandler>) {
+        self.captcha_handler = Some(captcha_handler);
+    }
+
+    /// Sets the client properties sent along with every request.
     ///
-    /// # Examples
+    /// This is done by [`HttpBuilder::build`], so only needs calling on a ratelimiter used on its
+    /// own.
     ///
-    /// View the `reset` time of the route for `ChannelsId(7)`:
+    /// [`HttpBuilder::build`]: super::HttpBuilder::build
+    pub fn set_client_properties(&mut self, client_properties: ClientProperties) {
+        self.client_properties = client_properties.into();
+    }
+
+    /// Sets where requests are sent to.
     ///
-    /// ```rust,no_run
-    /// use serenity::http::Route;
-    /// # use serenity::http::Http;
-    /// # use serenity::model::prelude::*;
+    /// This is done by [`HttpBuilder::build`], so only needs calling on a ratelimiter used on its
+    /// own.
     ///
-    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    /// # let http: Http = unimplemented!();
-    /// let routes = http.ratelimiter.unwrap().routes();
-    /// let reader = routes.read().await;
+    /// [`HttpBuilder::build`]: super::HttpBuilder::build
+    pub fn set_api_base(&mut self, api_base: ApiBase) {
+        self.api_base = api_base;
+    }
+
+    /// Sets the [`RatelimitStore`] keeping track of ratelimits, which is a [`LocalRatelimitStore`]
+    /// by default.
+    pub fn set_store(&mut self, store: Arc<dyn RatelimitStore>) {
+        self.store = store;
+        self.routes = Arc::default();
+    }
+
+    /// Sets the middleware requests are run through, which is done by [`HttpBuilder::build`].
+    ///
+    /// [`HttpBuilder::build`]: super::HttpBuilder::build
+    pub(super) fn set_middleware(&mut self, middleware: MiddlewareChain) {
+        self.middleware = middleware;
+    }
+
+    // Sets whether absolute ratelimits should be used.
+    pub fn set_absolute_ratelimits(&mut self, absolute_ratelimits: bool) {
+        self.absolute_ratelimits = absolute_ratelimits;
+    }
+
+    /// The routes mutex is a HashMap of each [`RatelimitingBucket`] and their respective ratelimit
+    /// information, as kept by the [`LocalRatelimitStore`] the ratelimiter starts with.
     ///
-    /// let channel_id = ChannelId::new(7);
-    /// let route = Route::Channel {
-    ///     channel_id,
-    /// };
-    /// if let Some(route) = reader.get(&route.ratelimiting_bucket()) {
-    ///     if let Some(reset) = route.lock().await.reset() {
-    ///         println!("Reset time at: {:?}", reset);
-    ///     }
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
+    /// Once another [`RatelimitStore`] is set through [`Self::set_store`], nothing updates the
+    /// routes anymore. Use [`LocalRatelimitStore::routes`] on the store that is set instead.
+    #[cfg_attr(
+        not(ignore_serenity_deprecated),
+        deprecated = "Use LocalRatelimitStore::routes, as this isn't updated once a store is set"
+    )]
     #[must_use]
     pub fn routes(&self) -> Arc<RwLock<HashMap<RatelimitingBucket, Arc<Mutex<Ratelimit>>>>> {
         Arc::clone(&self.routes)
@@ -180,9 +259,44 @@ impl Ratelimiter {
     /// Only error kind that may be returned is [`Error::Http`].
     #[instrument]
     pub async fn perform(&self, req: Request<'_>) -> Result<Response> {
+        let response = self.perform_ratelimited(&req).await?;
+
+        let Some(captcha_handler) = &self.captcha_handler else {
+            return Ok(response);
+        };
+        if response.status() != StatusCode::BAD_REQUEST {
+            return Ok(response);
+        }
+
+        // Telling whether a captcha is required means reading the body, so the response can't be
+        // returned anymore.
+        let captcha = match HttpError::from_response(response, req.method.reqwest_method()).await {
+            HttpError::CaptchaRequired(captcha) => captcha,
+            error => return Err(error.into()),
+        };
+        let Some(solution) = captcha_handler.solve(&captcha).await else {
+            return Err(HttpError::CaptchaRequired(captcha).into());
+        };
+
+        let header = |value: &str| HeaderValue::from_str(value).map_err(HttpError::InvalidHeader);
+        let mut headers = req.headers.clone().unwrap_or_default();
+        headers.insert("X-Captcha-Key", header(&solution)?);
+        if let Some(rqtoken) = &captcha.captcha_rqtoken {
+            headers.insert("X-Captcha-Rqtoken", header(rqtoken)?);
+        }
+        if let Some(session_id) = &captcha.captcha_session_id {
+            headers.insert("X-Captcha-Session-Id", header(session_id)?);
+        }
+
+        self.perform_ratelimited(&req.headers(Some(headers))).await
+    }
+
+    async fn perform_ratelimited(&self, req: &Request<'_>) -> Result<Response> {
+        let mut attempt = 1;
+
         loop {
             // This will block if another thread hit the global ratelimit.
-            drop(self.global.lock().await);
+            self.store.wait_global().await?;
 
             // Perform pre-checking here:
             // - get the route's relevant rate
@@ -191,13 +305,65 @@ impl Ratelimiter {
             // - sleep if there is 0 remaining
             // - then, perform the request
             let ratelimiting_bucket = req.route.ratelimiting_bucket();
-            let bucket =
-                Arc::clone(self.routes.write().await.entry(ratelimiting_bucket).or_default());
+            let mut key = self.key(ratelimiting_bucket);
 
-            bucket.lock().await.pre_hook(&req, &self.ratelimit_callback).await;
+            if !ratelimiting_bucket.is_none() {
+                self.store.pre_hook(&key, req, &self.ratelimit_callback).await?;
+            }
 
-            let request = req.clone().build(&self.client, self.token.expose_secret(), None)?;
-            let response = self.client.execute(request.build()?).await?;
+            let result = self
+                .middleware
+                .send(
+                    &self.client,
+                    req.clone(),
+                    self.token.expose_secret(),
+                    &self.api_base,
+                    &self.client_properties,
+                )
+                .await;
+
+            if let Ok(response) = &result {
+                let hash = response.headers().get("x-ratelimit-bucket");
+                if let Some(hash) = hash.and_then(|hash| hash.to_str().ok()) {
+                    self.learn_hash(&mut key, hash);
+                }
+            }
+
+            let retry_delay = self
+                .retry_policy
+                .as_ref()
+                .and_then(|retry_policy| retry_policy.retry_delay(req, &result, attempt));
+            if let Some(delay) = retry_delay {
+                // The failed response still tells how much of the ratelimit is left. The request
+                // is retried either way, so whether the store asks for it to be redone is moot.
+                if let (Ok(response), false) = (&result, ratelimiting_bucket.is_none()) {
+                    drop(
+                        self.store
+                            .post_hook(
+                                &key,
+                                response,
+                                req,
+                                &self.ratelimit_callback,
+                                self.absolute_ratelimits,
+                            )
+                            .await,
+                    );
+                }
+
+                debug!("Retrying request to {:?} in {:?}", req.route, delay);
+                (self.retry_callback)(RetryInfo {
+                    attempt,
+                    delay,
+                    status: result.as_ref().ok().map(Response::status),
+                    method: req.method,
+                    path: req.route.path().to_string(),
+                });
+
+                sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+            let response = result?;
 
             // Check if the request got ratelimited by checking for status 429, and if so, sleep
             // for the value of the header 'retry-after' - which is in milliseconds - and then
@@ -216,8 +382,6 @@ impl Ratelimiter {
             }
 
             let redo = if response.headers().get("x-ratelimit-global").is_some() {
-                drop(self.global.lock().await);
-
                 Ok(
                     if let Some(retry_after) =
                         parse_header::<f64>(response.headers(), "retry-after")?
@@ -233,7 +397,7 @@ impl Ratelimiter {
                             path: req.route.path().to_string(),
                             global: true,
                         });
-                        sleep(Duration::from_secs_f64(retry_after)).await;
+                        self.store.hold_global(Duration::from_secs_f64(retry_after)).await?;
 
                         true
                     } else {
@@ -241,10 +405,14 @@ impl Ratelimiter {
                     },
                 )
             } else {
-                bucket
-                    .lock()
-                    .await
-                    .post_hook(&response, &req, &self.ratelimit_callback, self.absolute_ratelimits)
+                self.store
+                    .post_hook(
+                        &key,
+                        &response,
+                        req,
+                        &self.ratelimit_callback,
+                        self.absolute_ratelimits,
+                    )
                     .await
             };
 
@@ -253,6 +421,79 @@ impl Ratelimiter {
             }
         }
     }
+
+    /// Returns the key of the bucket the requests grouped by the [`RatelimitingBucket`] are
+    /// ratelimited in, which is that of the bucket of its route if it has been seen in one before.
+    pub(super) fn key(&self, ratelimiting_bucket: RatelimitingBucket) -> RatelimitKey {
+        let hashes = self.hashes.lock().expect("poison");
+        let hash = ratelimiting_bucket.route_name().and_then(|route| hashes.get(route)).cloned();
+
+        RatelimitKey::new(ratelimiting_bucket, hash)
+    }
+
+    /// Records the hash of the bucket a response said the route of the key is in.
+    pub(super) fn learn_hash(&self, key: &mut RatelimitKey, hash: &str) {
+        if key.hash() == Some(hash) {
+            return;
+        }
+
+        if let Some(route) = key.ratelimiting_bucket().route_name() {
+            self.hashes.lock().expect("poison").insert(route, hash.to_string());
+        }
+        *key = RatelimitKey::new(key.ratelimiting_bucket(), Some(hash.to_string()));
+    }
+}
+
+/// Identifies the bucket a request is ratelimited in.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RatelimitKey {
+    ratelimiting_bucket: RatelimitingBucket,
+    hash: Option<String>,
+}
+
+impl RatelimitKey {
+    pub(super) fn new(ratelimiting_bucket: RatelimitingBucket, hash: Option<String>) -> Self {
+        Self {
+            ratelimiting_bucket,
+            hash,
+        }
+    }
+
+    /// The route and major parameter of the request.
+    #[must_use]
+    pub fn ratelimiting_bucket(&self) -> RatelimitingBucket {
+        self.ratelimiting_bucket
+    }
+
+    /// The hash of the bucket the route has been seen in, as told by the `x-ratelimit-bucket`
+    /// header, if it has been seen in one yet.
+    #[must_use]
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// The major parameter of the request, such as the Id of a channel.
+    #[must_use]
+    pub fn major_parameter(&self) -> Option<NonZeroU64> {
+        self.ratelimiting_bucket.major_parameter()
+    }
+}
+
+/// Formats the key as text that is the same for every process running the same build, for stores
+/// keeping ratelimits outside of the process.
+impl fmt::Display for RatelimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.hash, self.ratelimiting_bucket.route_name()) {
+            (Some(hash), _) => f.write_str(hash)?,
+            (None, Some(route)) => f.write_str(route)?,
+            (None, None) => f.write_str("none")?,
+        }
+
+        match self.major_parameter() {
+            Some(id) => write!(f, ":{id}"),
+            None => Ok(()),
+        }
+    }
 }
 
 /// A set of data containing information about the ratelimits for a particular
@@ -267,13 +508,15 @@ impl Ratelimiter {
 #[derive(Debug)]
 pub struct Ratelimit {
     /// The total number of requests that can be made in a period of time.
-    limit: i64,
+    pub(super) limit: i64,
     /// The number of requests remaining in the period of time.
-    remaining: i64,
+    pub(super) remaining: i64,
     /// The absolute time when the interval resets.
-    reset: Option<SystemTime>,
+    pub(super) reset: Option<SystemTime>,
     /// The total time when the interval resets.
-    reset_after: Option<Duration>,
+    pub(super) reset_after: Option<Duration>,
+    /// When the last request was made.
+    used_at: Instant,
 }
 
 impl Ratelimit {
@@ -283,14 +526,24 @@ impl Ratelimit {
         req: &Request<'_>,
         ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
     ) {
+        if let Some(delay) = self.take_ticket() {
+            wait_for_reset(delay, self.limit, req, ratelimit_callback).await;
+        }
+    }
+
+    /// Takes one of the remaining requests, returning how long to wait for the interval to reset
+    /// if there are none left.
+    pub(super) fn take_ticket(&mut self) -> Option<Duration> {
+        self.used_at = Instant::now();
+
         if self.limit() == 0 {
-            return;
+            return None;
         }
 
         let Some(reset) = self.reset else {
             // We're probably in the past.
             self.remaining = self.limit;
-            return;
+            return None;
         };
 
         let Ok(delay) = reset.duration_since(SystemTime::now()) else {
@@ -298,29 +551,15 @@ impl Ratelimit {
             if self.remaining() != 0 {
                 self.remaining -= 1;
             }
-            return;
+            return None;
         };
 
         if self.remaining() == 0 {
-            debug!(
-                "Pre-emptive ratelimit on route {:?} for {}ms",
-                req.route.ratelimiting_bucket(),
-                delay.as_millis(),
-            );
-            ratelimit_callback(RatelimitInfo {
-                timeout: delay,
-                limit: self.limit,
-                method: req.method,
-                path: req.route.path().to_string(),
-                global: false,
-            });
-
-            sleep(delay).await;
-
-            return;
+            return Some(delay);
         }
 
         self.remaining -= 1;
+        None
     }
 
     #[instrument(skip(ratelimit_callback))]
@@ -331,52 +570,34 @@ impl Ratelimit {
         ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
         absolute_ratelimits: bool,
     ) -> Result<bool> {
-        if let Some(limit) = parse_header(response.headers(), "x-ratelimit-limit")? {
+        let headers = RatelimitHeaders::parse(response.headers()).map_err(|e| *e)?;
+        self.update(&headers, absolute_ratelimits);
+        wait_for_retry(response, self.limit, req, ratelimit_callback).await
+    }
+
+    /// Updates the ratelimit from the headers of a response.
+    pub(super) fn update(&mut self, headers: &RatelimitHeaders, absolute_ratelimits: bool) {
+        if let Some(limit) = headers.limit {
             self.limit = limit;
         }
 
-        if let Some(remaining) = parse_header(response.headers(), "x-ratelimit-remaining")? {
+        if let Some(remaining) = headers.remaining {
             self.remaining = remaining;
         }
 
         if absolute_ratelimits {
-            if let Some(reset) = parse_header::<f64>(response.headers(), "x-ratelimit-reset")? {
+            if let Some(reset) = headers.reset {
                 self.reset = Some(std::time::UNIX_EPOCH + Duration::from_secs_f64(reset));
             }
         }
 
-        if let Some(reset_after) =
-            parse_header::<f64>(response.headers(), "x-ratelimit-reset-after")?
-        {
+        if let Some(reset_after) = headers.reset_after {
             if !absolute_ratelimits {
                 self.reset = Some(SystemTime::now() + Duration::from_secs_f64(reset_after));
             }
 
             self.reset_after = Some(Duration::from_secs_f64(reset_after));
         }
-
-        Ok(if response.status() != StatusCode::TOO_MANY_REQUESTS {
-            false
-        } else if let Some(retry_after) = parse_header::<f64>(response.headers(), "retry-after")? {
-            debug!(
-                "Ratelimited on route {:?} for {:?}s",
-                req.route.ratelimiting_bucket(),
-                retry_after
-            );
-            ratelimit_callback(RatelimitInfo {
-                timeout: Duration::from_secs_f64(retry_after),
-                limit: self.limit,
-                method: req.method,
-                path: req.route.path().to_string(),
-                global: false,
-            });
-
-            sleep(Duration::from_secs_f64(retry_after)).await;
-
-            true
-        } else {
-            false
-        })
     }
 
     /// The total number of requests that can be made in a period of time.
@@ -406,6 +627,12 @@ impl Ratelimit {
     pub const fn reset_after(&self) -> Option<Duration> {
         self.reset_after
     }
+
+    /// Whether the interval has reset and no request has been made for [`BUCKET_IDLE_TIMEOUT`].
+    pub(super) fn is_idle(&self, now: Instant) -> bool {
+        let reset = self.reset.map_or(true, |reset| reset <= SystemTime::now());
+        reset && now.saturating_duration_since(self.used_at) >= BUCKET_IDLE_TIMEOUT
+    }
 }
 
 impl Default for Ratelimit {
@@ -415,10 +642,84 @@ impl Default for Ratelimit {
             remaining: i64::MAX,
             reset: None,
             reset_after: None,
+            used_at: Instant::now(),
         }
     }
 }
 
+/// Waits for the interval of a bucket with no requests left to reset.
+pub(super) async fn wait_for_reset(
+    delay: Duration,
+    limit: i64,
+    req: &Request<'_>,
+    ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+) {
+    debug!(
+        "Pre-emptive ratelimit on route {:?} for {}ms",
+        req.route.ratelimiting_bucket(),
+        delay.as_millis(),
+    );
+    ratelimit_callback(RatelimitInfo {
+        timeout: delay,
+        limit,
+        method: req.method,
+        path: req.route.path().to_string(),
+        global: false,
+    });
+
+    sleep(delay).await;
+}
+
+/// Waits for the time a ratelimited response said to wait, returning whether the request has to
+/// be retried.
+pub(super) async fn wait_for_retry(
+    response: &Response,
+    limit: i64,
+    req: &Request<'_>,
+    ratelimit_callback: &(dyn Fn(RatelimitInfo) + Send + Sync),
+) -> Result<bool> {
+    Ok(if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        false
+    } else if let Some(retry_after) = parse_header::<f64>(response.headers(), "retry-after")? {
+        debug!("Ratelimited on route {:?} for {:?}s", req.route.ratelimiting_bucket(), retry_after);
+        ratelimit_callback(RatelimitInfo {
+            timeout: Duration::from_secs_f64(retry_after),
+            limit,
+            method: req.method,
+            path: req.route.path().to_string(),
+            global: false,
+        });
+
+        sleep(Duration::from_secs_f64(retry_after)).await;
+
+        true
+    } else {
+        false
+    })
+}
+
+/// The ratelimit headers of a response, parsed before updating a [`Ratelimit`] with them.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct RatelimitHeaders {
+    limit: Option<i64>,
+    remaining: Option<i64>,
+    reset: Option<f64>,
+    reset_after: Option<f64>,
+}
+
+impl RatelimitHeaders {
+    /// Returns an [`HttpError::RateLimitUtf8`] or [`HttpError::RateLimitI64F64`] if a header is
+    /// malformed, boxed to keep the [`Result`] small.
+    pub(super) fn parse(headers: &HeaderMap) -> StdResult<Self, Box<Error>> {
+        Ok(Self {
+            limit: parse_header(headers, "x-ratelimit-limit").map_err(Box::new)?,
+            remaining: parse_header(headers, "x-ratelimit-remaining").map_err(Box::new)?,
+            reset: parse_header(headers, "x-ratelimit-reset").map_err(Box::new)?,
+            reset_after: parse_header(headers, "x-ratelimit-reset-after").map_err(Box::new)?,
+        })
+    }
+}
+
 fn parse_header<T: FromStr>(headers: &HeaderMap, header: &str) -> Result<Option<T>> {
     let Some(header) = headers.get(header) else { return Ok(None) };
 
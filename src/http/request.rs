@@ -1,5 +1,7 @@
 use std::fmt::Write;
 
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine as _;
 use reqwest::header::{
     HeaderMap as Headers,
     HeaderValue,
@@ -13,9 +15,10 @@ use tracing::instrument;
 
 use super::multipart::Multipart;
 use super::routing::Route;
-use super::{HttpError, LightMethod};
-use crate::constants;
+use super::{ApiBase, HttpError, LightMethod};
 use crate::internal::prelude::*;
+use crate::json::to_vec;
+use crate::model::gateway::ClientProperties;
 
 #[deprecated = "use Request directly now"]
 pub type RequestBuilder<'a> = Request<'a>;
@@ -63,6 +66,14 @@ impl<'a> Request<'a> {
         self
     }
 
+    /// Builds the request, sending it to the `proxy` instead of Discord if one is given, and
+    /// describing the client by the default [`ClientProperties`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`HttpError::Url`] if the URL of the request is invalid, an
+    /// [`HttpError::InvalidHeader`] if the token can't be used as a header value, and an error if
+    /// the multipart form can't be built.
     #[instrument(skip(token))]
     pub fn build(
         self,
@@ -70,12 +81,23 @@ impl<'a> Request<'a> {
         token: &str,
         proxy: Option<&str>,
     ) -> Result<ReqwestRequestBuilder> {
-        let mut path = self.route.path().to_string();
+        let api_base = proxy.map_or_else(ApiBase::default, |proxy| ApiBase::new().proxy(proxy));
+        self.build_with(client, token, &api_base, &SuperProperties::default()).map_err(|e| *e)
+    }
 
-        if let Some(proxy) = proxy {
-            // trim_end_matches to prevent double slashes after the domain
-            path = path.replace("https://discord.com", proxy.trim_end_matches('/'));
-        }
+    #[instrument(skip(token, properties))]
+    pub(super) fn build_with(
+        self,
+        client: &Client,
+        token: &str,
+        api_base: &ApiBase,
+        properties: &SuperProperties,
+    ) -> StdResult<ReqwestRequestBuilder, Box<Error>> {
+        let SuperProperties {
+            properties,
+            encoded,
+        } = properties;
+        let mut path = api_base.api_url(&self.route.path());
 
         if let Some(params) = self.params {
             path += "?";
@@ -84,17 +106,28 @@ impl<'a> Request<'a> {
             }
         }
 
-        let mut builder = client
-            .request(self.method.reqwest_method(), Url::parse(&path).map_err(HttpError::Url)?);
+        let mut builder = client.request(
+            self.method.reqwest_method(),
+            Url::parse(&path).map_err(|e| boxed(HttpError::Url(e)))?,
+        );
 
         let mut headers = self.headers.unwrap_or_default();
-        headers.insert(USER_AGENT, HeaderValue::from_static(constants::USER_AGENT));
-        headers
-            .insert(AUTHORIZATION, HeaderValue::from_str(token).map_err(HttpError::InvalidHeader)?);
+        let header = |value: &str| {
+            HeaderValue::from_str(value).map_err(|e| boxed(HttpError::InvalidHeader(e)))
+        };
+        headers.insert(AUTHORIZATION, header(token)?);
+
+        // Describe the client the same way it identifies on the gateway.
+        headers.insert(USER_AGENT, header(&properties.browser_user_agent)?);
+        headers.insert("X-Super-Properties", header(encoded)?);
+        headers.insert("X-Discord-Locale", header(&properties.system_locale)?);
+        if let Some(timezone) = &properties.timezone {
+            headers.insert("X-Discord-Timezone", header(timezone)?);
+        }
 
         if let Some(multipart) = self.multipart {
             // Setting multipart adds the content-length header.
-            builder = builder.multipart(multipart.build_form()?);
+            builder = builder.multipart(multipart.build_form().map_err(Box::new)?);
         } else if let Some(bytes) = self.body {
             headers.insert(CONTENT_LENGTH, bytes.len().into());
             headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
@@ -146,3 +179,62 @@ impl<'a> Request<'a> {
         self.params.as_deref_mut()
     }
 }
+
+/// Boxes an error, keeping the [`Result`] returned by [`Request::build_with`] small.
+fn boxed(error: impl Into<Error>) -> Box<Error> {
+    Box::new(error.into())
+}
+
+/// [`ClientProperties`] along with their encoding for the `X-Super-Properties` header, which is
+/// done once when they're set rather than for every request.
+#[derive(Clone, Debug)]
+pub(super) struct SuperProperties {
+    pub(super) properties: ClientProperties,
+    encoded: String,
+}
+
+impl From<ClientProperties> for SuperProperties {
+    fn from(properties: ClientProperties) -> Self {
+        let json = to_vec(&properties).expect("ClientProperties always serialize");
+
+        Self {
+            encoded: BASE64_STANDARD.encode(json),
+            properties,
+        }
+    }
+}
+
+impl Default for SuperProperties {
+    fn default() -> Self {
+        ClientProperties::default().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Route;
+    use crate::json::{from_slice, Value};
+
+    #[test]
+    fn client_properties_headers() {
+        let mut properties = ClientProperties::default();
+        properties.timezone = Some("Europe/London".into());
+
+        let request = Request::new(Route::Gateway, LightMethod::Get)
+            .build_with(&Client::new(), "token", &ApiBase::default(), &properties.clone().into())
+            .unwrap()
+            .build()
+            .unwrap();
+        let headers = request.headers();
+
+        assert_eq!(headers[USER_AGENT], properties.browser_user_agent.as_str());
+        assert_eq!(headers["X-Discord-Locale"], "en-US");
+        assert_eq!(headers["X-Discord-Timezone"], "Europe/London");
+
+        let super_properties = BASE64_STANDARD.decode(&headers["X-Super-Properties"]).unwrap();
+        let super_properties: Value = from_slice(&super_properties).unwrap();
+        assert_eq!(super_properties["browser"], "Firefox");
+        assert!(super_properties.get("timezone").is_none());
+    }
+}
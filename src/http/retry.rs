@@ -0,0 +1,267 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error as StdError;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Error as IoError, ErrorKind};
+
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Error as ReqwestError, Response, StatusCode};
+use tokio::time::Duration;
+
+use super::{HttpError, LightMethod, Request};
+use crate::internal::prelude::*;
+use crate::json::from_slice;
+
+/// Passed to the [`Ratelimiter::set_retry_callback`] callback before a request that failed
+/// transiently is retried.
+///
+/// [`Ratelimiter::set_retry_callback`]: super::Ratelimiter::set_retry_callback
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RetryInfo {
+    /// The attempt that failed, starting at 1.
+    pub attempt: u32,
+    /// How long is waited before the next attempt.
+    pub delay: Duration,
+    /// The status of the response, or [`None`] if the connection failed.
+    pub status: Option<StatusCode>,
+    pub method: LightMethod,
+    pub path: String,
+}
+
+/// Decides which requests are retried after failing transiently, and how long is waited before
+/// each attempt.
+///
+/// Requests fail transiently when Discord's edge answers with a status of 502, 503 or 504, or when
+/// the connection fails. By default, up to 3 attempts are made for `GET`, `PUT` and `DELETE`
+/// requests, which are idempotent, waiting about half a second before the first retry and twice
+/// as long before each of the next ones. Responses with a `Retry-After` header are waited out for
+/// at least as long as it says.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// use serenity::http::{HttpBuilder, LightMethod, RetryPolicy};
+///
+/// let policy = RetryPolicy::new()
+///     .max_attempts(5)
+///     .backoff(Duration::from_secs(1), Duration::from_secs(30))
+///     .methods([LightMethod::Get])
+///     .retry_nonced_posts(true);
+/// let http = HttpBuilder::new("token").retry_policy(policy).build();
+/// ```
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    methods: Vec<LightMethod>,
+    retry_nonced_posts: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            methods: vec![LightMethod::Get, LightMethod::Put, LightMethod::Delete],
+            retry_nonced_posts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of attempts made at most, including the first. Requests aren't retried if
+    /// this is 1.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry, which doubles for each of the next ones up to
+    /// `max_delay`. The delays are randomly shortened by up to half, so that clients retrying at
+    /// the same time spread out.
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the methods of the requests that are retried.
+    pub fn methods(mut self, methods: impl IntoIterator<Item = LightMethod>) -> Self {
+        self.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Sets whether `POST` requests are retried if Discord de-duplicates them by their nonce, such
+    /// as messages sent with [`CreateMessage::enforce_nonce`], even if `POST` isn't one of the
+    /// [`Self::methods`].
+    ///
+    /// [`CreateMessage::enforce_nonce`]: crate::builder::CreateMessage::enforce_nonce
+    pub fn retry_nonced_posts(mut self, retry_nonced_posts: bool) -> Self {
+        self.retry_nonced_posts = retry_nonced_posts;
+        self
+    }
+
+    /// Returns the delay before retrying the request, if the attempt failed transiently and the
+    /// request should be retried.
+    pub(super) fn retry_delay(
+        &self,
+        req: &Request<'_>,
+        result: &Result<Response>,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let transient = match result {
+            Ok(response) => matches!(
+                response.status(),
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Err(Error::Http(HttpError::Request(error))) => is_transient(error),
+            Err(_) => false,
+        };
+
+        if !(transient && attempt < self.max_attempts && self.is_retryable(req)) {
+            return None;
+        }
+
+        // Discord's edge may say how long it will be unavailable for, which is waited out at
+        // least.
+        let retry_after = result
+            .as_ref()
+            .ok()
+            .and_then(|response| response.headers().get(RETRY_AFTER)?.to_str().ok()?.parse().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+        let delay = self.delay(attempt);
+        Some(retry_after.map_or(delay, |retry_after| delay.max(retry_after)))
+    }
+
+    fn is_retryable(&self, req: &Request<'_>) -> bool {
+        if self.methods.contains(&req.method) {
+            return true;
+        }
+
+        self.retry_nonced_posts
+            && req.method == LightMethod::Post
+            && req.body.as_deref().is_some_and(has_enforced_nonce)
+    }
+
+    /// The delay after the given attempt, with jitter.
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        #[allow(clippy::cast_precision_loss)]
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(1.0 - jitter / 2.0)
+    }
+}
+
+/// Whether the JSON body of a request sets a nonce that Discord de-duplicates it by.
+fn has_enforced_nonce(body: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct Nonce {
+        nonce: Option<crate::json::Value>,
+        #[serde(default)]
+        enforce_nonce: bool,
+    }
+
+    from_slice::<Nonce>(body).is_ok_and(|body| body.nonce.is_some() && body.enforce_nonce)
+}
+
+/// Whether the request failed to connect, timed out, or lost its connection.
+fn is_transient(error: &ReqwestError) -> bool {
+    if error.is_connect() || error.is_timeout() {
+        return true;
+    }
+
+    let mut source = error.source();
+    while let Some(error) = source {
+        if let Some(error) = error.downcast_ref::<IoError>() {
+            return matches!(
+                error.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            );
+        }
+        source = error.source();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::http::{test_server, HttpBuilder, LocalRatelimitStore, Route};
+    use crate::json::{json, to_vec};
+    use crate::model::id::ChannelId;
+
+    #[test]
+    fn retryable_requests() {
+        let policy = RetryPolicy::new();
+        let route = Route::ChannelMessages {
+            channel_id: ChannelId::new(1),
+        };
+        let post = |body| Request::new(route, LightMethod::Post).body(Some(to_vec(&body).unwrap()));
+
+        assert!(policy.is_retryable(&Request::new(route, LightMethod::Get)));
+        assert!(!policy.is_retryable(&post(json!({"content": "a"}))));
+
+        let policy = policy.retry_nonced_posts(true);
+        assert!(!policy.is_retryable(&post(json!({"nonce": "1"}))));
+        assert!(policy.is_retryable(&post(json!({"nonce": "1", "enforce_nonce": true}))));
+
+        let policy = policy.backoff(Duration::from_secs(1), Duration::from_secs(3));
+        for (attempt, max) in [(1, 1), (2, 2), (3, 3), (10, 3)] {
+            let delay = policy.delay(attempt);
+            assert!(delay <= Duration::from_secs(max) && delay >= Duration::from_secs(max) / 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_after() {
+        let (url, server) = test_server([
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0.2\r\n\
+             X-RateLimit-Limit: 5\r\nX-RateLimit-Remaining: 3\r\n\
+             X-RateLimit-Reset-After: 60\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let store = LocalRatelimitStore::default();
+        let routes = store.routes();
+        let http = HttpBuilder::new("token")
+            .proxy(url)
+            .ratelimit_store(Arc::new(store))
+            .retry_policy(RetryPolicy::new().backoff(Duration::from_millis(1), Duration::ZERO))
+            .retry_callback({
+                let delays = Arc::clone(&delays);
+                Box::new(move |info| delays.lock().expect("poison").push(info.delay))
+            })
+            .build();
+
+        let route = Route::Channel {
+            channel_id: ChannelId::new(1),
+        };
+        let response = http.request(Request::new(route, LightMethod::Get)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        server.join().unwrap();
+
+        // The server's delay is waited out rather than the shorter backoff.
+        let delays = delays.lock().expect("poison").clone();
+        assert_eq!(delays, [Duration::from_millis(200)]);
+
+        // The ratelimit was updated by the failed response, before the retry took a ticket.
+        let routes = routes.read().await;
+        let ratelimit = routes[&route.ratelimiting_bucket()].lock().await;
+        assert_eq!((ratelimit.limit(), ratelimit.remaining()), (5, 2));
+    }
+}
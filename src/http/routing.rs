@@ -1,18 +1,27 @@
 use std::borrow::Cow;
-use std::mem::Discriminant;
 use std::num::NonZeroU64;
 
 use crate::model::id::*;
 
 /// Used to group requests together for ratelimiting.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub struct RatelimitingBucket(Option<(std::mem::Discriminant<Route<'static>>, Option<NonZeroU64>)>);
+pub struct RatelimitingBucket(Option<(&'static str, Option<NonZeroU64>)>);
 
 impl RatelimitingBucket {
     #[must_use]
     pub fn is_none(&self) -> bool {
         self.0.is_none()
     }
+
+    /// The name of the [`Route`] variant of the requests, or [`None`] if they aren't ratelimited.
+    pub(super) fn route_name(&self) -> Option<&'static str> {
+        self.0.map(|(route, _)| route)
+    }
+
+    /// The major parameter of the requests, such as the Id of a channel.
+    pub(super) fn major_parameter(&self) -> Option<NonZeroU64> {
+        self.0.and_then(|(_, id)| id)
+    }
 }
 
 enum RatelimitingKind {
@@ -53,27 +62,23 @@ macro_rules! routes {
 
             #[must_use]
             pub fn ratelimiting_bucket(&self) -> RatelimitingBucket {
+                // The name of the variant identifies the route the same way in every build, unlike
+                // its discriminant, and avoids adding a lifetime on RatelimitingBucket.
                 #[allow(unused_variables)]
-                let ratelimiting_kind = match *self {
+                let (name, ratelimiting_kind) = match *self {
                     $(
-                        Self::$name $({ $($field_name),* })? => $ratelimiting_kind,
+                        Self::$name $({ $($field_name),* })? => {
+                            (stringify!($name), $ratelimiting_kind)
+                        },
                     )+
                 };
 
-                // This avoids adding a lifetime on RatelimitingBucket and causing lifetime infection
-                // SAFETY: std::mem::discriminant erases lifetimes.
-                let discriminant = unsafe {
-                    std::mem::transmute::<Discriminant<Route<'a>>, Discriminant<Route<'static>>>(
-                        std::mem::discriminant(self),
-                    )
-                };
-
                 RatelimitingBucket(ratelimiting_kind.map(|r| {
                     let id = match r {
                         RatelimitingKind::PathAndId(id) => Some(id),
                         RatelimitingKind::Path => None,
                     };
-                    (discriminant, id)
+                    (name, id)
                 }))
             }
 
@@ -418,6 +423,18 @@ routes! ('a, {
     api!("/users/@me/guilds"),
     Some(RatelimitingKind::Path);
 
+    UserMeRelationship { user_id: UserId },
+    api!("/users/@me/relationships/{}", user_id),
+    Some(RatelimitingKind::Path);
+
+    UserMeRelationships,
+    api!("/users/@me/relationships"),
+    Some(RatelimitingKind::Path);
+
+    UserMeSettingsProto { kind: u8 },
+    api!("/users/@me/settings-proto/{}", kind),
+    Some(RatelimitingKind::Path);
+
     VoiceRegions,
     api!("/voice/regions"),
     Some(RatelimitingKind::Path);
@@ -2,6 +2,8 @@
 use reqwest::Client as ReqwestClient;
 use serde_cow::CowStr;
 
+#[cfg(feature = "model")]
+use crate::http::Http;
 #[cfg(feature = "model")]
 use crate::internal::prelude::*;
 use crate::model::prelude::*;
@@ -85,7 +87,10 @@ impl Attachment {
         self.width.and_then(|width| self.height.map(|height| (width, height)))
     }
 
-    /// Downloads the attachment, returning back a vector of bytes.
+    /// Downloads the attachment from Discord's CDN, returning back a vector of bytes.
+    ///
+    /// This uses a client of its own, so see [`Self::download_with`] to download the attachment
+    /// through an [`Http`] client and the CDN of its [`ApiBase`] instead.
     ///
     /// # Examples
     ///
@@ -150,9 +155,25 @@ impl Attachment {
     /// Returns an [`Error::Http`] when there is a problem retrieving the attachment.
     ///
     /// [`Message`]: super::Message
+    /// [`ApiBase`]: crate::http::ApiBase
     pub async fn download(&self) -> Result<Vec<u8>> {
         let reqwest = ReqwestClient::new();
         let bytes = reqwest.get(&self.url).send().await?.bytes().await?;
         Ok(bytes.to_vec())
     }
+
+    /// Downloads the attachment like [`Self::download`], but through the client of the [`Http`],
+    /// from the CDN set in its [`ApiBase`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Http`] when there is a problem retrieving the attachment.
+    ///
+    /// [`ApiBase`]: crate::http::ApiBase
+    pub async fn download_with(&self, http: impl AsRef<Http>) -> Result<Vec<u8>> {
+        let http = http.as_ref();
+        let url = http.api_base().cdn_url(&self.url);
+        let bytes = http.client.get(url).send().await?.bytes().await?;
+        Ok(bytes.to_vec())
+    }
 }
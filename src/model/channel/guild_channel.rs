@@ -195,6 +195,41 @@ enum_number! {
     }
 }
 
+impl GuildChannel {
+    /// Returns the Id of the guild member list shown in this channel's sidebar.
+    ///
+    /// Channels share a list when their overwrites allow or deny [`Permissions::VIEW_CHANNEL`] to
+    /// the same roles and members. The list is `"everyone"` if no overwrite touches it, and
+    /// otherwise a hash of those overwrites.
+    #[must_use]
+    pub fn member_list_id(&self) -> String {
+        let mut overwrites: Vec<_> = self
+            .permission_overwrites
+            .iter()
+            .filter_map(|overwrite| {
+                let id = match overwrite.kind {
+                    PermissionOverwriteType::Member(id) => id.get(),
+                    PermissionOverwriteType::Role(id) => id.get(),
+                };
+                if overwrite.allow.contains(Permissions::VIEW_CHANNEL) {
+                    Some(format!("allow:{id}"))
+                } else if overwrite.deny.contains(Permissions::VIEW_CHANNEL) {
+                    Some(format!("deny:{id}"))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if overwrites.is_empty() {
+            return "everyone".into();
+        }
+
+        overwrites.sort_unstable();
+        murmur3_32(overwrites.join(",").as_bytes()).to_string()
+    }
+}
+
 #[cfg(feature = "model")]
 impl GuildChannel {
     /// Whether or not this channel is text-based, meaning that it is possible to send messages.
@@ -34,7 +34,14 @@ pub struct PrivateChannel {
     /// The recipient to the private channel.
     /// 
     /// This will always be a single user in the case of [`ChannelType::Private`]. Can be empty in the case of [`ChannelType::GroupDM`] if it is a group with just the current user
+    #[serde(default)]
     pub recipients: Vec<User>,
+    /// The Ids of the recipients, sent instead of [`Self::recipients`] in [`Ready`] with
+    /// [`GatewayCapabilities::DEDUPE_USER_OBJECTS`].
+    ///
+    /// The users are filled into [`Self::recipients`] when the [`Ready`] is deserialized.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipient_ids: Vec<UserId>,
 }
 
 #[cfg(feature = "model")]
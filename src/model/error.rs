@@ -151,6 +151,8 @@ pub enum Error {
     StickerAmount,
     /// When attempting to edit a voice message.
     CannotEditVoiceMessage,
+    /// When a user settings protobuf blob could not be decoded.
+    InvalidSettingsProto,
 }
 
 impl Error {
@@ -199,6 +201,7 @@ impl fmt::Display for Error {
             Self::NoStickerFileSet => f.write_str("Sticker file is not set."),
             Self::StickerAmount => f.write_str("Too many stickers in a message."),
             Self::CannotEditVoiceMessage => f.write_str("Cannot edit voice message."),
+            Self::InvalidSettingsProto => f.write_str("Invalid user settings protobuf."),
         }
     }
 }
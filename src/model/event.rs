@@ -80,7 +80,8 @@ pub struct AutoModActionExecutionEvent {
 /// This is fired when:
 /// - A [`Channel`] is created in a [`Guild`]
 ///
-/// Requires [`GatewayIntents::GUILDS`].
+/// Requires [`GatewayIntents::GUILDS`] for guild channels. Private channels and group DMs are
+/// received without any intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#channel-create).
 #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
@@ -89,10 +90,11 @@ pub struct AutoModActionExecutionEvent {
 #[non_exhaustive]
 pub struct ChannelCreateEvent {
     /// The channel that was created.
-    pub channel: GuildChannel,
+    pub channel: Channel,
 }
 
-/// Requires [`GatewayIntents::GUILDS`].
+/// Requires [`GatewayIntents::GUILDS`] for guild channels. Private channels and group DMs are
+/// received without any intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#channel-delete).
 #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
@@ -100,7 +102,7 @@ pub struct ChannelCreateEvent {
 #[serde(transparent)]
 #[non_exhaustive]
 pub struct ChannelDeleteEvent {
-    pub channel: GuildChannel,
+    pub channel: Channel,
 }
 
 /// Requires [`GatewayIntents::GUILDS`] or [`GatewayIntents::DIRECT_MESSAGES`].
@@ -115,7 +117,40 @@ pub struct ChannelPinsUpdateEvent {
     pub last_pin_timestamp: Option<Timestamp>,
 }
 
-/// Requires [`GatewayIntents::GUILDS`].
+/// Sent when a user is added to a group DM.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#channel-recipient-add).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ChannelRecipientAddEvent {
+    /// The Id of the group DM.
+    pub channel_id: ChannelId,
+    /// The user that was added.
+    pub user: User,
+    /// The nickname of the user in the group DM, if any.
+    pub nick: Option<String>,
+}
+
+/// Sent when a user is removed from a group DM.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#channel-recipient-remove).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ChannelRecipientRemoveEvent {
+    /// The Id of the group DM.
+    pub channel_id: ChannelId,
+    /// The user that was removed.
+    pub user: User,
+}
+
+/// Requires [`GatewayIntents::GUILDS`] for guild channels. Private channels and group DMs are
+/// received without any intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#channel-update).
 #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
@@ -123,7 +158,7 @@ pub struct ChannelPinsUpdateEvent {
 #[serde(transparent)]
 #[non_exhaustive]
 pub struct ChannelUpdateEvent {
-    pub channel: GuildChannel,
+    pub channel: Channel,
 }
 
 /// Requires [`GatewayIntents::GUILD_MODERATION`] and [`Permissions::VIEW_AUDIT_LOG`].
@@ -263,6 +298,57 @@ pub struct GuildMemberUpdateEvent {
     pub unusual_dm_activity_until: Option<Timestamp>,
 }
 
+/// Requires no gateway intents.
+///
+/// Sent for the member list ranges subscribed to with
+/// [`ShardMessenger::update_guild_subscriptions`].
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#guild-member-list-update).
+///
+/// [`ShardMessenger::update_guild_subscriptions`]: crate::gateway::ShardMessenger::update_guild_subscriptions
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(remote = "Self")]
+#[non_exhaustive]
+pub struct GuildMemberListUpdateEvent {
+    /// The Id of the member list, shared by all channels with the same view permissions.
+    pub id: String,
+    /// The Id of the guild.
+    pub guild_id: GuildId,
+    /// The number of members that can view the channels backed by this list.
+    pub member_count: u64,
+    /// The number of those members that are online.
+    pub online_count: u64,
+    /// The groups in the list, in display order.
+    pub groups: Vec<MemberListGroup>,
+    /// The operations to apply to the list, in order.
+    pub ops: Vec<MemberListOp>,
+}
+
+// Manual impl needed to insert guild_id fields in Member and Presence
+impl<'de> Deserialize<'de> for GuildMemberListUpdateEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let mut event = Self::deserialize(deserializer)?; // calls #[serde(remote)]-generated inherent method
+        for op in &mut event.ops {
+            for item in op.items_mut() {
+                if let MemberListItem::Member(member) = item {
+                    member.member.guild_id = event.guild_id;
+                    if let Some(presence) = &mut member.presence {
+                        presence.guild_id = Some(event.guild_id);
+                    }
+                }
+            }
+        }
+        Ok(event)
+    }
+}
+
+impl Serialize for GuildMemberListUpdateEvent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        Self::serialize(self, serializer) // calls #[serde(remote)]-generated inherent method
+    }
+}
+
 /// Requires no gateway intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#guild-members-chunk).
@@ -707,6 +793,108 @@ pub struct ReadyEvent {
     pub ready: Ready,
 }
 
+/// Sent to user sessions right after [`ReadyEvent`], with the data that was left out of it.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#ready-supplemental).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(remote = "Self")]
+#[non_exhaustive]
+pub struct ReadySupplementalEvent {
+    /// Presences of the current user's friends, and of users in each guild.
+    pub merged_presences: MergedPresences,
+    /// Members of each guild for the current user and the users in
+    /// [`MergedPresences::guilds`], in the same order as [`Self::guilds`].
+    pub merged_members: Vec<Vec<MergedMember>>,
+    /// Private channels that were left out of [`Ready::private_channels`].
+    #[serde(default)]
+    pub lazy_private_channels: Vec<Channel>,
+    /// Extra data for each guild, in the same order as [`Ready::guilds`].
+    pub guilds: Vec<SupplementalGuild>,
+}
+
+// Manual impl needed to insert guild_id fields in MergedMember, Presence and VoiceState
+impl<'de> Deserialize<'de> for ReadySupplementalEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let mut event = Self::deserialize(deserializer)?; // calls #[serde(remote)]-generated inherent method
+        for (i, guild) in event.guilds.iter_mut().enumerate() {
+            for voice_state in &mut guild.voice_states {
+                voice_state.guild_id = Some(guild.id);
+            }
+            for member in event.merged_members.get_mut(i).into_iter().flatten() {
+                member.guild_id = guild.id;
+            }
+            for presence in event.merged_presences.guilds.get_mut(i).into_iter().flatten() {
+                presence.guild_id = Some(guild.id);
+            }
+        }
+        Ok(event)
+    }
+}
+
+impl Serialize for ReadySupplementalEvent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        Self::serialize(self, serializer) // calls #[serde(remote)]-generated inherent method
+    }
+}
+
+/// Sent when a relationship is created, e.g. a friend request is received or a user is blocked.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#relationship-add).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RelationshipAddEvent {
+    #[serde(flatten)]
+    pub relationship: Relationship,
+    /// Whether the client should notify the user of this relationship's creation.
+    #[serde(default)]
+    pub should_notify: bool,
+}
+
+/// Sent when a relationship is removed, e.g. a friend request is cancelled or a user is
+/// unblocked.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#relationship-remove).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RelationshipRemoveEvent {
+    /// The Id of the target user.
+    pub id: UserId,
+    /// The kind of the relationship that was removed.
+    #[serde(rename = "type")]
+    pub kind: RelationshipType,
+    /// The nickname the current user had given to the target user, if any.
+    pub nickname: Option<String>,
+}
+
+/// Sent when a relationship is modified, e.g. a friend request is accepted or a nickname is set.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#relationship-update).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct RelationshipUpdateEvent {
+    /// The Id of the target user.
+    pub id: UserId,
+    /// The new kind of the relationship.
+    #[serde(rename = "type")]
+    pub kind: RelationshipType,
+    /// The nickname the current user has given to the target user, if any.
+    pub nickname: Option<String>,
+    /// When the relationship was created.
+    pub since: Option<Timestamp>,
+}
+
 /// Requires no gateway intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#resumed).
@@ -757,6 +945,22 @@ pub struct UserUpdateEvent {
     pub current_user: CurrentUser,
 }
 
+/// Sent when the current user's protobuf-encoded settings change.
+///
+/// Requires no gateway intents.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#user-settings-proto-update).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct UserSettingsProtoUpdateEvent {
+    /// The new settings.
+    pub settings: UserSettingsProto,
+    /// Whether only the fields that changed are set, rather than the full settings.
+    #[serde(default)]
+    pub partial: bool,
+}
+
 /// Requires no gateway intents.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway-events#voice-server-update).
@@ -1207,6 +1411,19 @@ pub enum Event {
     ///
     /// [`EventHandler::channel_pins_update`]: crate::client::EventHandler::channel_pins_update
     ChannelPinsUpdate(ChannelPinsUpdateEvent),
+    /// A [`User`] was added to a group DM.
+    ///
+    /// Fires the [`EventHandler::channel_recipient_add`] event.
+    ///
+    /// [`EventHandler::channel_recipient_add`]: crate::client::EventHandler::channel_recipient_add
+    ChannelRecipientAdd(ChannelRecipientAddEvent),
+    /// A [`User`] was removed from a group DM.
+    ///
+    /// Fires the [`EventHandler::channel_recipient_remove`] event.
+    ///
+    /// [`EventHandler::channel_recipient_remove`]:
+    /// crate::client::EventHandler::channel_recipient_remove
+    ChannelRecipientRemove(ChannelRecipientRemoveEvent),
     /// A [`Channel`] has been updated.
     ///
     /// Fires the [`EventHandler::channel_update`] event.
@@ -1224,6 +1441,13 @@ pub enum Event {
     GuildMemberRemove(GuildMemberRemoveEvent),
     /// A member's roles have changed
     GuildMemberUpdate(GuildMemberUpdateEvent),
+    /// A subscribed range of a guild member list has changed.
+    ///
+    /// Fires the [`EventHandler::guild_member_list_update`] event handler.
+    ///
+    /// [`EventHandler::guild_member_list_update`]:
+    /// crate::client::EventHandler::guild_member_list_update
+    GuildMemberListUpdate(GuildMemberListUpdateEvent),
     GuildMembersChunk(GuildMembersChunkEvent),
     GuildRoleCreate(GuildRoleCreateEvent),
     GuildRoleDelete(GuildRoleDeleteEvent),
@@ -1285,12 +1509,38 @@ pub enum Event {
     ///
     /// May also be received at a later time in the event of a reconnect.
     Ready(ReadyEvent),
+    /// Data that was left out of [`Ready`] for user sessions.
+    ///
+    /// Fires the [`EventHandler::ready_supplemental`] event handler.
+    ///
+    /// [`EventHandler::ready_supplemental`]: crate::client::EventHandler::ready_supplemental
+    ReadySupplemental(ReadySupplementalEvent),
+    /// A relationship with another user was created.
+    ///
+    /// Fires the [`EventHandler::relationship_add`] event handler.
+    ///
+    /// [`EventHandler::relationship_add`]: crate::client::EventHandler::relationship_add
+    RelationshipAdd(RelationshipAddEvent),
+    /// A relationship with another user was removed.
+    ///
+    /// Fires the [`EventHandler::relationship_remove`] event handler.
+    ///
+    /// [`EventHandler::relationship_remove`]: crate::client::EventHandler::relationship_remove
+    RelationshipRemove(RelationshipRemoveEvent),
+    /// A relationship with another user was modified.
+    ///
+    /// Fires the [`EventHandler::relationship_update`] event handler.
+    ///
+    /// [`EventHandler::relationship_update`]: crate::client::EventHandler::relationship_update
+    RelationshipUpdate(RelationshipUpdateEvent),
     /// The connection has successfully resumed after a disconnect.
     Resumed(ResumedEvent),
     /// A user is typing; considered to last 5 seconds
     TypingStart(TypingStartEvent),
     /// Update to the logged-in user's information
     UserUpdate(UserUpdateEvent),
+    /// Update to the logged-in user's protobuf-encoded settings
+    UserSettingsProtoUpdate(UserSettingsProtoUpdateEvent),
     /// A member's voice state has changed
     VoiceStateUpdate(VoiceStateUpdateEvent),
     /// Voice server information is available
@@ -1,7 +1,9 @@
 //! Models pertaining to the gateway.
 
+use std::mem::take;
 use std::num::NonZeroU16;
 
+use serde::de::Error as DeError;
 use serde::ser::SerializeSeq;
 use url::Url;
 
@@ -334,6 +336,7 @@ pub struct Presence {
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#ready-ready-event-fields).
 #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(remote = "Self")]
 #[non_exhaustive]
 pub struct Ready {
     /// API version
@@ -346,9 +349,9 @@ pub struct Ready {
     /// Omitted when using the USER_SETTINGS_PROTO gateway capability
     #[serde(skip)] ///TODO: Implement user_settings object
     pub user_settings: Option<String>, 
-    /// Base 64 encoded user settings
-    /// TODO: Implement UserSettingProto stuct and desirialization methods.
-    pub user_settings_proto: Option<String>,
+    /// The user's settings, sent as base64-encoded protobuf.
+    #[serde(default, with = "super::user_settings::base64_proto")]
+    pub user_settings_proto: Option<PreloadedUserSettings>,
     /// Guilds the user is in
     /// TODO: Implement guild chaching for ReadyEvent
     pub guilds: Vec<Guild>,
@@ -356,8 +359,8 @@ pub struct Ready {
     #[serde(skip)] ///TODO: Implement guild_join_request object
     pub guild_join_requests: Vec<String>,
     /// Relationships the user has with other users
-    #[serde(skip)] ///TODO: Implement relationship object
-    pub relationships: Option<Vec<String>>,
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
     /// The number of friend suggestions for the user
     pub friend_suggestion_count: Option<u32>,
     /// The user's private channels i.e. DMs and Group DMs (User)
@@ -370,13 +373,21 @@ pub struct Ready {
     pub presences: Vec<Presence>,
     /// Presences of friends and implicit relationships and any guild presences
     /// 
-    /// This only exists when the DEDUPE_USER_OBJECTS gateway capability is enabled
-    #[serde(skip)] ///TODO: Implement merged_presences object
-    pub merged_presences: Option<Vec<String>>,
+    /// This only exists when the DEDUPE_USER_OBJECTS gateway capability is enabled. The guild
+    /// presences are moved into [`Guild::presences`] on deserialization, leaving
+    /// [`MergedPresences::guilds`] empty.
+    #[serde(default)]
+    pub merged_presences: Option<MergedPresences>,
     /// The Deduped users accross the entire event
     /// 
     /// This only exists when the DEDUPE_USER_OBJECTS gateway capability is enabled
     pub users: Option<Vec<User>>,
+    /// The current user's member in each guild, in the same order as [`Self::guilds`].
+    ///
+    /// This only exists when the DEDUPE_USER_OBJECTS gateway capability is enabled, and is moved
+    /// into [`Guild::members`] on deserialization, leaving this [`None`].
+    #[serde(default)]
+    pub merged_members: Option<Vec<Vec<MergedMember>>>,
     /// The application of the user if it is a bot
     pub application: Option<PartialCurrentApplicationInfo>,
     /// Used for resuming connections
@@ -408,6 +419,163 @@ pub struct Ready {
     pub resume_gateway_url: String,
 }
 
+// Manual impl needed to resolve the deduplicated users and members into the rest of the event
+impl<'de> Deserialize<'de> for Ready {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let mut ready = Self::deserialize(deserializer)?; // calls #[serde(remote)]-generated inherent method
+        ready.resolve_users();
+        Ok(ready)
+    }
+}
+
+impl serde::Serialize for Ready {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        Self::serialize(self, serializer) // calls #[serde(remote)]-generated inherent method
+    }
+}
+
+impl Ready {
+    /// Fills in the users that [`GatewayCapabilities::DEDUPE_USER_OBJECTS`] replaces with Ids,
+    /// and moves merged members and presences into their guilds.
+    fn resolve_users(&mut self) {
+        let current_user = User::from(self.user.clone());
+        let users: HashMap<UserId, &User> =
+            self.users.iter().flatten().map(|user| (user.id, user)).collect();
+        let lookup = |id: UserId| {
+            if id == current_user.id {
+                Some(current_user.clone())
+            } else {
+                users.get(&id).map(|&user| user.clone())
+            }
+        };
+
+        for relationship in &mut self.relationships {
+            if relationship.user.is_none() {
+                relationship.user = lookup(relationship.id);
+            }
+        }
+
+        for channel in &mut self.private_channels {
+            if let Channel::Private(channel) = channel {
+                if channel.recipients.is_empty() {
+                    channel.recipients =
+                        channel.recipient_ids.iter().filter_map(|&id| lookup(id)).collect();
+                }
+            }
+        }
+
+        let merged_members = self.merged_members.take().into_iter().flatten();
+        for (guild, members) in self.guilds.iter_mut().zip(merged_members) {
+            for mut member in members {
+                if let Some(user) = lookup(member.user_id) {
+                    member.guild_id = guild.id;
+                    guild.members.insert(user.id, member.into_member(user));
+                }
+            }
+        }
+
+        let merged_presences = self.merged_presences.iter_mut().flat_map(|p| take(&mut p.guilds));
+        for (guild, presences) in self.guilds.iter_mut().zip(merged_presences) {
+            for mut presence in presences {
+                presence.guild_id = Some(guild.id);
+                guild.presences.insert(presence.user.id, presence);
+            }
+        }
+    }
+}
+
+/// Presences that refer to their users by Id, as sent in deduplicated payloads such as
+/// [`ReadySupplementalEvent::merged_presences`].
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#merged-presences-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct MergedPresences {
+    /// Presences of the current user's friends and implicit relationships.
+    #[serde(deserialize_with = "merged_presences")]
+    pub friends: Vec<Presence>,
+    /// Presences in each guild, in the same order as [`Ready::guilds`].
+    #[serde(deserialize_with = "merged_guild_presences")]
+    pub guilds: Vec<Vec<Presence>>,
+}
+
+/// A presence with either a partial user or just the user's Id.
+#[derive(Deserialize)]
+struct MergedPresence {
+    user_id: Option<UserId>,
+    user: Option<PresenceUser>,
+    guild_id: Option<GuildId>,
+    status: OnlineStatus,
+    #[serde(default)]
+    activities: Vec<Activity>,
+    client_status: Option<ClientStatus>,
+}
+
+impl TryFrom<MergedPresence> for Presence {
+    type Error = &'static str;
+
+    fn try_from(presence: MergedPresence) -> StdResult<Self, Self::Error> {
+        let user = match (presence.user, presence.user_id) {
+            (Some(user), _) => user,
+            (None, Some(id)) => PresenceUser {
+                id,
+                avatar: None,
+                bot: None,
+                discriminator: None,
+                email: None,
+                mfa_enabled: None,
+                name: None,
+                verified: None,
+                public_flags: None,
+            },
+            (None, None) => return Err("presence has neither `user` nor `user_id`"),
+        };
+
+        Ok(Self {
+            user,
+            guild_id: presence.guild_id,
+            status: presence.status,
+            activities: presence.activities,
+            client_status: presence.client_status,
+        })
+    }
+}
+
+fn merged_presences<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> StdResult<Vec<Presence>, D::Error> {
+    Vec::<MergedPresence>::deserialize(deserializer)?
+        .into_iter()
+        .map(|p| Presence::try_from(p).map_err(D::Error::custom))
+        .collect()
+}
+
+fn merged_guild_presences<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> StdResult<Vec<Vec<Presence>>, D::Error> {
+    Vec::<Vec<MergedPresence>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|presences| {
+            presences.into_iter().map(|p| Presence::try_from(p).map_err(D::Error::custom)).collect()
+        })
+        .collect()
+}
+
+/// Extra data about a guild sent in [`ReadySupplementalEvent`].
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#supplemental-guild-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct SupplementalGuild {
+    /// The Id of the guild.
+    pub id: GuildId,
+    /// The states of members currently in voice channels.
+    #[serde(default)]
+    pub voice_states: Vec<VoiceState>,
+}
+
 /// Information describing how many gateway sessions you can initiate within a ratelimit period.
 ///
 /// [Discord docs](https://discord.com/developers/docs/topics/gateway#session-start-limit-object-session-start-limit-structure).
@@ -839,3 +1007,174 @@ impl Default for GatewayIntents {
         Self::non_privileged()
     }
 }
+
+bitflags! {
+    /// Capabilities a user session can opt into when identifying, which change the shape of
+    /// some gateway payloads.
+    ///
+    /// By default no capabilities are enabled. The library understands the payloads of every
+    /// capability listed here; for example, with [`Self::DEDUPE_USER_OBJECTS`] the users sent in
+    /// [`Ready::users`] are resolved back into [`Ready::relationships`],
+    /// [`Ready::private_channels`] and the guilds' members when the [`Ready`] is deserialized.
+    ///
+    /// [Discord docs](https://docs.discord.sex/topics/gateway#gateway-capabilities).
+    #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+    #[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+    pub struct GatewayCapabilities: u64 {
+        /// Removes the notes field from [`Ready`].
+        const LAZY_USER_NOTES = 1 << 0;
+        /// Removes the Ids of users the current user has affinity with from [`Ready`].
+        const NO_AFFINE_USER_IDS = 1 << 1;
+        /// Enables versioned read states, sending only changed read states in [`Ready`].
+        const VERSIONED_READ_STATES = 1 << 2;
+        /// Enables versioned user guild settings, sending only changed settings in [`Ready`].
+        const VERSIONED_USER_GUILD_SETTINGS = 1 << 3;
+        /// Sends users once in [`Ready::users`] and refers to them by Id everywhere else in
+        /// [`Ready`], and sends guild members in [`Ready::merged_members`].
+        const DEDUPE_USER_OBJECTS = 1 << 4;
+        /// Splits [`Ready`] into two payloads, sending presences, voice states and some private
+        /// channels in [`ReadySupplementalEvent`].
+        const PRIORITIZED_READY_PAYLOAD = 1 << 5;
+        /// Sends multiple populations per guild experiment in [`Ready`].
+        const MULTIPLE_GUILD_EXPERIMENT_POPULATIONS = 1 << 6;
+        /// Includes read states that aren't tied to a channel.
+        const NON_CHANNEL_READ_STATES = 1 << 7;
+        /// Sends a refreshed token in [`Ready::auth_token`] when the current one is outdated.
+        const AUTH_TOKEN_REFRESH = 1 << 8;
+        /// Sends [`Ready::user_settings_proto`] instead of the deprecated JSON user settings.
+        const USER_SETTINGS_PROTO = 1 << 9;
+        /// Uses the newer format of the client state sent in Identify.
+        const CLIENT_STATE_V2 = 1 << 10;
+        /// Sends batched passive updates instead of some events for guilds that aren't subscribed
+        /// to.
+        const PASSIVE_GUILD_UPDATE = 1 << 11;
+        /// Connects the session to any calls already in progress.
+        const AUTO_CALL_CONNECT = 1 << 12;
+        /// Debounces reaction events sent for the same message.
+        const DEBOUNCE_MESSAGE_REACTIONS = 1 << 13;
+        /// The second version of [`Self::PASSIVE_GUILD_UPDATE`].
+        const PASSIVE_GUILD_UPDATE_V2 = 1 << 14;
+    }
+}
+
+/// The properties a client identifies itself with, sent in Identify and, base64-encoded, in the
+/// `X-Super-Properties` header of every HTTP request.
+///
+/// The defaults describe the Firefox browser of [`constants::USER_AGENT`] on Windows. Set them
+/// once on the [`HttpBuilder`] or [`ClientBuilder`] so that the gateway and HTTP API see the same
+/// client.
+///
+/// [Discord docs](https://docs.discord.sex/reference#client-properties).
+///
+/// [`constants::USER_AGENT`]: crate::constants::USER_AGENT
+/// [`HttpBuilder`]: crate::http::HttpBuilder::client_properties
+/// [`ClientBuilder`]: crate::client::ClientBuilder::client_properties
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ClientProperties {
+    /// The operating system, e.g. `"Windows"`.
+    pub os: String,
+    /// The version of the operating system, e.g. `"10"`.
+    pub os_version: String,
+    /// The browser or client, e.g. `"Firefox"` or `"Discord Client"`.
+    pub browser: String,
+    /// The user agent of the browser, also sent as the `User-Agent` header.
+    pub browser_user_agent: String,
+    /// The version of the browser, e.g. `"132.0"`.
+    pub browser_version: String,
+    /// The device model, empty on desktop.
+    pub device: String,
+    /// The locale of the system, e.g. `"en-US"`, also sent as the `X-Discord-Locale` header.
+    pub system_locale: String,
+    /// The URL of the page that referred the user to Discord.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referrer: Option<String>,
+    /// The release channel of the client, e.g. `"stable"` or `"canary"`.
+    pub release_channel: String,
+    /// The build number of the client.
+    pub client_build_number: u64,
+    /// The IANA time zone of the system, e.g. `"Europe/London"`, sent as the
+    /// `X-Discord-Timezone` header if set.
+    ///
+    /// This is not part of the properties themselves.
+    #[serde(skip)]
+    pub timezone: Option<String>,
+}
+
+impl Default for ClientProperties {
+    fn default() -> Self {
+        Self {
+            os: "Windows".into(),
+            os_version: "10".into(),
+            browser: "Firefox".into(),
+            browser_user_agent: crate::constants::USER_AGENT.into(),
+            browser_version: "132.0".into(),
+            device: String::new(),
+            system_locale: "en-US".into(),
+            referrer: None,
+            release_channel: "stable".into(),
+            client_build_number: 346_892,
+            timezone: None,
+        }
+    }
+}
+
+/// Builds the payload of a [`Ready`] with only the fields it can't do without, for tests. The
+/// current user has the Id 1, and the given fields are set on top.
+#[cfg(test)]
+pub(crate) fn ready_json<'a>(
+    fields: impl IntoIterator<Item = (&'a str, crate::json::Value)>,
+) -> crate::json::Value {
+    use crate::json::{json, to_value, JsonMap};
+
+    let required = [
+        ("v", json!(9)),
+        ("user", user_json("1")),
+        ("guilds", json!([])),
+        ("relationships", json!([])),
+        ("private_channels", json!([])),
+        ("connected_accounts", json!([])),
+        ("notes", json!({})),
+        ("presences", json!([])),
+        ("session_id", json!("session")),
+        ("session_type", json!("normal")),
+        ("auth_session_id_hash", json!("")),
+        ("analytics_token", json!("")),
+        ("country_code", json!("US")),
+        ("geo_ordered_rtc_regions", json!([])),
+        ("resume_gateway_url", json!("wss://resume.test")),
+    ];
+
+    let mut ready = JsonMap::new();
+    for (key, value) in required.into_iter().chain(fields) {
+        ready.insert(key.to_string(), value);
+    }
+    to_value(ready).expect("JSON maps always serialize")
+}
+
+/// Builds the payload of a [`User`] with the given Id, for tests.
+#[cfg(test)]
+pub(crate) fn user_json(id: &str) -> crate::json::Value {
+    crate::json::json!({"id": id, "username": "user", "discriminator": "0", "avatar": null})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{from_value, json};
+
+    #[test]
+    fn ready_dedupe_user_objects() {
+        let ready: Ready = from_value(ready_json([
+            ("relationships", json!([{"id": "2", "type": 1, "nickname": null}])),
+            ("private_channels", json!([{"id": "3", "type": 1, "recipient_ids": ["2"]}])),
+            ("users", json!([user_json("2")])),
+        ]))
+        .unwrap();
+
+        assert_eq!(ready.relationships[0].user.as_ref().unwrap().id, UserId::new(2));
+        let Channel::Private(channel) = &ready.private_channels[0] else { panic!() };
+        assert_eq!(channel.recipients[0].id, UserId::new(2));
+    }
+}
@@ -642,6 +642,72 @@ impl From<Member> for PartialMember {
     }
 }
 
+/// A guild member that refers to its user by Id, as sent in deduplicated payloads such as
+/// [`ReadySupplementalEvent::merged_members`].
+///
+/// [Discord docs](https://docs.discord.sex/resources/guild#merged-member-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct MergedMember {
+    /// The Id of the member's user.
+    pub user_id: UserId,
+    /// The member's nickname, if present.
+    pub nick: Option<String>,
+    /// The guild avatar hash
+    pub avatar: Option<ImageHash>,
+    /// Vector of Ids of [`Role`]s given to the member.
+    pub roles: Vec<RoleId>,
+    /// Timestamp representing the date when the member joined.
+    pub joined_at: Option<Timestamp>,
+    /// Timestamp representing the date since the member is boosting the guild.
+    pub premium_since: Option<Timestamp>,
+    /// Indicator of whether the member can hear in voice channels.
+    #[serde(default)]
+    pub deaf: bool,
+    /// Indicator of whether the member can speak in voice channels.
+    #[serde(default)]
+    pub mute: bool,
+    /// Guild member flags.
+    #[serde(default)]
+    pub flags: GuildMemberFlags,
+    /// Indicator that the member hasn't accepted the rules of the guild yet.
+    #[serde(default)]
+    pub pending: bool,
+    /// When the user's timeout will expire.
+    pub communication_disabled_until: Option<Timestamp>,
+    /// When the member's flag for sending excessive DMs to non-friend members will expire.
+    pub unusual_dm_activity_until: Option<Timestamp>,
+    /// The unique Id of the guild that the member is a part of.
+    ///
+    /// Manually inserted in [`ReadySupplementalEvent::deserialize`].
+    #[serde(default)]
+    pub guild_id: GuildId,
+}
+
+impl MergedMember {
+    /// Combines this member with the user it refers to.
+    #[must_use]
+    pub fn into_member(self, user: User) -> Member {
+        Member {
+            user,
+            nick: self.nick,
+            avatar: self.avatar,
+            roles: self.roles,
+            joined_at: self.joined_at,
+            premium_since: self.premium_since,
+            deaf: self.deaf,
+            mute: self.mute,
+            flags: self.flags,
+            pending: self.pending,
+            permissions: None,
+            communication_disabled_until: self.communication_disabled_until,
+            guild_id: self.guild_id,
+            unusual_dm_activity_until: self.unusual_dm_activity_until,
+        }
+    }
+}
+
 #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[non_exhaustive]
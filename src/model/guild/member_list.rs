@@ -0,0 +1,363 @@
+use serde::Serialize;
+
+use crate::model::event::GuildMemberListUpdateEvent;
+use crate::model::prelude::*;
+
+/// A group header in a guild member list, either a hoisted role or one of the `"online"` and
+/// `"offline"` pseudo-groups.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#member-list-group-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct MemberListGroup {
+    /// The Id of the hoisted role, or `"online"`/`"offline"`.
+    pub id: String,
+    /// The number of members in the group.
+    #[serde(default)]
+    pub count: u64,
+}
+
+impl MemberListGroup {
+    /// Returns the Id of the hoisted role this group represents, if it is not one of the
+    /// `"online"` and `"offline"` pseudo-groups.
+    #[must_use]
+    pub fn role_id(&self) -> Option<RoleId> {
+        self.id.parse().ok().map(RoleId::new)
+    }
+}
+
+/// A member entry in a guild member list, along with their presence.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#member-list-item-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct MemberListMember {
+    #[serde(flatten)]
+    pub member: Member,
+    pub presence: Option<Presence>,
+}
+
+/// A single row of a guild member list.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#member-list-item-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum MemberListItem {
+    /// A group header, followed by the members in that group.
+    Group(MemberListGroup),
+    /// A member of the group whose header precedes it.
+    Member(Box<MemberListMember>),
+}
+
+impl MemberListItem {
+    /// Returns the member if this row is not a group header.
+    #[must_use]
+    pub fn member(&self) -> Option<&MemberListMember> {
+        match self {
+            Self::Member(member) => Some(member),
+            Self::Group(_) => None,
+        }
+    }
+}
+
+/// An operation to apply to a guild member list, in the order received.
+///
+/// Ranges are inclusive on both ends.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#member-list-operation-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "UPPERCASE")]
+#[non_exhaustive]
+pub enum MemberListOp {
+    /// Replaces the rows in a subscribed range.
+    Sync { range: [u32; 2], items: Vec<MemberListItem> },
+    /// Inserts a row at the given index, shifting the following rows down.
+    Insert { index: u32, item: MemberListItem },
+    /// Replaces the row at the given index.
+    Update { index: u32, item: MemberListItem },
+    /// Removes the row at the given index, shifting the following rows up.
+    Delete { index: u32 },
+    /// Marks the rows in a range as no longer being kept up to date, usually because the range
+    /// was unsubscribed from.
+    Invalidate { range: [u32; 2] },
+}
+
+impl MemberListOp {
+    /// Returns the rows carried by this operation, if any.
+    #[must_use]
+    pub fn items(&self) -> &[MemberListItem] {
+        match self {
+            Self::Sync {
+                items, ..
+            } => items,
+            Self::Insert {
+                item, ..
+            }
+            | Self::Update {
+                item, ..
+            } => std::slice::from_ref(item),
+            Self::Delete {
+                ..
+            }
+            | Self::Invalidate {
+                ..
+            } => &[],
+        }
+    }
+
+    pub(crate) fn items_mut(&mut self) -> &mut [MemberListItem] {
+        match self {
+            Self::Sync {
+                items, ..
+            } => items,
+            Self::Insert {
+                item, ..
+            }
+            | Self::Update {
+                item, ..
+            } => std::slice::from_mut(item),
+            Self::Delete {
+                ..
+            }
+            | Self::Invalidate {
+                ..
+            } => &mut [],
+        }
+    }
+}
+
+/// The state of a guild member list, built up from [`GuildMemberListUpdateEvent`]s.
+///
+/// Channels whose permission overwrites grant or deny [`Permissions::VIEW_CHANNEL`] to the same
+/// set of roles and members share a list; use [`GuildChannel::member_list_id`] to find the list
+/// that backs a channel's sidebar.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct MemberList {
+    /// The Id of the list.
+    pub id: String,
+    /// The Id of the guild the list belongs to.
+    pub guild_id: GuildId,
+    /// The number of members that can view the channels backed by this list.
+    pub member_count: u64,
+    /// The number of those members that are online.
+    pub online_count: u64,
+    /// The groups in the list, in display order, including empty ones.
+    pub groups: Vec<MemberListGroup>,
+    /// The rows of the list, in display order.
+    ///
+    /// Rows outside of the subscribed ranges, or in invalidated ranges, are [`None`].
+    pub items: Vec<Option<MemberListItem>>,
+}
+
+impl MemberList {
+    #[must_use]
+    pub fn new(guild_id: GuildId, id: String) -> Self {
+        Self {
+            id,
+            guild_id,
+            member_count: 0,
+            online_count: 0,
+            groups: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Applies the counts, groups and operations of an update to this list.
+    pub fn apply(&mut self, event: &GuildMemberListUpdateEvent) {
+        self.member_count = event.member_count;
+        self.online_count = event.online_count;
+        self.groups.clone_from(&event.groups);
+
+        for op in &event.ops {
+            self.apply_op(op);
+        }
+    }
+
+    fn apply_op(&mut self, op: &MemberListOp) {
+        match op {
+            MemberListOp::Sync {
+                range: [start, end],
+                items,
+            } => {
+                let slots = self.slots(*start as usize, *end as usize);
+                slots.fill(None);
+                for (slot, item) in slots.iter_mut().zip(items) {
+                    *slot = Some(item.clone());
+                }
+            },
+            MemberListOp::Insert {
+                index,
+                item,
+            } => {
+                let index = *index as usize;
+                if index > self.items.len() {
+                    self.items.resize(index, None);
+                }
+                self.items.insert(index, Some(item.clone()));
+            },
+            MemberListOp::Update {
+                index,
+                item,
+            } => {
+                let index = *index as usize;
+                self.slots(index, index)[0] = Some(item.clone());
+            },
+            MemberListOp::Delete {
+                index,
+            } => {
+                let index = *index as usize;
+                if index < self.items.len() {
+                    self.items.remove(index);
+                }
+            },
+            MemberListOp::Invalidate {
+                range: [start, end],
+            } => {
+                let end = (*end as usize + 1).min(self.items.len());
+                if let Some(slots) = self.items.get_mut(*start as usize..end) {
+                    slots.fill(None);
+                }
+            },
+        }
+
+        while self.items.last().is_some_and(Option::is_none) {
+            self.items.pop();
+        }
+    }
+
+    /// Returns the rows in `start..=end`, growing the list if needed.
+    fn slots(&mut self, start: usize, end: usize) -> &mut [Option<MemberListItem>] {
+        if self.items.len() <= end {
+            self.items.resize(end + 1, None);
+        }
+        self.items.get_mut(start..=end).unwrap_or_default()
+    }
+
+    /// Returns the group with the given Id, e.g. to look up the count shown in its header.
+    #[must_use]
+    pub fn group(&self, id: &str) -> Option<&MemberListGroup> {
+        self.groups.iter().find(|g| g.id == id)
+    }
+
+    /// Iterates over the members in the synced rows, in display order.
+    pub fn members(&self) -> impl Iterator<Item = &MemberListMember> {
+        self.items.iter().flatten().filter_map(MemberListItem::member)
+    }
+}
+
+/// Computes the 32-bit x86 variant of MurmurHash3 with a seed of 0, which Discord uses to derive
+/// member list Ids from permission overwrites.
+pub(crate) fn murmur3_32(data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut hash = 0u32;
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+
+    for chunk in chunks {
+        let mut k = u32::from_le_bytes(chunk.try_into().expect("4 bytes"));
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    if !tail.is_empty() {
+        let k = tail.iter().rev().fold(0u32, |k, &b| (k << 8) | u32::from(b));
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let len = data.len() as u32;
+    hash ^= len;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{from_value, json, to_value, Value};
+
+    fn member(id: u64) -> Value {
+        json!({"member": {
+            "user": {"id": id.to_string(), "username": "user", "discriminator": "0", "avatar": null},
+            "roles": [],
+            "joined_at": "2020-01-01T00:00:00.000000+00:00",
+            "deaf": false,
+            "mute": false,
+            "flags": 0,
+            "presence": {"user": {"id": id.to_string()}, "status": "online", "activities": []},
+        }})
+    }
+
+    fn user_id(item: &Option<MemberListItem>) -> Option<u64> {
+        item.as_ref()?.member().map(|m| m.member.user.id.get())
+    }
+
+    #[test]
+    fn apply_ops() {
+        let event: GuildMemberListUpdateEvent = from_value(json!({
+            "id": "everyone",
+            "guild_id": "1",
+            "member_count": 3,
+            "online_count": 3,
+            "groups": [{"id": "online", "count": 3}],
+            "ops": [{"op": "SYNC", "range": [0, 99], "items": [
+                {"group": {"id": "online", "count": 3}}, member(10), member(11), member(12),
+            ]}],
+        }))
+        .unwrap();
+
+        let mut list = MemberList::new(event.guild_id, event.id.clone());
+        list.apply(&event);
+        assert_eq!(list.items.len(), 4);
+        assert_eq!(list.members().count(), 3);
+        assert_eq!(list.members().next().unwrap().member.guild_id, GuildId::new(1));
+        assert_eq!(list.group("online").unwrap().count, 3);
+
+        let event: GuildMemberListUpdateEvent = from_value(json!({
+            "id": "everyone",
+            "guild_id": "1",
+            "member_count": 3,
+            "online_count": 3,
+            "groups": [{"id": "online", "count": 3}],
+            "ops": [
+                {"op": "DELETE", "index": 1},
+                {"op": "INSERT", "index": 3, "item": member(13)},
+                {"op": "UPDATE", "index": 1, "item": member(14)},
+                {"op": "INVALIDATE", "range": [3, 99]},
+            ],
+        }))
+        .unwrap();
+
+        list.apply(&event);
+        let ids: Vec<_> = list.items.iter().map(user_id).collect();
+        assert_eq!(ids, [None, Some(14), Some(12)]);
+    }
+
+    #[test]
+    fn op_serde() {
+        let op = MemberListOp::Invalidate {
+            range: [0, 99],
+        };
+        assert_eq!(to_value(&op).unwrap(), json!({"op": "INVALIDATE", "range": [0, 99]}));
+    }
+
+    #[test]
+    fn murmur3() {
+        assert_eq!(murmur3_32(b""), 0);
+        assert_eq!(murmur3_32(b"hello"), 0x248b_fa47);
+        assert_eq!(murmur3_32(b"hello, world"), 0x149b_bb7f);
+    }
+}
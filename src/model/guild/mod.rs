@@ -7,6 +7,7 @@ mod guild_id;
 mod guild_preview;
 mod integration;
 mod member;
+mod member_list;
 mod partial_guild;
 mod premium_tier;
 mod role;
@@ -25,6 +26,7 @@ pub use self::guild_id::*;
 pub use self::guild_preview::*;
 pub use self::integration::*;
 pub use self::member::*;
+pub use self::member_list::*;
 pub use self::partial_guild::*;
 pub use self::premium_tier::*;
 pub use self::role::*;
@@ -256,14 +258,20 @@ pub struct Guild {
     /// The number of members in the guild.
     pub member_count: u64,
     /// A mapping of [`User`]s to their current voice state.
-    #[serde(serialize_with = "serialize_map_values")]
+    ///
+    /// For user sessions, these are sent in [`ReadySupplementalEvent`] rather than the
+    /// [`ReadyEvent`].
+    #[serde(default, serialize_with = "serialize_map_values")]
     #[serde(deserialize_with = "deserialize_voice_states")]
     pub voice_states: HashMap<UserId, VoiceState>,
     /// Users who are members of the guild.
     ///
     /// Members might not all be available when the [`ReadyEvent`] is received if the
     /// [`Self::member_count`] is greater than the [`LARGE_THRESHOLD`] set by the library.
-    #[serde(with = "members")]
+    ///
+    /// With [`GatewayCapabilities::DEDUPE_USER_OBJECTS`], the [`ReadyEvent`] sends members
+    /// separately in [`Ready::merged_members`], and they are moved here on deserialization.
+    #[serde(default, with = "members")]
     pub members: HashMap<UserId, Member>,
     /// All voice and text channels contained within a guild.
     ///
@@ -281,7 +289,7 @@ pub struct Guild {
     /// A mapping of [`User`]s' Ids to their current presences.
     ///
     /// **Note**: This will be empty unless the "guild presences" privileged intent is enabled.
-    #[serde(with = "presences")]
+    #[serde(default, with = "presences")]
     pub presences: HashMap<UserId, Presence>,
     /// The stage instances in this guild.
     pub stage_instances: Vec<StageInstance>,
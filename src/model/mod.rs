@@ -34,9 +34,11 @@ pub mod mention;
 pub mod misc;
 pub mod monetization;
 pub mod permissions;
+pub mod relationship;
 pub mod sticker;
 pub mod timestamp;
 pub mod user;
+pub mod user_settings;
 pub mod voice;
 pub mod webhook;
 
@@ -93,8 +95,10 @@ pub mod prelude {
         misc::*,
         monetization::*,
         permissions::*,
+        relationship::*,
         sticker::*,
         user::*,
+        user_settings::*,
         voice::*,
         webhook::*,
         ModelError,
@@ -0,0 +1,85 @@
+//! Models for relationships between the current user and other users.
+
+use super::prelude::*;
+
+/// A relationship between the current user and another user, such as a friendship, a block or a
+/// pending friend request.
+///
+/// [Discord docs](https://docs.discord.sex/resources/relationships#relationship-object).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct Relationship {
+    /// The Id of the target user.
+    pub id: UserId,
+    /// The kind of relationship with the target user.
+    #[serde(rename = "type")]
+    pub kind: RelationshipType,
+    /// The nickname the current user has given to the target user, if any.
+    pub nickname: Option<String>,
+    /// The target user.
+    ///
+    /// **Note**: This is omitted from [`Ready::relationships`] when the `DEDUPE_USER_OBJECTS`
+    /// gateway capability is enabled, in which case the user can be found in [`Ready::users`].
+    pub user: Option<User>,
+    /// When the relationship was created.
+    pub since: Option<Timestamp>,
+}
+
+enum_number! {
+    /// The kind of a [`Relationship`].
+    ///
+    /// [Discord docs](https://docs.discord.sex/resources/relationships#relationship-type).
+    #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+    #[serde(from = "u8", into = "u8")]
+    #[non_exhaustive]
+    pub enum RelationshipType {
+        /// No relationship exists.
+        #[default]
+        None = 0,
+        /// The target user is a friend.
+        Friend = 1,
+        /// The target user is blocked.
+        Blocked = 2,
+        /// The target user has sent a friend request to the current user.
+        IncomingRequest = 3,
+        /// The current user has sent a friend request to the target user.
+        OutgoingRequest = 4,
+        /// The target user is an implicit relationship, e.g. someone the current user frequently
+        /// interacts with.
+        Implicit = 5,
+        _ => Unknown(u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{assert_json, from_value, json};
+
+    #[test]
+    fn relationship_serde() {
+        let relationship: Relationship = from_value(json!({
+            "id": "2",
+            "type": 3,
+            "nickname": null,
+            "user": {"id": "2", "username": "friend", "discriminator": "0", "avatar": null},
+            "since": "2024-01-01T00:00:00+00:00",
+        }))
+        .unwrap();
+        assert_eq!(relationship.id, UserId::new(2));
+        assert_eq!(relationship.kind, RelationshipType::IncomingRequest);
+        assert_eq!(relationship.user.unwrap().name, "friend");
+        assert!(relationship.since.is_some());
+
+        // Deduplicated users and unknown kinds are still deserialized.
+        let relationship: Relationship =
+            from_value(json!({"id": "3", "type": 9, "nickname": "n", "since": null})).unwrap();
+        assert_eq!(relationship.kind, RelationshipType::Unknown(9));
+        assert_eq!(relationship.nickname.as_deref(), Some("n"));
+        assert!(relationship.user.is_none());
+
+        assert_json(&RelationshipType::Blocked, json!(2));
+    }
+}
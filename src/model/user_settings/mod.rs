@@ -0,0 +1,820 @@
+//! Models for the protobuf-encoded user settings.
+//!
+//! Discord stores most client settings as protobuf blobs, which are sent base64-encoded in
+//! [`Ready::user_settings_proto`] and [`UserSettingsProtoUpdateEvent`]s. Only the commonly used
+//! parts of each message are modelled here; any other fields are skipped when decoding.
+//!
+//! [`UserSettingsProtoUpdateEvent`]: super::event::UserSettingsProtoUpdateEvent
+
+mod wire;
+
+use std::num::NonZeroU64;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine as _;
+use serde::de::Error as DeError;
+use serde::ser::{Serialize, Serializer};
+
+use self::wire::{Reader, Value, Writer};
+use super::prelude::*;
+
+enum_number! {
+    /// The kind of a user settings protobuf blob.
+    ///
+    /// [Discord docs](https://docs.discord.sex/resources/user-settings-proto#user-settings-type).
+    #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+    #[serde(from = "u8", into = "u8")]
+    #[non_exhaustive]
+    pub enum UserSettingsProtoType {
+        /// General user settings, see [`PreloadedUserSettings`].
+        #[default]
+        PreloadedUserSettings = 1,
+        /// Frequently and recently used items, see [`FrecencyUserSettings`].
+        FrecencyUserSettings = 2,
+        /// Settings used for testing by Discord.
+        TestSettings = 3,
+        _ => Unknown(u8),
+    }
+}
+
+/// A protobuf-encoded user settings message that can be fetched and edited through the
+/// [`Http::get_user_settings_proto`] and [`Http::edit_user_settings_proto`] methods.
+///
+/// [`Http::get_user_settings_proto`]: crate::http::Http::get_user_settings_proto
+/// [`Http::edit_user_settings_proto`]: crate::http::Http::edit_user_settings_proto
+pub trait SettingsProto: Sized {
+    /// The kind of settings this message holds.
+    const KIND: UserSettingsProtoType;
+
+    /// Decodes the message from its protobuf encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ModelError::InvalidSettingsProto`] if the bytes are not a valid encoding.
+    fn decode(bytes: &[u8]) -> Result<Self, ModelError>;
+
+    /// Encodes the message into its protobuf encoding.
+    ///
+    /// Fields that are [`None`] are omitted, so a partially filled message can be used to only
+    /// update some settings.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes the message from base64-encoded protobuf, as sent by Discord.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ModelError::InvalidSettingsProto`] if the string is not valid base64 or does not
+    /// contain a valid encoding.
+    fn from_base64(encoded: &str) -> Result<Self, ModelError> {
+        let bytes =
+            BASE64_STANDARD.decode(encoded).map_err(|_| ModelError::InvalidSettingsProto)?;
+        Self::decode(&bytes)
+    }
+
+    /// Encodes the message into base64-encoded protobuf, as expected by Discord.
+    fn to_base64(&self) -> String {
+        BASE64_STANDARD.encode(self.encode())
+    }
+}
+
+/// Schema of a single protobuf message, used to share the decoding loop between all messages.
+trait Message: Default {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError>;
+
+    fn write_fields(&self, w: &mut Writer);
+}
+
+fn decode<M: Message>(bytes: &[u8]) -> Result<M, ModelError> {
+    let mut message = M::default();
+    let mut reader = Reader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        message.merge_field(field, value)?;
+    }
+    Ok(message)
+}
+
+fn encode<M: Message>(message: &M) -> Vec<u8> {
+    let mut w = Writer::default();
+    message.write_fields(&mut w);
+    w.into_inner()
+}
+
+/// Returns the inner value of one of the `google.protobuf.*Value` wrapper messages, or [`None`]
+/// if the wrapper holds the default value.
+fn wrapped(value: Value<'_>) -> Result<Option<Value<'_>>, ModelError> {
+    let mut reader = Reader::new(value.as_bytes()?);
+    let mut inner = None;
+    while let Some((field, value)) = reader.next_field()? {
+        if field == 1 {
+            inner = Some(value);
+        }
+    }
+    Ok(inner)
+}
+
+fn wrapped_string(value: Value<'_>) -> Result<String, ModelError> {
+    wrapped(value)?.map_or(Ok(String::new()), |v| v.as_str().map(String::from))
+}
+
+fn non_zero(value: u64) -> Option<u64> {
+    (value != 0).then_some(value)
+}
+
+/// Decodes a map entry, which is encoded as a message with the key in field 1 and the value in
+/// field 2.
+fn map_entry(value: Value<'_>) -> Result<(Option<Value<'_>>, Option<Value<'_>>), ModelError> {
+    let mut reader = Reader::new(value.as_bytes()?);
+    let (mut key, mut val) = (None, None);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            1 => key = Some(value),
+            2 => val = Some(value),
+            _ => {},
+        }
+    }
+    Ok((key, val))
+}
+
+/// General settings of the current user.
+///
+/// [Discord docs](https://docs.discord.sex/resources/user-settings-proto#preloaded-user-settings-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct PreloadedUserSettings {
+    /// Versioning information of the settings.
+    pub versions: Option<UserSettingsVersions>,
+    /// The user's online status and custom status.
+    pub status: Option<StatusSettings>,
+    /// The user's language and time zone.
+    pub localization: Option<LocalizationSettings>,
+    /// The client's appearance, such as the theme.
+    pub appearance: Option<AppearanceSettings>,
+    /// The folders the user has sorted their guilds into.
+    pub guild_folders: Option<GuildFolders>,
+}
+
+impl Message for PreloadedUserSettings {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        let bytes = || value.as_bytes();
+        match field {
+            1 => self.versions = Some(decode(bytes()?)?),
+            11 => self.status = Some(decode(bytes()?)?),
+            12 => self.localization = Some(decode(bytes()?)?),
+            13 => self.appearance = Some(decode(bytes()?)?),
+            14 => self.guild_folders = Some(decode(bytes()?)?),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        if let Some(versions) = &self.versions {
+            w.message(1, |w| versions.write_fields(w));
+        }
+        if let Some(status) = &self.status {
+            w.message(11, |w| status.write_fields(w));
+        }
+        if let Some(localization) = &self.localization {
+            w.message(12, |w| localization.write_fields(w));
+        }
+        if let Some(appearance) = &self.appearance {
+            w.message(13, |w| appearance.write_fields(w));
+        }
+        if let Some(guild_folders) = &self.guild_folders {
+            w.message(14, |w| guild_folders.write_fields(w));
+        }
+    }
+}
+
+impl SettingsProto for PreloadedUserSettings {
+    const KIND: UserSettingsProtoType = UserSettingsProtoType::PreloadedUserSettings;
+
+    fn decode(bytes: &[u8]) -> Result<Self, ModelError> {
+        decode(bytes)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+}
+
+/// Versioning information of a settings message.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UserSettingsVersions {
+    /// The version of the client that last wrote the settings.
+    pub client_version: u32,
+    /// The version of the server-side settings.
+    pub server_version: u32,
+    /// The version of the settings data, which increases with every change.
+    pub data_version: u32,
+}
+
+impl Message for UserSettingsVersions {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => self.client_version = value.as_u32()?,
+            2 => self.server_version = value.as_u32()?,
+            3 => self.data_version = value.as_u32()?,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        w.varint(1, self.client_version.into());
+        w.varint(2, self.server_version.into());
+        w.varint(3, self.data_version.into());
+    }
+}
+
+/// The user's online status and custom status.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct StatusSettings {
+    /// The user's online status.
+    pub status: Option<OnlineStatus>,
+    /// The user's custom status.
+    pub custom_status: Option<CustomStatus>,
+    /// Whether the user's current game is shown in their presence.
+    pub show_current_game: Option<bool>,
+    /// Unix timestamp in milliseconds of when the online status expires, if it does.
+    pub status_expires_at_ms: Option<u64>,
+}
+
+impl Message for StatusSettings {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => {
+                self.status = match wrapped_string(value)?.as_str() {
+                    "online" => Some(OnlineStatus::Online),
+                    "idle" => Some(OnlineStatus::Idle),
+                    "dnd" => Some(OnlineStatus::DoNotDisturb),
+                    "invisible" => Some(OnlineStatus::Invisible),
+                    "offline" => Some(OnlineStatus::Offline),
+                    _ => None,
+                };
+            },
+            2 => self.custom_status = Some(decode(value.as_bytes()?)?),
+            3 => {
+                self.show_current_game = Some(wrapped(value)?.map_or(Ok(false), Value::as_bool)?);
+            },
+            4 => self.status_expires_at_ms = non_zero(value.as_u64()?),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        if let Some(status) = self.status {
+            w.message(1, |w| w.string(1, status.name()));
+        }
+        if let Some(custom_status) = &self.custom_status {
+            w.message(2, |w| custom_status.write_fields(w));
+        }
+        if let Some(show_current_game) = self.show_current_game {
+            w.message(3, |w| w.bool(1, show_current_game));
+        }
+        w.fixed64(4, self.status_expires_at_ms.unwrap_or_default());
+    }
+}
+
+/// A custom status set by the user.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct CustomStatus {
+    /// The text of the custom status.
+    pub text: String,
+    /// The Id of the custom emoji shown next to the status, if any.
+    pub emoji_id: Option<EmojiId>,
+    /// The name of the emoji shown next to the status, or the unicode emoji itself.
+    pub emoji_name: Option<String>,
+    /// Unix timestamp in milliseconds of when the custom status expires, if it does.
+    pub expires_at_ms: Option<u64>,
+    /// Unix timestamp in milliseconds of when the custom status was set.
+    pub created_at_ms: Option<u64>,
+}
+
+impl Message for CustomStatus {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => value.as_str()?.clone_into(&mut self.text),
+            2 => self.emoji_id = non_zero(value.as_u64()?).map(EmojiId::new),
+            3 => self.emoji_name = Some(value.as_str()?.to_owned()).filter(|s| !s.is_empty()),
+            4 => self.expires_at_ms = non_zero(value.as_u64()?),
+            5 => self.created_at_ms = non_zero(value.as_u64()?),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        w.string(1, &self.text);
+        w.fixed64(2, self.emoji_id.map(EmojiId::get).unwrap_or_default());
+        w.string(3, self.emoji_name.as_deref().unwrap_or_default());
+        w.fixed64(4, self.expires_at_ms.unwrap_or_default());
+        w.fixed64(5, self.created_at_ms.unwrap_or_default());
+    }
+}
+
+/// The user's language and time zone.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LocalizationSettings {
+    /// The user's locale, e.g. `en-US`.
+    pub locale: Option<String>,
+    /// The user's offset from UTC, in minutes.
+    pub timezone_offset: Option<i32>,
+}
+
+impl Message for LocalizationSettings {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => self.locale = Some(wrapped_string(value)?),
+            2 => self.timezone_offset = Some(wrapped(value)?.map_or(Ok(0), Value::as_i32)?),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        if let Some(locale) = &self.locale {
+            w.message(1, |w| w.string(1, locale));
+        }
+        if let Some(timezone_offset) = self.timezone_offset {
+            w.message(2, |w| w.int32(1, timezone_offset));
+        }
+    }
+}
+
+/// The appearance of the client.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AppearanceSettings {
+    /// The client theme.
+    pub theme: Theme,
+    /// Whether developer mode is enabled, which allows copying Ids from the client.
+    pub developer_mode: bool,
+}
+
+impl Message for AppearanceSettings {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => {
+                let theme = u8::try_from(value.as_u64()?);
+                self.theme = theme.map_err(|_| ModelError::InvalidSettingsProto)?.into();
+            },
+            2 => self.developer_mode = value.as_bool()?,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        w.varint(1, u8::from(self.theme).into());
+        w.bool(2, self.developer_mode);
+    }
+}
+
+enum_number! {
+    /// The theme of the client.
+    ///
+    /// [Discord docs](https://docs.discord.sex/resources/user-settings-proto#theme).
+    #[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+    #[serde(from = "u8", into = "u8")]
+    #[non_exhaustive]
+    pub enum Theme {
+        #[default]
+        Unset = 0,
+        Dark = 1,
+        Light = 2,
+        Darker = 3,
+        Midnight = 4,
+        _ => Unknown(u8),
+    }
+}
+
+/// The folders the user has sorted their guilds into.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct GuildFolders {
+    /// The guild folders, in display order.
+    pub folders: Vec<GuildFolder>,
+    /// The display order of guilds that are not in a folder.
+    pub guild_positions: Vec<GuildId>,
+}
+
+impl Message for GuildFolders {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => self.folders.push(decode(value.as_bytes()?)?),
+            2 => extend_ids(value, &mut self.guild_positions)?,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        for folder in &self.folders {
+            w.message(1, |w| folder.write_fields(w));
+        }
+        w.packed_fixed64s(2, self.guild_positions.iter().map(|id| id.get()));
+    }
+}
+
+/// A folder of guilds.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct GuildFolder {
+    /// The guilds in the folder, in display order.
+    pub guild_ids: Vec<GuildId>,
+    /// The Id of the folder.
+    pub id: Option<i64>,
+    /// The name of the folder.
+    pub name: Option<String>,
+    /// The colour of the folder.
+    pub colour: Option<Colour>,
+}
+
+impl Message for GuildFolder {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => extend_ids(value, &mut self.guild_ids)?,
+            2 => self.id = Some(wrapped(value)?.map_or(Ok(0), Value::as_u64)? as i64),
+            3 => self.name = Some(wrapped_string(value)?),
+            4 => self.colour = Some(Colour(wrapped(value)?.map_or(Ok(0), Value::as_u64)? as u32)),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn write_fields(&self, w: &mut Writer) {
+        w.packed_fixed64s(1, self.guild_ids.iter().map(|id| id.get()));
+        if let Some(id) = self.id {
+            w.message(2, |w| w.varint(1, id as u64));
+        }
+        if let Some(name) = &self.name {
+            w.message(3, |w| w.string(1, name));
+        }
+        if let Some(colour) = self.colour {
+            w.message(4, |w| w.varint(1, colour.0.into()));
+        }
+    }
+}
+
+fn extend_ids<Id: From<NonZeroU64>>(value: Value<'_>, out: &mut Vec<Id>) -> Result<(), ModelError> {
+    let mut raw = Vec::new();
+    value.extend_fixed64s(&mut raw)?;
+    out.extend(raw.into_iter().filter_map(NonZeroU64::new).map(Id::from));
+    Ok(())
+}
+
+/// Favourite and frequently used items of the current user.
+///
+/// [Discord docs](https://docs.discord.sex/resources/user-settings-proto#frecency-user-settings-structure).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[non_exhaustive]
+pub struct FrecencyUserSettings {
+    /// Versioning information of the settings.
+    pub versions: Option<UserSettingsVersions>,
+    /// The stickers the user has marked as favourite.
+    pub favorite_stickers: Option<Vec<StickerId>>,
+    /// How frequently and recently the user has used each sticker.
+    pub sticker_frecency: Option<HashMap<StickerId, FrecencyItem>>,
+    /// The emojis the user has marked as favourite, either as unicode emojis or custom emoji Ids.
+    pub favorite_emojis: Option<Vec<String>>,
+    /// How frequently and recently the user has used each emoji, keyed by the unicode emoji or
+    /// custom emoji Id.
+    pub emoji_frecency: Option<HashMap<String, FrecencyItem>>,
+}
+
+impl Message for FrecencyUserSettings {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => self.versions = Some(decode(value.as_bytes()?)?),
+            3 => {
+                let stickers = self.favorite_stickers.get_or_insert_with(Vec::new);
+                let mut reader = Reader::new(value.as_bytes()?);
+                while let Some((field, value)) = reader.next_field()? {
+                    if field == 1 {
+                        extend_ids(value, stickers)?;
+                    }
+                }
+            },
+            4 => {
+                let frecency = self.sticker_frecency.get_or_insert_with(HashMap::new);
+                let mut reader = Reader::new(value.as_bytes()?);
+                while let Some((field, value)) = reader.next_field()? {
+                    if field == 1 {
+                        let (key, item) = map_entry(value)?;
+                        let key = key.map_or(Ok(0), Value::as_u64)?;
+                        if let Some(id) = NonZeroU64::new(key) {
+                            let item = item.map_or(Ok(&[][..]), Value::as_bytes)?;
+                            frecency.insert(StickerId::from(id), decode(item)?);
+                        }
+                    }
+                }
+            },
+            5 => {
+                let emojis = self.favorite_emojis.get_or_insert_with(Vec::new);
+                let mut reader = Reader::new(value.as_bytes()?);
+                while let Some((field, value)) = reader.next_field()? {
+                    if field == 1 {
+                        emojis.push(value.as_str()?.to_owned());
+                    }
+                }
+            },
+            6 => {
+                let frecency = self.emoji_frecency.get_or_insert_with(HashMap::new);
+                let mut reader = Reader::new(value.as_bytes()?);
+                while let Some((field, value)) = reader.next_field()? {
+                    if field == 1 {
+                        let (key, item) = map_entry(value)?;
+                        let key = key.map_or(Ok(""), Value::as_str)?;
+                        let item = item.map_or(Ok(&[][..]), Value::as_bytes)?;
+                        frecency.insert(key.to_owned(), decode(item)?);
+                    }
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        if let Some(versions) = &self.versions {
+            w.message(1, |w| versions.write_fields(w));
+        }
+        if let Some(stickers) = &self.favorite_stickers {
+            w.message(3, |w| w.packed_fixed64s(1, stickers.iter().map(|id| id.get())));
+        }
+        if let Some(frecency) = &self.sticker_frecency {
+            w.message(4, |w| {
+                for (id, item) in frecency {
+                    w.message(1, |w| {
+                        w.fixed64(1, id.get());
+                        w.message(2, |w| item.write_fields(w));
+                    });
+                }
+            });
+        }
+        if let Some(emojis) = &self.favorite_emojis {
+            w.message(5, |w| {
+                for emoji in emojis {
+                    w.bytes(1, emoji.as_bytes());
+                }
+            });
+        }
+        if let Some(frecency) = &self.emoji_frecency {
+            w.message(6, |w| {
+                for (emoji, item) in frecency {
+                    w.message(1, |w| {
+                        w.string(1, emoji);
+                        w.message(2, |w| item.write_fields(w));
+                    });
+                }
+            });
+        }
+    }
+}
+
+impl SettingsProto for FrecencyUserSettings {
+    const KIND: UserSettingsProtoType = UserSettingsProtoType::FrecencyUserSettings;
+
+    fn decode(bytes: &[u8]) -> Result<Self, ModelError> {
+        decode(bytes)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+}
+
+/// How frequently and recently an item has been used.
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FrecencyItem {
+    /// The total number of times the item has been used.
+    pub total_uses: u32,
+    /// Unix timestamps in milliseconds of the most recent uses.
+    pub recent_uses: Vec<u64>,
+    /// The frecency score computed by the client.
+    pub frecency: i32,
+    /// The raw score of the item.
+    pub score: i32,
+}
+
+impl Message for FrecencyItem {
+    fn merge_field(&mut self, field: u32, value: Value<'_>) -> Result<(), ModelError> {
+        match field {
+            1 => self.total_uses = value.as_u32()?,
+            2 => value.extend_varints(&mut self.recent_uses)?,
+            3 => self.frecency = value.as_i32()?,
+            4 => self.score = value.as_i32()?,
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn write_fields(&self, w: &mut Writer) {
+        w.varint(1, self.total_uses.into());
+        w.packed_varints(2, self.recent_uses.iter().copied());
+        w.int32(3, self.frecency);
+        w.int32(4, self.score);
+    }
+}
+
+/// A user settings protobuf blob, decoded according to its kind.
+///
+/// [Discord docs](https://docs.discord.sex/topics/gateway-events#user-settings-proto-update).
+#[cfg_attr(feature = "typesize", derive(typesize::derive::TypeSize))]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum UserSettingsProto {
+    Preloaded(PreloadedUserSettings),
+    Frecency(FrecencyUserSettings),
+    /// Settings of a kind that is not modelled, with the raw protobuf encoding.
+    Unknown {
+        kind: UserSettingsProtoType,
+        proto: Vec<u8>,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+struct RawUserSettingsProto {
+    #[serde(rename = "type")]
+    kind: UserSettingsProtoType,
+    proto: String,
+}
+
+impl<'de> Deserialize<'de> for UserSettingsProto {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> StdResult<Self, D::Error> {
+        let raw = RawUserSettingsProto::deserialize(deserializer)?;
+        let proto = BASE64_STANDARD.decode(raw.proto).map_err(D::Error::custom)?;
+
+        Ok(match raw.kind {
+            UserSettingsProtoType::PreloadedUserSettings => {
+                Self::Preloaded(SettingsProto::decode(&proto).map_err(D::Error::custom)?)
+            },
+            UserSettingsProtoType::FrecencyUserSettings => {
+                Self::Frecency(SettingsProto::decode(&proto).map_err(D::Error::custom)?)
+            },
+            kind => Self::Unknown {
+                kind,
+                proto,
+            },
+        })
+    }
+}
+
+impl Serialize for UserSettingsProto {
+    fn serialize<S: Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        let (kind, proto) = match self {
+            Self::Preloaded(settings) => (PreloadedUserSettings::KIND, settings.encode()),
+            Self::Frecency(settings) => (FrecencyUserSettings::KIND, settings.encode()),
+            Self::Unknown {
+                kind,
+                proto,
+            } => (*kind, proto.clone()),
+        };
+
+        RawUserSettingsProto {
+            kind,
+            proto: BASE64_STANDARD.encode(proto),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Used with `#[serde(with = "base64_proto")]` on optional fields holding base64-encoded
+/// settings.
+pub(crate) mod base64_proto {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_cow::CowStr;
+
+    use super::SettingsProto;
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: SettingsProto,
+    {
+        let encoded = <Option<CowStr<'de>>>::deserialize(deserializer)?;
+        encoded.map(|CowStr(s)| T::from_base64(&s).map_err(D::Error::custom)).transpose()
+    }
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: SettingsProto,
+    {
+        match value {
+            Some(value) => serializer.serialize_some(&value.to_base64()),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preloaded_round_trip() {
+        let settings = PreloadedUserSettings {
+            versions: Some(UserSettingsVersions {
+                client_version: 14,
+                server_version: 0,
+                data_version: 3021,
+            }),
+            status: Some(StatusSettings {
+                status: Some(OnlineStatus::DoNotDisturb),
+                custom_status: Some(CustomStatus {
+                    text: "hi".to_owned(),
+                    emoji_name: Some("\u{1f44b}".to_owned()),
+                    ..Default::default()
+                }),
+                show_current_game: Some(false),
+                status_expires_at_ms: None,
+            }),
+            localization: Some(LocalizationSettings {
+                locale: Some("en-GB".to_owned()),
+                timezone_offset: Some(-300),
+            }),
+            appearance: Some(AppearanceSettings {
+                theme: Theme::Dark,
+                developer_mode: true,
+            }),
+            guild_folders: Some(GuildFolders {
+                folders: vec![GuildFolder {
+                    guild_ids: vec![GuildId::new(1), GuildId::new(2)],
+                    id: Some(-7),
+                    name: Some("folder".to_owned()),
+                    colour: Some(Colour(0x00FF_00FF)),
+                }],
+                guild_positions: vec![GuildId::new(3)],
+            }),
+        };
+
+        let decoded = PreloadedUserSettings::from_base64(&settings.to_base64()).unwrap();
+        assert_eq!(decoded, settings);
+    }
+
+    #[test]
+    fn partial_encoding_omits_unset_fields() {
+        let settings = PreloadedUserSettings {
+            appearance: Some(AppearanceSettings {
+                theme: Theme::Light,
+                developer_mode: false,
+            }),
+            ..Default::default()
+        };
+
+        // Field 13, length 2, containing field 1 = 2.
+        assert_eq!(settings.encode(), [0x6A, 0x02, 0x08, 0x02]);
+    }
+
+    #[test]
+    fn frecency_round_trip() {
+        let settings = FrecencyUserSettings {
+            favorite_stickers: Some(vec![StickerId::new(749_054_660_769_218_631)]),
+            favorite_emojis: Some(vec!["\u{1f600}".to_owned(), "1234".to_owned()]),
+            emoji_frecency: Some(HashMap::from([("\u{1f600}".to_owned(), FrecencyItem {
+                total_uses: 3,
+                recent_uses: vec![1_700_000_000_000, 1_700_000_100_000],
+                frecency: -1,
+                score: 50,
+            })])),
+            ..Default::default()
+        };
+
+        let decoded = FrecencyUserSettings::decode(&settings.encode()).unwrap();
+        assert_eq!(decoded, settings);
+    }
+
+    #[test]
+    fn skips_unknown_fields_and_rejects_truncated_input() {
+        // Field 9 (unmodelled message), then field 13 with theme = Midnight.
+        let bytes = [0x4A, 0x01, 0x00, 0x6A, 0x02, 0x08, 0x04];
+        let decoded = PreloadedUserSettings::decode(&bytes).unwrap();
+        assert_eq!(decoded.appearance.unwrap().theme, Theme::Midnight);
+
+        assert!(PreloadedUserSettings::decode(&bytes[..5]).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! A minimal reader and writer for the protobuf wire format, covering exactly what the user
+//! settings messages need.
+
+use super::super::ModelError;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LEN: u8 = 2;
+const FIXED32: u8 = 5;
+
+/// A single decoded field value, before it is interpreted according to its message's schema.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    pub fn as_u64(self) -> Result<u64, ModelError> {
+        match self {
+            Self::Varint(v) | Self::Fixed64(v) => Ok(v),
+            Self::Fixed32(v) => Ok(v.into()),
+            Self::Bytes(_) => Err(ModelError::InvalidSettingsProto),
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn as_u32(self) -> Result<u32, ModelError> {
+        self.as_u64().map(|v| v as u32)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn as_i32(self) -> Result<i32, ModelError> {
+        self.as_u64().map(|v| v as i32)
+    }
+
+    pub fn as_bool(self) -> Result<bool, ModelError> {
+        self.as_u64().map(|v| v != 0)
+    }
+
+    pub fn as_bytes(self) -> Result<&'a [u8], ModelError> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(ModelError::InvalidSettingsProto),
+        }
+    }
+
+    pub fn as_str(self) -> Result<&'a str, ModelError> {
+        std::str::from_utf8(self.as_bytes()?).map_err(|_| ModelError::InvalidSettingsProto)
+    }
+
+    /// Appends the values of a repeated varint field, which may be either packed or unpacked.
+    pub fn extend_varints(self, out: &mut Vec<u64>) -> Result<(), ModelError> {
+        match self {
+            Self::Bytes(mut bytes) => {
+                while !bytes.is_empty() {
+                    out.push(read_varint(&mut bytes)?);
+                }
+                Ok(())
+            },
+            other => {
+                out.push(other.as_u64()?);
+                Ok(())
+            },
+        }
+    }
+
+    /// Appends the values of a repeated fixed64 field, which may be either packed or unpacked.
+    pub fn extend_fixed64s(self, out: &mut Vec<u64>) -> Result<(), ModelError> {
+        match self {
+            Self::Bytes(bytes) => {
+                let chunks = bytes.chunks_exact(8);
+                if !chunks.remainder().is_empty() {
+                    return Err(ModelError::InvalidSettingsProto);
+                }
+                out.extend(chunks.map(|c| u64::from_le_bytes(c.try_into().expect("8 bytes"))));
+                Ok(())
+            },
+            other => {
+                out.push(other.as_u64()?);
+                Ok(())
+            },
+        }
+    }
+}
+
+fn read_varint(buf: &mut &[u8]) -> Result<u64, ModelError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf.split_first().ok_or(ModelError::InvalidSettingsProto)?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ModelError::InvalidSettingsProto)
+}
+
+fn read_bytes<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], ModelError> {
+    if buf.len() < len {
+        return Err(ModelError::InvalidSettingsProto);
+    }
+    let (head, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(head)
+}
+
+/// Iterates over the fields of an encoded message.
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+        }
+    }
+
+    /// Reads the next field number and value, or returns [`None`] at the end of the message.
+    pub fn next_field(&mut self) -> Result<Option<(u32, Value<'a>)>, ModelError> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+
+        let key = read_varint(&mut self.buf)?;
+        let field = u32::try_from(key >> 3).map_err(|_| ModelError::InvalidSettingsProto)?;
+        let value = match (key & 0x7) as u8 {
+            VARINT => Value::Varint(read_varint(&mut self.buf)?),
+            FIXED64 => {
+                let bytes = read_bytes(&mut self.buf, 8)?;
+                Value::Fixed64(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+            },
+            LEN => {
+                let len = read_varint(&mut self.buf)?;
+                let len = usize::try_from(len).map_err(|_| ModelError::InvalidSettingsProto)?;
+                Value::Bytes(read_bytes(&mut self.buf, len)?)
+            },
+            FIXED32 => {
+                let bytes = read_bytes(&mut self.buf, 4)?;
+                Value::Fixed32(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+            },
+            _ => return Err(ModelError::InvalidSettingsProto),
+        };
+
+        Ok(Some((field, value)))
+    }
+}
+
+/// Builds an encoded message field by field.
+#[derive(Default)]
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn write_key(&mut self, field: u32, wire_type: u8) {
+        self.write_varint(u64::from(field) << 3 | u64::from(wire_type));
+    }
+
+    pub fn varint(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.write_key(field, VARINT);
+            self.write_varint(value);
+        }
+    }
+
+    /// Negative values are sign-extended to ten bytes, as the protobuf spec requires for `int32`.
+    pub fn int32(&mut self, field: u32, value: i32) {
+        self.varint(field, i64::from(value) as u64);
+    }
+
+    pub fn bool(&mut self, field: u32, value: bool) {
+        self.varint(field, value.into());
+    }
+
+    pub fn fixed64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.write_key(field, FIXED64);
+            self.buf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    pub fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.write_key(field, LEN);
+        self.write_varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    pub fn string(&mut self, field: u32, value: &str) {
+        if !value.is_empty() {
+            self.bytes(field, value.as_bytes());
+        }
+    }
+
+    /// Writes a nested message, even if it ends up empty.
+    pub fn message(&mut self, field: u32, f: impl FnOnce(&mut Writer)) {
+        let mut inner = Writer::default();
+        f(&mut inner);
+        self.bytes(field, &inner.buf);
+    }
+
+    pub fn packed_varints(&mut self, field: u32, values: impl IntoIterator<Item = u64>) {
+        let mut inner = Writer::default();
+        values.into_iter().for_each(|v| inner.write_varint(v));
+        if !inner.buf.is_empty() {
+            self.bytes(field, &inner.buf);
+        }
+    }
+
+    pub fn packed_fixed64s(&mut self, field: u32, values: impl IntoIterator<Item = u64>) {
+        let buf: Vec<u8> = values.into_iter().flat_map(u64::to_le_bytes).collect();
+        if !buf.is_empty() {
+            self.bytes(field, &buf);
+        }
+    }
+}